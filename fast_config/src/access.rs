@@ -0,0 +1,95 @@
+//! Reading and writing single values of a config by their dotted key path, see [`FastConfig::get_path`].
+//!
+//! Paths are checked against the fields the derive describes in [`ConfigFields::FIELDS`], while the values go
+//! through the config's serde impls as a whole, so they are read and written the way a saved file has them.
+//! Generated per-field accessors would have to special-case `with` fields, generic fields
+//! and the keys `#[fast_config(unknown)]` keeps below other fields.
+
+use crate::ConfigFields;
+use crate::Error;
+use crate::FastConfig;
+use crate::FieldDescriptor;
+use crate::file;
+use crate::unknown;
+use crate::value;
+use crate::value::Segment;
+use crate::value::Value;
+
+/// The value at `path`, which must lead to a field of the config or into one of its values.
+pub(crate) fn get<T: FastConfig + ConfigFields>(config: &T, path: &str) -> Result<Value, Error> {
+    let known = resolve(T::FIELDS, path)?;
+    let document = file::to_value(config).map_err(std::io::Error::other)?;
+    match document.get_path(path) {
+        Some(value) => Ok(value.clone()),
+        // Fields such as `Option`s may be left out of the document while unset
+        None if known => Ok(Value::Null),
+        None => Err(unknown_path(path)),
+    }
+}
+
+/// Replaces the value at `path`, leaving the config untouched when the result doesn't fit its type.
+pub(crate) fn set<T: FastConfig + ConfigFields>(config: &mut T, path: &str, value: Value) -> Result<(), Error> {
+    let known = resolve(T::FIELDS, path)?;
+    let mut document = file::to_value(config).map_err(std::io::Error::other)?;
+    let segments = document.set_path_matching(path, value, false).map_err(|_| unknown_path(path))?;
+    // The value is read as text, the way `--set` overrides are
+    let coercion = value::Coercion { paths: vec![segments], numbers: false };
+    let (updated, unread) = value::from_value_tracking::<T>(document, coercion);
+    let mut updated = updated.map_err(|e| invalid(path, e))?;
+    if !known && !written(&updated, path) {
+        return Err(unknown_path(path));
+    }
+    unknown::keep(&mut updated, &unread);
+    *config = updated;
+    Ok(())
+}
+
+/// Whether the path ends on a field the derive describes, failing when it names a field that isn't there. <br/>
+/// Paths going on into lists, maps and other values are only checked against the config's contents, see [`written`].
+pub(crate) fn resolve(fields: &'static [FieldDescriptor], path: &str) -> Result<bool, Error> {
+    let mut segments = value::parse_path(path).map_err(|_| unknown_path(path))?.into_iter();
+    let mut fields = fields;
+    while let Some(segment) = segments.next() {
+        let Segment::Key(key) = segment else {
+            return Ok(false);
+        };
+        let field = fields.iter().find(|field| field.key == key).ok_or_else(|| unknown_path(path))?;
+        match segments.len() == 0 {
+            true => return Ok(true),
+            false if field.children.is_empty() || container(field.type_name) => return Ok(false),
+            false => fields = field.children,
+        }
+    }
+    Ok(false)
+}
+
+/// Whether the path leads to a value once the config is written. <br/>
+/// Keys inside values the derive can't see into, such as plain serde structs, are only known this way.
+pub(crate) fn written<T: FastConfig>(config: &T, path: &str) -> bool {
+    file::to_value(config).is_ok_and(|document| document.get_path(path).is_some())
+}
+
+/// Whether a type, once out of any `Option`s, is one of the lists or maps whose items `nested` fields reach into.
+fn container(type_name: &str) -> bool {
+    let mut type_name = type_name;
+    while let Some(inner) = type_name.split_once('<').filter(|(outer, _)| outer.ends_with("Option")) {
+        type_name = inner.1.strip_suffix('>').unwrap_or(inner.1);
+    }
+    let outer = type_name.split('<').next().unwrap_or_default();
+    let outer = outer.rsplit("::").next().unwrap_or_default();
+    type_name.starts_with('[') || ["Vec", "VecDeque", "HashSet", "BTreeSet", "HashMap", "BTreeMap"].contains(&outer)
+}
+
+pub(crate) fn unknown_path(path: &str) -> Error {
+    Error::UnknownPath { key: path.to_owned() }
+}
+
+fn invalid(path: &str, e: value::Error) -> Error {
+    Error::Deserialize {
+        key: match e.path.is_empty() {
+            true => path.to_owned(),
+            false => e.path,
+        },
+        message: e.message,
+    }
+}
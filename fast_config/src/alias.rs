@@ -0,0 +1,37 @@
+use crate::value::Value;
+
+/// An old key still accepted in place of a renamed or moved one, set with `#[fast_config(deprecated_alias = "...")]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alias {
+    /// The dotted key path the value used to live at, such as `log_level`.
+    pub from: String,
+    /// The dotted key path it lives at now, such as `logging.level`.
+    pub to: String,
+    /// The version the old key was deprecated in, shown in the warning.
+    pub since: Option<String>,
+}
+
+impl Alias {
+    /// The deprecation warning for a file still using the old key.
+    pub(crate) fn warning(&self) -> String {
+        let since = self.since.as_ref().map(|since| format!(" since {since}")).unwrap_or_default();
+        format!("deprecated{since}, use `{}` instead", self.to)
+    }
+}
+
+/// Moves the values found at old keys to their new place, returning the aliases that were used. <br/>
+/// When both keys are present the new one wins.
+pub(crate) fn apply(document: &mut Value, aliases: Vec<Alias>) -> Vec<Alias> {
+    aliases
+        .into_iter()
+        .filter(|alias| {
+            let Some(moved) = document.remove_path(&alias.from) else {
+                return false;
+            };
+            if document.get_path(&alias.to).is_none() {
+                let _ = document.set_path_matching(&alias.to, moved, false);
+            }
+            true
+        })
+        .collect()
+}
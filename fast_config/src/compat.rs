@@ -0,0 +1,221 @@
+//! Which values each format can hold, so that writing a document never drops or changes one silently.
+
+use crate::Error;
+use crate::FastConfig;
+use crate::Format;
+use crate::file;
+use crate::recover::Problem;
+use crate::value;
+use crate::value::Segment;
+use crate::value::Value;
+use crate::value::render_path;
+
+/// How numbers a format has no exact form for are written, see [`FastConfig::numbers`]:
+/// integers past `i64` in TOML, and NaN, infinity and integers past 64 bits in JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberPolicy {
+    /// Writes them the way the format's serializer always has, past the specification. This crate reads TOML's
+    /// large integers back, but other TOML parsers reject them. <br/>
+    /// JSON writes NaN and infinity as `null` and integers past 64 bits as they are, which don't read back the same.
+    Native,
+    /// Fails with [`Error::Unrepresentable`] on every one of them, keeping files within the specification.
+    #[default]
+    Error,
+    /// Writes them as strings, such as `"18446744073709551616"` or `"NaN"`, which number fields read back.
+    String,
+}
+
+/// Checks that `format` can write every value of the document as it is, failing with [`Error::Unrepresentable`]
+/// listing each one it can't, such as nulls in TOML or NaN in JSON.
+///
+/// ```rust
+/// let document = Value::load("config.json", Format::JSON)?;
+/// fast_config::check_format(&document, Format::TOML)?;
+/// ```
+pub fn check_format(value: &Value, format: Format) -> Result<(), Error> {
+    match lossy(value, format, false) {
+        found if found.is_empty() => Ok(()),
+        found => Err(unrepresentable(format, found)),
+    }
+}
+
+/// The key path and reason of every value `format` can't write as it is. <br/>
+/// JSON has no NaN or infinity and reads integers past 64 bits back as floats,
+/// while TOML has no null, needs a table at the top and only has 64-bit signed integers. <br/>
+/// With `omit_nulls`, keys that are null may be left out, as config fields read missing keys as `None`.
+pub(crate) fn lossy(value: &Value, format: Format, omit_nulls: bool) -> Vec<(String, String)> {
+    problems(value, format, omit_nulls, true)
+}
+
+/// Like [`lossy`], leaving out the numbers unless `numbers` is set.
+fn problems(value: &Value, format: Format, omit_nulls: bool, numbers: bool) -> Vec<(String, String)> {
+    let mut found = Vec::new();
+    #[cfg(feature = "toml")]
+    if format == Format::TOML && !matches!(value, Value::Table(_)) {
+        found.push((String::new(), format!("a TOML document must be a table, not {}", value.kind())));
+    }
+    walk(value, format, numbers, &mut Vec::new(), &mut found);
+    if omit_nulls {
+        let in_table = |path: &str| !path.is_empty() && !path.ends_with(']');
+        found.retain(|(path, _)| !in_table(path) || value.get_path(path).is_none_or(|value| !value.is_null()));
+    }
+    found
+}
+
+#[allow(unused_variables)]
+fn walk(value: &Value, format: Format, numbers: bool, path: &mut Vec<Segment>, found: &mut Vec<(String, String)>) {
+    let problem = match (format, value) {
+        #[cfg(feature = "toml")]
+        (Format::TOML, Value::Null) => Some("TOML has no null".to_owned()),
+        _ if numbers => number(value, format),
+        _ => None,
+    };
+    if let Some(problem) = problem {
+        found.push((render_path(path), problem));
+    }
+    match value {
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                path.push(Segment::Index(index));
+                walk(item, format, numbers, path, found);
+                path.pop();
+            }
+        }
+        Value::Table(map) => {
+            for (key, value) in map.iter() {
+                path.push(Segment::Key(key.clone()));
+                walk(value, format, numbers, path, found);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Why the specification of `format` has no exact form for the number, if it doesn't.
+#[allow(unused_variables)]
+fn number(value: &Value, format: Format) -> Option<String> {
+    match (format, value) {
+        #[cfg(feature = "json")]
+        (Format::JSON, Value::Float(float)) if !float.is_finite() => Some(format!("JSON has no {float} numbers")),
+        #[cfg(feature = "json")]
+        (Format::JSON, Value::Integer(integer)) if !(i64::MIN.into()..=u64::MAX.into()).contains(integer) => {
+            Some(format!("{integer} is too large for a JSON number"))
+        }
+        #[cfg(feature = "toml")]
+        (Format::TOML, Value::Integer(integer)) if i64::try_from(*integer).is_err() => {
+            Some(format!("{integer} is too large for a TOML integer"))
+        }
+        _ => None,
+    }
+}
+
+/// Applies the number policy to a document about to be written in `format`, failing with [`Error::Unrepresentable`]
+/// on the numbers it doesn't let through and on everything else [`check_format`] reports, such as nulls in TOML lists.
+/// Null keys are left out, as config fields read missing keys as `None`.
+pub(crate) fn numbers(document: &mut Value, format: Format, policy: NumberPolicy) -> Result<(), Error> {
+    if policy == NumberPolicy::String {
+        encode(document, format);
+    }
+    let found = problems(document, format, true, policy == NumberPolicy::Error);
+    match found.is_empty() {
+        true => Ok(()),
+        false => Err(unrepresentable(format, found)),
+    }
+}
+
+/// Writes the numbers `format` has no exact form for as strings.
+fn encode(value: &mut Value, format: Format) {
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(|item| encode(item, format)),
+        Value::Table(map) => map.iter_mut().for_each(|(_, value)| encode(value, format)),
+        _ if number(value, format).is_some() => {
+            let text = match &*value {
+                Value::Float(float) if float.is_nan() => "NaN".to_owned(),
+                Value::Float(float) if float.is_infinite() && *float > 0.0 => "Infinity".to_owned(),
+                Value::Float(float) if float.is_infinite() => "-Infinity".to_owned(),
+                other => value::to_inline(other),
+            };
+            *value = Value::String(text);
+        }
+        _ => {}
+    }
+}
+
+/// Reads back what `save` is about to write, see [`FastConfig::verify_on_save`]. <br/>
+/// `written` is the outcome of serializing the config, which fails outright for some values the format can't hold.
+pub(crate) fn verify<T: FastConfig>(
+    config: &T,
+    written: Result<String, Error>,
+    format: Format,
+    profile: Option<&str>,
+) -> Result<String, Error> {
+    let document = file::to_value(config).map_err(std::io::Error::other)?;
+    // What is actually written, with the numbers the policy turns into strings
+    let mut written_document = document.clone();
+    numbers(&mut written_document, format, T::numbers())?;
+    let read_back = written.and_then(|content| {
+        let read: T = file::from_document(Value::from_string(&content, format)?, profile)?;
+        Ok((content, file::to_value(&read).map_err(std::io::Error::other)?))
+    });
+    // Values the format is known not to hold are reported even when this crate happens to read them back,
+    // and explain a failure better than the format does
+    let mut found = lossy(&written_document, format, true);
+    let content = match read_back {
+        Ok((content, read)) => {
+            let mut changed = Vec::new();
+            differences(&document, &read, &mut Vec::new(), &mut changed);
+            for (path, message) in changed {
+                if !found.iter().any(|(known, _)| *known == path) {
+                    found.push((path, message));
+                }
+            }
+            content
+        }
+        Err(e) if found.is_empty() => return Err(e),
+        Err(_) => String::new(),
+    };
+    match found.is_empty() {
+        true => Ok(content),
+        false => Err(unrepresentable(format, found)),
+    }
+}
+
+/// Where `read` differs from the `written` document, with how. Keys that were null may be missing.
+fn differences(written: &Value, read: &Value, path: &mut Vec<Segment>, found: &mut Vec<(String, String)>) {
+    match (written, read) {
+        (Value::Table(written), Value::Table(read)) => {
+            for (key, value) in written.iter() {
+                path.push(Segment::Key(key.clone()));
+                match read.get(key) {
+                    Some(read) => differences(value, read, path, found),
+                    None if value.is_null() => {}
+                    None => found.push((render_path(path), "is left out".to_owned())),
+                }
+                path.pop();
+            }
+            for (key, _) in read.iter().filter(|(key, _)| !written.contains_key(key)) {
+                path.push(Segment::Key(key.clone()));
+                found.push((render_path(path), "appears when read back".to_owned()));
+                path.pop();
+            }
+        }
+        (Value::Array(written), Value::Array(read)) if written.len() == read.len() => {
+            for (index, (written, read)) in written.iter().zip(read).enumerate() {
+                path.push(Segment::Index(index));
+                differences(written, read, path, found);
+                path.pop();
+            }
+        }
+        (Value::Float(written), Value::Float(read)) if written.is_nan() && read.is_nan() => {}
+        (written, read) if written != read => {
+            found.push((render_path(path), format!("is read back as {}", value::to_inline(read))));
+        }
+        _ => {}
+    }
+}
+
+fn unrepresentable(format: Format, found: Vec<(String, String)>) -> Error {
+    let problems = found.into_iter().map(|(path, message)| Problem { path, message, location: None });
+    Error::Unrepresentable { format, problems: problems.collect() }
+}
@@ -0,0 +1,187 @@
+//! Converting a config file to another format without knowing its type, see [`convert`].
+
+use crate::Error;
+use crate::Format;
+use crate::compat;
+use crate::file;
+use crate::location::Locations;
+use crate::recover::Problem;
+use crate::value::Value;
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Converts a config file to another format, going through a [`Value`] so that no config type is needed. <br/>
+/// Fails with [`Error::Unrepresentable`] before writing anything when the file holds values the target format
+/// can't, such as nulls in TOML or YAML keys that aren't strings, listing every one of them. <br/>
+/// Comments on their own lines above keys are carried over when both formats have comments, which JSON doesn't.
+///
+/// ```rust
+/// fast_config::convert("config.json", Format::JSON, "config.toml", Format::TOML)?;
+/// ```
+pub fn convert(
+    src_path: impl AsRef<Path>,
+    src_format: Format,
+    dst_path: impl AsRef<Path>,
+    dst_format: Format,
+) -> Result<(), Error> {
+    let (src, dst) = (src_path.as_ref(), dst_path.as_ref());
+    let content = std::fs::read_to_string(src).map_err(|e| file::in_untyped_file(e.into(), src, None, None))?;
+    let document =
+        Value::from_string(&content, src_format).map_err(|e| file::in_untyped_file(e, src, Some(&content), None))?;
+    let locations = Locations::new(&content, src_format);
+
+    let lossy = compat::lossy(&document, dst_format, false).into_iter();
+    #[cfg(feature = "yaml")]
+    let lossy = lossy.chain(match src_format == Format::YAML && dst_format != Format::YAML {
+        true => yaml_keys(&content),
+        false => Vec::new(),
+    });
+    let problems = lossy
+        .map(|(path, message)| Problem { location: locations.closest(&path), path, message })
+        .collect::<Vec<_>>();
+    if !problems.is_empty() {
+        let first = problems.first().cloned();
+        let error = Error::Unrepresentable { format: dst_format, problems };
+        return Err(file::in_untyped_file(error, src, Some(&content), first));
+    }
+
+    let write = || {
+        let mut out = document.to_string_pretty(dst_format)?;
+        if let (Some(from), Some(to)) = (marker(src_format), marker(dst_format)) {
+            out = carry_comments(&content, &locations, from, &out, dst_format, to);
+        }
+        if let Some(parent_dir) = dst.parent() {
+            std::fs::create_dir_all(parent_dir)?;
+        }
+        std::fs::write(dst, out)?;
+        Ok(())
+    };
+    write().map_err(|e| file::in_untyped_file(e, dst, None, None))
+}
+
+/// What line comments start with in the format, if it has comments.
+fn marker(format: Format) -> Option<&'static str> {
+    match format {
+        #[cfg(feature = "json")]
+        Format::JSON => None,
+        #[cfg(feature = "json5")]
+        Format::JSON5 => Some("//"),
+        #[cfg(feature = "toml")]
+        Format::TOML => Some("#"),
+        #[cfg(feature = "yaml")]
+        Format::YAML => Some("#"),
+    }
+}
+
+/// The text of a line holding nothing but a comment, such as `# text` or `/* text */`.
+fn comment<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    let line = line.trim();
+    let text = match line.strip_prefix(marker) {
+        Some(text) => text,
+        // JSON5 block comments are only understood when they fit on their line
+        None if marker == "//" => line.strip_prefix("/*")?.strip_suffix("*/")?.trim_end(),
+        None => return None,
+    };
+    Some(text.strip_prefix(' ').unwrap_or(text))
+}
+
+/// Writes the comments of `content` into `out`: the block the file starts with, when a blank line sets it apart,
+/// and the lines right above each key, above the same key in `out`.
+fn carry_comments(
+    content: &str,
+    locations: &Locations,
+    from: &str,
+    out: &str,
+    format: Format,
+    to: &str,
+) -> String {
+    let lines = content.lines().collect::<Vec<_>>();
+    let above = |line: usize| {
+        let start = lines[..line - 1].iter().rposition(|line| comment(line, from).is_none()).map_or(0, |i| i + 1);
+        lines[start..line - 1].iter().filter_map(|line| comment(line, from)).collect::<Vec<_>>()
+    };
+    let header = lines.iter().take_while(|line| comment(line, from).is_some()).count();
+    let header = match lines.get(header).is_some_and(|line| line.trim().is_empty()) {
+        true => lines[..header].iter().filter_map(|line| comment(line, from)).collect(),
+        false => Vec::new(),
+    };
+
+    // Keys sharing a line, such as a YAML list item and its first key, give their comments to the outermost one
+    let mut keys: BTreeMap<usize, &str> = BTreeMap::new();
+    for (path, location) in locations.iter().filter(|(path, _)| !path.is_empty()) {
+        let key = keys.entry(location.line).or_insert(path);
+        if path.len() < key.len() {
+            *key = path;
+        }
+    }
+    let written = Locations::new(out, format);
+    let mut inserted: BTreeMap<usize, Vec<&str>> = BTreeMap::new();
+    for (line, path) in keys {
+        let comments = above(line);
+        if let (false, Some(location)) = (comments.is_empty(), written.get(path)) {
+            inserted.entry(location.line).or_default().extend(comments);
+        }
+    }
+
+    let mut result = String::new();
+    let write = |result: &mut String, indent: &str, text: &str| match text.is_empty() {
+        true => result.push_str(&format!("{indent}{to}\n")),
+        false => result.push_str(&format!("{indent}{to} {text}\n")),
+    };
+    for text in &header {
+        write(&mut result, "", text);
+    }
+    if !header.is_empty() {
+        result.push('\n');
+    }
+    for (index, line) in out.lines().enumerate() {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        for text in inserted.get(&(index + 1)).into_iter().flatten() {
+            write(&mut result, indent, text);
+        }
+        result.push_str(line);
+        result.push('\n');
+    }
+    result
+}
+
+/// YAML keys that aren't strings, such as `1:` or `true:`, which the other formats would turn into strings.
+#[cfg(feature = "yaml")]
+fn yaml_keys(content: &str) -> Vec<(String, String)> {
+    use serde_yml::Value as Yaml;
+
+    fn walk(value: &Yaml, path: &str, found: &mut Vec<(String, String)>) {
+        match value {
+            Yaml::Mapping(mapping) => {
+                for (key, value) in mapping {
+                    let name = match key {
+                        Yaml::String(name) => name.clone(),
+                        other => serde_yml::to_string(other).unwrap_or_default().trim_end().to_owned(),
+                    };
+                    let child = match path.is_empty() {
+                        true => name,
+                        false => format!("{path}.{name}"),
+                    };
+                    if !key.is_string() {
+                        found.push((child.clone(), "keys that aren't strings can only be written in YAML".to_owned()));
+                    }
+                    walk(value, &child, found);
+                }
+            }
+            Yaml::Sequence(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    walk(item, &format!("{path}[{index}]"), found);
+                }
+            }
+            Yaml::Tagged(tagged) => walk(&tagged.value, path, found),
+            _ => {}
+        }
+    }
+
+    let mut found = Vec::new();
+    if let Ok(document) = serde_yml::from_str::<Yaml>(content) {
+        walk(&document, "", &mut found);
+    }
+    found
+}
@@ -0,0 +1,91 @@
+use crate::Error;
+use crate::FastConfig;
+use crate::FieldDescriptor;
+use crate::value;
+use crate::value::Value;
+
+/// An environment variable bound to a key path with `#[fast_config(env = "...")]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvVar {
+    /// The full name of the variable, without any prefix applied.
+    pub name: String,
+    /// The dotted key path the variable overrides, such as `server.port`.
+    pub path: String,
+}
+
+/// Overrides the values of `config` with the environment variables it opted into. <br/>
+/// `PREFIX_SERVER__PORT` maps to `server.port`, while pinned variables win over prefixed ones. <br/>
+/// Each name between `__` is matched against the keys of `fields` ignoring case and underscores,
+/// so `PREFIX_MAX_CONN` maps to a `maxConn` key, and prefixed variables that match no field are skipped.
+pub fn apply<T: FastConfig>(config: &mut T, fields: &'static [FieldDescriptor]) -> Result<(), Error> {
+    let prefix = T::env_prefix();
+    let pinned = T::env_vars();
+    if prefix.is_none() && pinned.is_empty() {
+        return Ok(());
+    }
+
+    let mut overrides = Vec::new();
+    if let Some(prefix) = prefix {
+        let prefix = format!("{prefix}_");
+        let mut prefixed = std::env::vars_os()
+            .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+            .filter(|(name, _)| !pinned.iter().any(|var| &var.name == name))
+            .filter_map(|(name, value)| {
+                let path = key_path(fields, name.strip_prefix(&prefix)?)?;
+                Some((EnvVar { name, path }, value))
+            })
+            .collect::<Vec<_>>();
+        prefixed.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
+        overrides.extend(prefixed);
+    }
+    for var in pinned {
+        if let Some(value) = std::env::var_os(&var.name).and_then(|v| v.into_string().ok()) {
+            overrides.push((var, value));
+        }
+    }
+    let patches = overrides
+        .iter()
+        .map(|(var, value)| (var.path.clone(), Value::String(value.clone())))
+        .collect::<Vec<_>>();
+    value::patch(config, &patches, true).map_err(|(i, e)| Error::Env {
+        name: overrides[i].0.name.clone(),
+        message: e.to_string(),
+    })
+}
+
+/// The key path a prefixed variable's name, such as `SERVER__MAX_CONN`, maps to, if it leads to a field. <br/>
+/// Names past the described fields, going into lists, maps and plain structs, are lowercased. Without any fields,
+/// as for types implementing `FastConfig` by hand, so is every name.
+fn key_path(fields: &'static [FieldDescriptor], name: &str) -> Option<String> {
+    let normalize = |key: &str| key.replace('_', "").to_lowercase();
+    let mut fields = Some(fields).filter(|fields| !fields.is_empty());
+    let mut path = Vec::new();
+    for segment in name.split("__") {
+        let Some(described) = fields else {
+            path.push(segment.to_lowercase());
+            continue;
+        };
+        let field = described.iter().find(|field| normalize(field.key) == normalize(segment))?;
+        path.push(field.key.to_owned());
+        fields = Some(field.children).filter(|children| !children.is_empty() && !field.repeated);
+    }
+    Some(path.join("."))
+}
+
+/// The prefixed variable name for a key path, such as `PREFIX_SERVER__MAX_CONN` for `server.maxConn`.
+pub(crate) fn var_name(prefix: &str, path: &str) -> String {
+    let name = path.split('.').map(|key| {
+        let mut name = String::new();
+        let mut previous = None::<char>;
+        for c in key.chars() {
+            let after_word = previous.is_some_and(|previous| previous.is_lowercase() || previous.is_ascii_digit());
+            if c.is_uppercase() && after_word {
+                name.push('_');
+            }
+            name.extend(c.to_uppercase());
+            previous = Some(c);
+        }
+        name.replace('-', "_")
+    });
+    format!("{prefix}_{}", name.collect::<Vec<_>>().join("__"))
+}
@@ -0,0 +1,73 @@
+/// A static description of the fields of a config struct, implemented by the derive alongside
+/// [`FastConfig`](crate::FastConfig) for building settings screens, documentation and checks that work with any config.
+///
+/// ```rust
+/// fn walk(fields: &[FieldDescriptor], depth: usize) {
+///     for field in fields {
+///         println!("{}{}: {}", "  ".repeat(depth), field.key, field.type_name);
+///         walk(field.children, depth + 1);
+///     }
+/// }
+/// walk(MyConfig::FIELDS, 0);
+/// ```
+pub trait ConfigFields {
+    /// Every field serde reads, in the order they are declared.
+    const FIELDS: &'static [FieldDescriptor];
+
+    /// The field at a dotted key path such as `server.port`, going through `nested` fields.
+    fn field(path: &str) -> Option<&'static FieldDescriptor> {
+        let mut fields = Self::FIELDS;
+        let mut found = None;
+        for key in path.split('.') {
+            let field = fields.iter().find(|field| field.key == key)?;
+            fields = field.children;
+            found = Some(field);
+        }
+        found
+    }
+}
+
+/// A field of a [`ConfigFields`] struct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldDescriptor {
+    /// The name of the field in Rust.
+    pub name: &'static str,
+    /// The key the field is written under, after serde renames.
+    pub key: &'static str,
+    /// The field's Rust type as written in the struct, such as `Option<Vec<String>>`.
+    pub type_name: &'static str,
+    /// The field's doc comment, without the leading `///`.
+    pub doc: Option<&'static str>,
+    /// Whether the field is an `Option`, which files may leave out.
+    pub optional: bool,
+    /// Whether the field is a list or a map, including one inside an `Option`, whose `children` describe each item.
+    pub repeated: bool,
+    /// The fields of a `nested` struct, including one inside an `Option`, a list or a map.
+    pub children: &'static [FieldDescriptor],
+    pub attributes: FieldAttributes,
+}
+
+/// Every field along with its dotted key path such as `server.port`, in the order they are declared. <br/>
+/// Goes into plain `nested` structs, but not those inside an `Option`, a list or a map, whose keys may be missing.
+pub(crate) fn flatten(fields: &'static [FieldDescriptor]) -> Vec<(String, &'static FieldDescriptor)> {
+    let mut flat = Vec::new();
+    for field in fields {
+        flat.push((field.key.to_owned(), field));
+        if !field.optional && !field.repeated {
+            let children = flatten(field.children).into_iter();
+            flat.extend(children.map(|(path, child)| (format!("{}.{path}", field.key), child)));
+        }
+    }
+    flat
+}
+
+/// The `#[fast_config(...)]` options of a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldAttributes {
+    pub env: Option<&'static str>,
+    pub nested: bool,
+    pub deprecated_alias: Option<&'static str>,
+    pub since: Option<&'static str>,
+    /// The validation rules as written, such as `range(min = 1, max = 65535)`.
+    pub rules: &'static [&'static str],
+}
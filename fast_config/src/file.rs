@@ -0,0 +1,532 @@
+use crate::ConfigFields;
+use crate::Error;
+use crate::alias;
+use crate::alias::Alias;
+use crate::compat;
+use crate::compat::NumberPolicy;
+use crate::FastConfig;
+use crate::FileError;
+use crate::Format;
+use crate::format_dependant;
+use crate::includes;
+use crate::includes::Sources;
+use crate::interpolate;
+use crate::location;
+use crate::location::Locations;
+use crate::migrate;
+use crate::recover;
+use crate::recover::Problem;
+use crate::recover::Recovered;
+use crate::render;
+use crate::unknown;
+use crate::unknown::Loaded;
+use crate::unknown::UnknownKeys;
+use crate::value;
+use crate::value::Value;
+
+use std::path::Path;
+
+/// Reads a config file through its includes and the given profile, then applies environment overrides and validates it.
+pub(crate) fn read<T: FastConfig>(path: &Path, format: Format, profile: Option<&str>) -> Result<T, Error> {
+    let content = content::<T>(path, profile)?;
+    let mut sources = None;
+    let mut read = || {
+        let rewrites = T::versioning().is_some_and(|versioning| versioning.rewrite);
+        let mut upgraded = None;
+        let mut config: T = match (T::includes(), profile) {
+            (None, None) if !rewrites => parse(&content, format)?,
+            (_, profile) => {
+                let mut document = document::<T>(path, &content, format, &mut sources)?;
+                upgraded = upgrade::<T>(&mut document)?;
+                from_document(document, profile)?
+            }
+        };
+        config.apply_env()?;
+        config.check()?;
+        rewrite(path, &content, format, upgraded)?;
+        Ok(config)
+    };
+    read().map_err(|e| in_source::<T>(e, path, (&content, format), sources.as_ref(), profile))
+}
+
+/// Parses the file's `content`, merging the files it includes on top when the type has includes,
+/// and keeping track of where each came from in `sources`.
+fn document<T: FastConfig>(
+    path: &Path,
+    content: &str,
+    format: Format,
+    sources: &mut Option<Sources>,
+) -> Result<Value, Error> {
+    match T::includes() {
+        Some(includes) => {
+            let (document, read) = includes::load(path, content, format, &includes)?;
+            *sources = Some(read);
+            Ok(document)
+        }
+        None => format_dependant::from_str(content, format),
+    }
+}
+
+/// Reads the file as it is on disk, which is only upgraded in place once it loaded fine.
+fn content<T: FastConfig>(path: &Path, profile: Option<&str>) -> Result<String, Error> {
+    std::fs::read_to_string(path).map_err(|e| in_file::<T>(e.into(), path, None, profile))
+}
+
+/// A file's document migrated from an older version, for [`rewrite`] to write back once the config loaded.
+struct Upgraded {
+    /// The version the file was at.
+    from: u32,
+    document: Value,
+}
+
+/// Migrates the file's document ahead of [`prepare`] when the type asks for migrated files to be rewritten,
+/// keeping a copy to write back if it was older. `prepare` then finds it at the current version. <br/>
+/// Files including others aren't rewritten, since their included files would keep the older keys.
+fn upgrade<T: FastConfig>(document: &mut Value) -> Result<Option<Upgraded>, Error> {
+    let Some(versioning) = T::versioning().filter(|versioning| versioning.rewrite && T::includes().is_none()) else {
+        return Ok(None);
+    };
+    // Migrations see the document the way `prepare` hands it to them, and the file keeps its schema reference
+    #[cfg(feature = "schema")]
+    let schema = document.as_table_mut().and_then(|map| map.remove("$schema"));
+    let from = migrate::migrate(document, &versioning)?;
+    let upgraded = (from < versioning.current).then(|| Upgraded { from, document: document.clone() });
+    #[cfg(feature = "schema")]
+    let upgraded = upgraded.map(|mut upgraded| {
+        if let (Some(schema), Value::Table(map)) = (schema, &mut upgraded.document) {
+            *map = std::iter::once(("$schema".to_owned(), schema)).chain(std::mem::take(map)).collect();
+        }
+        upgraded
+    });
+    Ok(upgraded)
+}
+
+/// Upgrades the file in place once it was read and validated, see [`upgrade`].
+fn rewrite(path: &Path, content: &str, format: Format, upgraded: Option<Upgraded>) -> Result<(), Error> {
+    match upgraded {
+        Some(upgraded) => migrate::rewrite(path, content, format, upgraded.from, &upgraded.document),
+        None => Ok(()),
+    }
+}
+
+/// Wraps an error in [`Error::File`], working out the key path and location it points at when the file's content is known.
+fn in_file<T: FastConfig>(error: Error, path: &Path, content: Option<(&str, Format)>, profile: Option<&str>) -> Error {
+    if let Error::File(_) = error {
+        return error;
+    }
+    let Some((content, format)) = content else {
+        return Error::File(Box::new(FileError {
+            path: path.to_owned(),
+            key: None,
+            location: None,
+            snippet: None,
+            help: None,
+            source: error,
+        }));
+    };
+    let prepared = format_dependant::from_str::<Value>(content, format)
+        .ok()
+        .and_then(|document| prepare::<T>(document, profile).ok());
+    let document = prepared.as_ref().map(|prepared| prepared.document.clone());
+    // Errors straight from the format crates don't know the key path, which reading the document again finds
+    let (key, message) = match error.key() {
+        Some(key) => (Some(key.to_owned()), error.to_string()),
+        None => match prepared.map(|prepared| value::from_value_coercing::<T>(prepared.document, prepared.coercion)) {
+            Some(Err(e)) => (Some(e.path), e.message),
+            _ => (None, error.to_string()),
+        },
+    };
+    let (help, misspelled) = render::help(&message, key.as_deref(), document.as_ref()).unzip();
+    // Pointing at the key gives the same answer in every format, unlike where each parser gave up.
+    // Missing keys point at the misspelled key found in their place, if any
+    let location = misspelled
+        .flatten()
+        .or_else(|| key.clone())
+        .and_then(|key| recover::locate(&Locations::new(content, format), &prefixes::<T>(profile), &key))
+        .or_else(|| location::of_format_error(&error, Some(content)));
+    Error::File(Box::new(FileError {
+        path: path.to_owned(),
+        snippet: location.and_then(|l| content.lines().nth(l.line - 1)).map(str::to_owned),
+        help,
+        key: key.filter(|key| !key.is_empty()),
+        location,
+        source: error,
+    }))
+}
+
+/// Like [`in_file`], but pointing into the included file the offending value was merged from, if any.
+fn in_source<T: FastConfig>(
+    error: Error,
+    path: &Path,
+    (content, format): (&str, Format),
+    sources: Option<&Sources>,
+    profile: Option<&str>,
+) -> Error {
+    let source = error.key().zip(sources).and_then(|(key, sources)| sources.source(&prefixes::<T>(profile), key));
+    match source {
+        Some(source) => in_file::<T>(error, &source.path, Some((&source.content, source.format)), profile),
+        None => in_file::<T>(error, path, Some((content, format)), profile),
+    }
+}
+
+/// Where the keys of the config live in the file: under the profile's section, or else the default one.
+fn prefixes<T: FastConfig>(profile: Option<&str>) -> Vec<String> {
+    match profile {
+        Some(profile) => vec![profile.to_owned(), T::profiles().unwrap_or_default().default],
+        None => vec![String::new()],
+    }
+}
+
+/// Parses a single document in the active profile, without validating it.
+pub(crate) fn parse<T: FastConfig>(content: &str, format: Format) -> Result<T, Error> {
+    let profile = T::profiles().map(|profiles| profiles.active());
+    // Numbers written as strings are only turned back into numbers by the document
+    let plain = T::versioning().is_none() && T::aliases().is_empty() && T::numbers() != NumberPolicy::String;
+    if profile.is_none() && !T::interpolate() && plain {
+        let config: T = format_dependant::from_str(content, format)?;
+        // Only the document knows which keys went unread
+        if config.unknown_fields().is_none() {
+            return Ok(config);
+        }
+    }
+    from_document(format_dependant::from_str(content, format)?, profile.as_deref())
+}
+
+/// Turns an already parsed document into the config,
+/// selecting the profile and expanding `${...}` references first when enabled.
+pub(crate) fn from_document<T: FastConfig>(document: Value, profile: Option<&str>) -> Result<T, Error> {
+    let prepared = prepare::<T>(document, profile)?;
+    let (config, unknown) = value::from_value_tracking(prepared.document, prepared.coercion);
+    let mut config = config.map_err(|e| Error::Deserialize {
+        key: e.path,
+        message: e.message,
+    })?;
+    unknown::keep(&mut config, &unknown);
+    Ok(config)
+}
+
+/// A document ready to deserialize, see [`prepare`].
+struct Prepared {
+    document: Value,
+    /// The deprecated keys that were found.
+    renamed: Vec<Alias>,
+    /// The strings that are read as text: those `${...}` references were expanded in,
+    /// and the numbers written by `NumberPolicy::String`.
+    coercion: value::Coercion,
+}
+
+/// Migrates older versions, selects the profile, moves deprecated keys to their new place
+/// and expands `${...}` references when enabled.
+fn prepare<T: FastConfig>(mut document: Value, profile: Option<&str>) -> Result<Prepared, Error> {
+    #[cfg(feature = "schema")]
+    crate::schema::strip(&mut document);
+    if let Some(versioning) = T::versioning() {
+        migrate::migrate(&mut document, &versioning)?;
+        migrate::unstamp(&mut document, &versioning);
+    }
+    let mut document = match profile {
+        Some(profile) => T::profiles().unwrap_or_default().select(document, profile)?,
+        None => document,
+    };
+    let renamed = alias::apply(&mut document, T::aliases());
+    let mut coercion = value::Coercion {
+        paths: Vec::new(),
+        numbers: T::numbers() == NumberPolicy::String,
+    };
+    if T::interpolate() {
+        coercion.paths = interpolate::expand(&mut document).map_err(|e| Error::Interpolation {
+            key: e.path,
+            message: e.message,
+        })?;
+    }
+    Ok(Prepared { document, renamed, coercion })
+}
+
+/// Like [`read`], but collects every problem instead of stopping at the first one.
+pub(crate) fn read_recovering<T: FastConfig>(
+    path: &Path,
+    format: Format,
+    profile: Option<&str>,
+) -> Result<Recovered<T>, Error> {
+    let content = content::<T>(path, profile)?;
+    let mut sources = None;
+    let mut recover = || {
+        let mut document = document::<T>(path, &content, format, &mut sources)?;
+        let upgraded = upgrade::<T>(&mut document)?;
+        let recovered = recovering::<T>(document, &content, format, profile, true)?;
+        if recovered.value.is_some() && recovered.problems.is_empty() {
+            rewrite(path, &content, format, upgraded)?;
+        }
+        Ok(recovered)
+    };
+    recover().map_err(|e| in_source::<T>(e, path, (&content, format), sources.as_ref(), profile))
+}
+
+/// Like [`parse`], but collects every problem instead of stopping at the first one.
+pub(crate) fn parse_recovering<T: FastConfig>(content: &str, format: Format) -> Result<Recovered<T>, Error> {
+    let profile = T::profiles().map(|profiles| profiles.active());
+    let document = format_dependant::from_str(content, format)?;
+    recovering(document, content, format, profile.as_deref(), false)
+}
+
+fn recovering<T: FastConfig>(
+    document: Value,
+    content: &str,
+    format: Format,
+    profile: Option<&str>,
+    env: bool,
+) -> Result<Recovered<T>, Error> {
+    let prepared = prepare::<T>(document, profile)?;
+    let (mut config, errors) = recover::deserialize::<T>(prepared.document, prepared.coercion);
+    let locations = Locations::new(content, format);
+    let prefixes = prefixes::<T>(profile);
+    let mut problems = recover::problems(errors, Some(&locations), &prefixes);
+    if let Some(config) = config.as_mut() {
+        if env {
+            config.apply_env()?;
+        }
+        match config.check() {
+            Ok(()) => {}
+            Err(Error::Validation(violations)) => {
+                problems.extend(violations.into_iter().map(|violation| recover::Problem {
+                    location: recover::locate(&locations, &prefixes, &violation.path),
+                    path: violation.path,
+                    message: violation.message,
+                }));
+            }
+            Err(other) => return Err(other),
+        }
+    }
+    recover::sort(&mut problems);
+    Ok(Recovered { value: config, problems })
+}
+
+/// Like [`read`], but deals with keys no field asks for as `unknown_keys` says.
+pub(crate) fn read_checked<T: FastConfig>(
+    path: &Path,
+    format: Format,
+    profile: Option<&str>,
+    unknown_keys: UnknownKeys,
+) -> Result<Loaded<T>, Error> {
+    let content = content::<T>(path, profile)?;
+    let mut sources = None;
+    let mut read = || {
+        let mut document = document::<T>(path, &content, format, &mut sources)?;
+        let upgraded = upgrade::<T>(&mut document)?;
+        let loaded = checked(document, &content, format, profile, true, unknown_keys)?;
+        rewrite(path, &content, format, upgraded)?;
+        Ok(loaded)
+    };
+    read().map_err(|e| in_source::<T>(e, path, (&content, format), sources.as_ref(), profile))
+}
+
+/// Like [`parse`], but deals with keys no field asks for as `unknown_keys` says.
+pub(crate) fn parse_checked<T: FastConfig>(
+    content: &str,
+    format: Format,
+    unknown_keys: UnknownKeys,
+) -> Result<Loaded<T>, Error> {
+    let profile = T::profiles().map(|profiles| profiles.active());
+    let document = format_dependant::from_str(content, format)?;
+    checked(document, content, format, profile.as_deref(), false, unknown_keys)
+}
+
+fn checked<T: FastConfig>(
+    document: Value,
+    content: &str,
+    format: Format,
+    profile: Option<&str>,
+    env: bool,
+    unknown_keys: UnknownKeys,
+) -> Result<Loaded<T>, Error> {
+    let prepared = prepare::<T>(document, profile)?;
+    let renamed = prepared.renamed;
+    let (config, unknown) = value::from_value_tracking::<T>(prepared.document, prepared.coercion);
+    let mut config = config.map_err(|e| Error::Deserialize {
+        key: e.path,
+        message: e.message,
+    })?;
+    unknown::keep(&mut config, &unknown);
+    let locations = Locations::new(content, format);
+    let prefixes = prefixes::<T>(profile);
+    let unknown = match unknown_keys {
+        UnknownKeys::Ignore => Vec::new(),
+        UnknownKeys::Warn | UnknownKeys::Deny => unknown::problems(unknown, Some(&locations), &prefixes),
+    };
+    if unknown_keys == UnknownKeys::Deny && !unknown.is_empty() {
+        return Err(Error::UnknownKeys(unknown));
+    }
+    if env {
+        config.apply_env()?;
+    }
+    config.check()?;
+    let deprecated = renamed.into_iter().map(|alias| recover::Problem {
+        location: recover::locate(&locations, &prefixes, &alias.from),
+        message: alias.warning(),
+        path: alias.from,
+    });
+    let mut warnings = deprecated.chain(unknown).collect::<Vec<_>>();
+    recover::sort(&mut warnings);
+    Ok(Loaded { value: config, warnings })
+}
+
+/// Writes the config to `path`, into the given profile's section when there is one.
+pub(crate) fn write<T: FastConfig>(
+    config: &T,
+    path: &Path,
+    format: Format,
+    pretty: bool,
+    profile: Option<&str>,
+) -> Result<(), Error> {
+    write_file(config, path, format, pretty, profile).map_err(|e| in_file::<T>(e, path, None, profile))
+}
+
+fn write_file<T: FastConfig>(
+    config: &T,
+    path: &Path,
+    format: Format,
+    pretty: bool,
+    profile: Option<&str>,
+) -> Result<(), Error> {
+    if T::check_on_save() {
+        config.check()?;
+    }
+    let written = |mut document: Value| {
+        compat::numbers(&mut document, format, T::numbers())?;
+        match pretty {
+            true => format_dependant::to_string_pretty(&document.in_format(format), format),
+            false => format_dependant::to_string(&document.in_format(format), format),
+        }
+    };
+    let content = match (profile, T::includes()) {
+        (Some(profile), _) => {
+            let profiles = T::profiles().unwrap_or_default();
+            written(crate::profiles::document_for_save(config, path, format, &profiles, profile)?)
+        }
+        (None, Some(includes)) if path.exists() => {
+            written(includes::document_for_save(config, path, format, &includes)?)
+        }
+        (None, _) => match pretty {
+            true => config.to_string_pretty(format),
+            false => config.to_string(format),
+        },
+    };
+    let content = match T::verify_on_save() {
+        true => compat::verify(config, content, format, profile)?,
+        false => content?,
+    };
+    if let Some(parent_dir) = path.parent() {
+        std::fs::create_dir_all(parent_dir)?;
+    }
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+/// Writes the documented default config to `path`, see [`FastConfig::write_template`].
+pub(crate) fn write_template<T: FastConfig + ConfigFields + Default>(path: &Path, format: Format) -> Result<(), Error> {
+    let write = || {
+        let content = crate::template::render::<T>(format)?;
+        if let Some(parent_dir) = path.parent() {
+            std::fs::create_dir_all(parent_dir)?;
+        }
+        std::fs::write(path, content)?;
+        Ok(())
+    };
+    write().map_err(|e| in_file::<T>(e, path, None, None))
+}
+
+/// Writes the config to `path` with a reference to its JSON Schema, see [`FastConfig::save_with_schema`].
+#[cfg(feature = "schema")]
+pub(crate) fn write_referencing<T: FastConfig>(config: &T, path: &Path, format: Format, schema: &str) -> Result<(), Error> {
+    let write = || {
+        if T::check_on_save() {
+            config.check()?;
+        }
+        let mut document = to_document(config).map_err(std::io::Error::other)?;
+        compat::numbers(&mut document, format, T::numbers())?;
+        let content = crate::schema::referencing(document, format, schema)?;
+        if let Some(parent_dir) = path.parent() {
+            std::fs::create_dir_all(parent_dir)?;
+        }
+        std::fs::write(path, content)?;
+        Ok(())
+    };
+    write().map_err(|e| in_file::<T>(e, path, None, None))
+}
+
+/// Reads an untyped document, see [`Value::load`].
+pub(crate) fn read_value(path: &Path, format: Format) -> Result<Value, Error> {
+    let content = std::fs::read_to_string(path).map_err(|e| in_untyped_file(e.into(), path, None, None))?;
+    Value::from_string(&content, format).map_err(|e| in_untyped_file(e, path, Some(&content), None))
+}
+
+/// Writes an untyped document, see [`Value::save`].
+pub(crate) fn write_value(document: &Value, path: &Path, format: Format, pretty: bool) -> Result<(), Error> {
+    let write = || {
+        let content = match pretty {
+            true => document.to_string_pretty(format)?,
+            false => document.to_string(format)?,
+        };
+        if let Some(parent_dir) = path.parent() {
+            std::fs::create_dir_all(parent_dir)?;
+        }
+        std::fs::write(path, content)?;
+        Ok(())
+    };
+    write().map_err(|e| in_untyped_file(e, path, None, None))
+}
+
+/// Like [`in_file`] for files read without a config type, pointing at the first problem found in the file
+/// or else where the format gave up.
+pub(crate) fn in_untyped_file(error: Error, path: &Path, content: Option<&str>, first: Option<Problem>) -> Error {
+    let location = first
+        .as_ref()
+        .and_then(|problem| problem.location)
+        .or_else(|| location::of_format_error(&error, content));
+    Error::File(Box::new(FileError {
+        path: path.to_owned(),
+        key: first.map(|problem| problem.path).filter(|key| !key.is_empty()),
+        snippet: location.zip(content).and_then(|(l, content)| content.lines().nth(l.line - 1)).map(str::to_owned),
+        help: None,
+        location,
+        source: error,
+    }))
+}
+
+/// Turns the config into a document, putting back the keys kept by its `#[fast_config(unknown)]` field.
+pub(crate) fn to_value<T: FastConfig>(config: &T) -> Result<Value, value::Error> {
+    let mut document = value::to_value(config)?;
+    unknown::restore(config, &mut document);
+    Ok(document)
+}
+
+/// Turns the config into the document `save` writes, with its kept keys and current version.
+pub(crate) fn to_document<T: FastConfig>(config: &T) -> Result<Value, value::Error> {
+    let mut document = to_value(config)?;
+    if let Some(versioning) = T::versioning() {
+        migrate::stamp(&mut document, &versioning);
+    }
+    Ok(document)
+}
+
+/// Serializes the config through its document, with its kept keys, current version and number policy.
+pub(crate) fn to_string<T: FastConfig>(config: &T, format: Format, pretty: bool) -> Result<String, Error> {
+    // A config the document can't hold is left for the format to report
+    let Ok(mut document) = to_document(config) else {
+        return match pretty {
+            true => format_dependant::to_string_pretty(config, format),
+            false => format_dependant::to_string(config, format),
+        };
+    };
+    compat::numbers(&mut document, format, T::numbers())?;
+    match pretty {
+        true => format_dependant::to_string_pretty(&document.in_format(format), format),
+        false => format_dependant::to_string(&document.in_format(format), format),
+    }
+}
+
+/// What the derived `save` and `save_pretty` call, writing into the active profile if the type has profiles.
+pub fn save<T: FastConfig>(config: &T, path: impl AsRef<Path>, format: Format, pretty: bool) -> Result<(), Error> {
+    let profile = T::profiles().map(|profiles| profiles.active());
+    write(config, path.as_ref(), format, pretty, profile.as_deref())
+}
@@ -0,0 +1,53 @@
+use crate::Error;
+use crate::Format;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Parses `content` in the given format into any deserializable type.
+pub(crate) fn from_str<T: DeserializeOwned>(content: &str, format: Format) -> Result<T, Error> {
+    let result = match format {
+        #[cfg(feature = "json")]
+        Format::JSON => serde_json::from_str::<T>(content)?,
+        #[cfg(feature = "json5")]
+        Format::JSON5 => json5::from_str::<T>(content)?,
+        #[cfg(feature = "toml")]
+        Format::TOML => toml::from_str::<T>(content)?,
+        #[cfg(feature = "yaml")]
+        Format::YAML => serde_yml::from_str::<T>(content)?,
+    };
+    Ok(result)
+}
+
+/// Writes `value` in the given format, compactly where the format allows it.
+pub(crate) fn to_string<T: Serialize>(value: &T, format: Format) -> Result<String, Error> {
+    let result = match format {
+        #[cfg(feature = "json")]
+        Format::JSON => serde_json::to_string(value)?,
+        #[cfg(feature = "json5")]
+        Format::JSON5 => json5::to_string(value)?,
+        #[cfg(feature = "toml")]
+        Format::TOML => toml::to_string(value)?,
+        #[cfg(feature = "yaml")]
+        Format::YAML => serde_yml::to_string(value)?,
+    };
+    Ok(result)
+}
+
+/// Writes `value` in the given format, indented and human-readable.
+pub(crate) fn to_string_pretty<T: Serialize>(
+    value: &T,
+    format: Format,
+) -> Result<String, Error> {
+    let result = match format {
+        #[cfg(feature = "json")]
+        Format::JSON => serde_json::to_string_pretty(value)?,
+        #[cfg(feature = "json5")]
+        Format::JSON5 => json5::to_string(value)?,
+        #[cfg(feature = "toml")]
+        Format::TOML => toml::to_string_pretty(value)?,
+        #[cfg(feature = "yaml")]
+        Format::YAML => serde_yml::to_string(value)?,
+    };
+    Ok(result)
+}
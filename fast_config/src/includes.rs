@@ -0,0 +1,264 @@
+use crate::Error;
+use crate::FastConfig;
+use crate::Format;
+use crate::file;
+use crate::format_dependant;
+use crate::value::Map;
+use crate::value::Segment;
+use crate::value::Value;
+use crate::value::render_path;
+
+use std::path::Path;
+use std::path::PathBuf;
+
+/// How `load` and `new` pull in other files, set with `#[fast_config(includes)]`.
+///
+/// The loaded file is read first, then each file listed under [`Includes::key`] is merged on top of it in order,
+/// followed by the drop-in directory (`config.d/` next to `config.toml`) in lexical order.
+/// Included files may use any enabled format, chosen by their extension.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Includes {
+    /// The key listing files to include, relative to the file that lists them. Defaults to `include`.
+    pub key: String,
+    /// Whether to merge the `<name>.d/` directory next to the loaded file. Defaults to `true`.
+    pub drop_ins: bool,
+    /// The directory every included file must stay inside. Defaults to the loaded file's directory.
+    pub root: Option<PathBuf>,
+}
+
+impl Default for Includes {
+    fn default() -> Self {
+        Self {
+            key: "include".into(),
+            drop_ins: true,
+            root: None,
+        }
+    }
+}
+
+/// The files a merged document was read from, in the order they were merged.
+pub(crate) struct Sources(Vec<Source>);
+
+/// A file read while loading, kept so errors can point into the file a value came from.
+pub(crate) struct Source {
+    pub(crate) path: PathBuf,
+    pub(crate) content: String,
+    pub(crate) format: Format,
+    document: Value,
+}
+
+impl Sources {
+    /// The last file setting `key` under any of the `prefixes`, which is the one its value was merged from.
+    pub(crate) fn source(&self, prefixes: &[String], key: &str) -> Option<&Source> {
+        let paths = prefixes.iter().map(|prefix| match prefix.is_empty() {
+            true => key.to_owned(),
+            false => format!("{prefix}.{key}"),
+        });
+        let paths = paths.collect::<Vec<_>>();
+        self.0
+            .iter()
+            .rev()
+            .find(|source| paths.iter().any(|path| source.document.get_path(path).is_some()))
+    }
+}
+
+/// Reads `path`, whose `content` was already read, along with everything it includes into a single merged document.
+pub(crate) fn load(path: &Path, content: &str, format: Format, includes: &Includes) -> Result<(Value, Sources), Error> {
+    let root = match &includes.root {
+        Some(root) => root.clone(),
+        None => match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        },
+    };
+    let root = root.canonicalize().map_err(|e| Error::Include {
+        path: root.clone(),
+        message: format!("cannot resolve the include root: {e}"),
+    })?;
+    let mut loader = Loader {
+        includes,
+        root,
+        stack: Vec::new(),
+        sources: Vec::new(),
+    };
+
+    let mut document = loader.load(path, Some(content), format, false)?;
+    if includes.drop_ins {
+        for file in drop_ins(path)? {
+            let format = Format::from_path(&file).expect("drop-ins are filtered by format");
+            let drop_in = loader.load(&file, None, format, true)?;
+            document.merge(drop_in);
+        }
+    }
+    Ok((document, Sources(loader.sources)))
+}
+
+/// The document `save` writes over a file including others: its include list, followed by the values of the config
+/// its included files and drop-ins don't already set. <br/>
+/// They are merged over the file listing them, so when the config changed a value one of them sets,
+/// this fails with [`Error::Include`] pointing at that file instead of writing a value the next load won't read.
+pub(crate) fn document_for_save<T: FastConfig>(
+    config: &T,
+    path: &Path,
+    format: Format,
+    includes: &Includes,
+) -> Result<Value, Error> {
+    let mut document = file::to_document(config).map_err(std::io::Error::other)?;
+    let content = std::fs::read_to_string(path)?;
+    let (_, sources) = load(path, &content, format, includes)?;
+    let Some((main, included)) = sources.0.split_first() else {
+        return Ok(document);
+    };
+    let mut base = Value::Table(Map::new());
+    for source in included {
+        let mut set = source.document.clone();
+        if let Value::Table(map) = &mut set {
+            map.remove(&includes.key);
+        }
+        base.merge(set);
+    }
+    let listed = main.document.as_table().and_then(|map| map.get(&includes.key)).cloned();
+    if let (Value::Table(map), Value::Table(base)) = (&mut document, &base) {
+        unset(map, base, &mut Vec::new()).map_err(|key| Error::Include {
+            path: sources.source(&[String::new()], &key).map_or(path, |source| &source.path).to_path_buf(),
+            message: format!("sets `{key}` over the value being saved to `{}`, change it there", path.display()),
+        })?;
+        if let Some(listed) = listed {
+            *map = std::iter::once((includes.key.clone(), listed)).chain(std::mem::take(map)).collect();
+        }
+    }
+    Ok(document)
+}
+
+/// Leaves out of `map` the values `base` sets the same, failing with the key path of one it sets differently.
+fn unset(map: &mut Map, base: &Map, path: &mut Vec<Segment>) -> Result<(), String> {
+    for (key, set) in base.iter() {
+        path.push(Segment::Key(key.clone()));
+        let remove = match (map.get_mut(key), set) {
+            (None, _) => false,
+            (Some(Value::Table(table)), Value::Table(set)) if !table.is_variant() && !set.is_variant() => {
+                unset(table, set, path)?;
+                table.is_empty()
+            }
+            (Some(value), set) if value == set => true,
+            (Some(_), _) => return Err(render_path(path)),
+        };
+        if remove {
+            map.remove(key);
+        }
+        path.pop();
+    }
+    Ok(())
+}
+
+struct Loader<'a> {
+    includes: &'a Includes,
+    root: PathBuf,
+    /// Files currently being loaded, used to detect include cycles.
+    stack: Vec<PathBuf>,
+    /// Every file read so far, in the order they were merged.
+    sources: Vec<Source>,
+}
+
+impl Loader<'_> {
+    /// Reads the file at `path`, unless its `content` is already known, and merges what it includes on top.
+    fn load(&mut self, path: &Path, content: Option<&str>, format: Format, confined: bool) -> Result<Value, Error> {
+        let include_error = |message: String| Error::Include {
+            path: path.to_path_buf(),
+            message,
+        };
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| include_error(format!("cannot read the file: {e}")))?;
+        if confined && !canonical.starts_with(&self.root) {
+            return Err(include_error(format!(
+                "the file is outside of the allowed directory `{}`",
+                self.root.display()
+            )));
+        }
+        if self.stack.contains(&canonical) {
+            let chain = self
+                .stack
+                .iter()
+                .chain([&canonical])
+                .map(|p| format!("`{}`", p.display()))
+                .collect::<Vec<_>>();
+            return Err(include_error(format!("include cycle {}", chain.join(" -> "))));
+        }
+
+        let content = match content {
+            Some(content) => content.to_owned(),
+            None => std::fs::read_to_string(&canonical).map_err(|e| file::in_untyped_file(e.into(), path, None, None))?,
+        };
+        let mut document = format_dependant::from_str::<Value>(&content, format)
+            .map_err(|e| file::in_untyped_file(e, path, Some(&content), None))?;
+        self.sources.push(Source {
+            path: path.to_path_buf(),
+            content,
+            format,
+            document: document.clone(),
+        });
+        let listed = match &mut document {
+            Value::Table(map) => map.remove(&self.includes.key),
+            _ => None,
+        };
+        let listed = match listed {
+            None => Vec::new(),
+            Some(Value::String(file)) => vec![file],
+            Some(Value::Array(files)) => files
+                .into_iter()
+                .map(|file| match file {
+                    Value::String(file) => Ok(file),
+                    other => Err(other),
+                })
+                .collect::<Result<_, _>>()
+                .map_err(|other| {
+                    include_error(format!("`{}` must only list file paths, found {}", self.includes.key, other.kind()))
+                })?,
+            Some(other) => {
+                return Err(include_error(format!(
+                    "`{}` must be a file path or a list of file paths, found {}",
+                    self.includes.key,
+                    other.kind()
+                )));
+            }
+        };
+
+        self.stack.push(canonical.clone());
+        let base = canonical.parent().unwrap_or(Path::new("/")).to_path_buf();
+        for file in listed {
+            let file = base.join(file);
+            let format = Format::from_path(&file).ok_or_else(|| Error::Include {
+                path: file.clone(),
+                message: "unknown or disabled file format".into(),
+            })?;
+            let included = self.load(&file, None, format, true)?;
+            document.merge(included);
+        }
+        self.stack.pop();
+        Ok(document)
+    }
+}
+
+/// Lists the files of the `<name>.d/` directory next to `path` in lexical order, skipping unknown formats.
+fn drop_ins(path: &Path) -> Result<Vec<PathBuf>, Error> {
+    let Some(stem) = path.file_stem() else {
+        return Ok(Vec::new());
+    };
+    let mut name = stem.to_os_string();
+    name.push(".d");
+    let dir = path.with_file_name(name);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let in_dir = |e: std::io::Error| file::in_untyped_file(e.into(), &dir, None, None);
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(&dir).map_err(in_dir)? {
+        let file = entry.map_err(in_dir)?.path();
+        if file.is_file() && Format::from_path(&file).is_some() {
+            files.push(file);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
@@ -0,0 +1,148 @@
+use crate::value;
+use crate::value::Value;
+
+use std::collections::HashMap;
+
+/// Expands `${...}` references inside every string of `document`. <br/>
+/// `${key.path}` reads another key of the document, falling back to an environment variable of the same name,
+/// `${env:NAME}` only reads the environment, `${X:-fallback}` provides a default and `$${` escapes a literal `${`.
+/// <br/> Returns the key paths of the strings that held references, which are read as text, see [`value::Coercion`].
+pub(crate) fn expand(document: &mut Value) -> Result<Vec<Vec<value::Segment>>, value::Error> {
+    let mut expander = Expander {
+        original: document.clone(),
+        resolved: HashMap::new(),
+        stack: Vec::new(),
+        expanded: Vec::new(),
+    };
+    expander.walk(document, &mut Vec::new())?;
+    Ok(expander.expanded)
+}
+
+struct Expander {
+    original: Value,
+    resolved: HashMap<String, String>,
+    stack: Vec<String>,
+    expanded: Vec<Vec<value::Segment>>,
+}
+
+impl Expander {
+    fn walk(&mut self, value: &mut Value, path: &mut Vec<value::Segment>) -> Result<(), value::Error> {
+        match value {
+            Value::String(text) if text.contains("${") => {
+                let at = value::render_path(path);
+                *text = self
+                    .expand_text(text)
+                    .map_err(|message| value::Error::at(at, message))?;
+                self.expanded.push(path.clone());
+            }
+            Value::Array(list) => {
+                for (i, item) in list.iter_mut().enumerate() {
+                    path.push(value::Segment::Index(i));
+                    self.walk(item, path)?;
+                    path.pop();
+                }
+            }
+            Value::Table(map) => {
+                for (key, item) in map.iter_mut() {
+                    path.push(value::Segment::Key(key.clone()));
+                    self.walk(item, path)?;
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn expand_text(&mut self, text: &str) -> Result<String, String> {
+        let mut out = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find('$') {
+            out.push_str(&rest[..start]);
+            rest = &rest[start..];
+            if let Some(escaped) = rest.strip_prefix("$${") {
+                out.push_str("${");
+                rest = escaped;
+            } else if let Some(body) = rest.strip_prefix("${") {
+                let end = closing_brace(body).ok_or_else(|| format!("unclosed `${{` in `{text}`"))?;
+                out.push_str(&self.resolve(&body[..end])?);
+                rest = &body[end + 1..];
+            } else {
+                out.push('$');
+                rest = &rest[1..];
+            }
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    fn resolve(&mut self, reference: &str) -> Result<String, String> {
+        let (name, default) = match reference.split_once(":-") {
+            Some((name, default)) => (name.trim(), Some(default)),
+            None => (reference.trim(), None),
+        };
+        let fallback = |this: &mut Self, missing: String| match default {
+            Some(default) => this.expand_text(default),
+            None => Err(missing),
+        };
+        if let Some(var) = name.strip_prefix("env:") {
+            return match std::env::var(var.trim()) {
+                Ok(value) => Ok(value),
+                Err(_) => fallback(self, format!("environment variable `{}` is not set", var.trim())),
+            };
+        }
+        if self.original.get_path(name).is_some() {
+            return self.lookup(name);
+        }
+        match std::env::var(name) {
+            Ok(value) => Ok(value),
+            Err(_) => fallback(
+                self,
+                format!("`{name}` is neither a key in this document nor an environment variable"),
+            ),
+        }
+    }
+
+    /// Reads another key of the document, expanding its own references first.
+    fn lookup(&mut self, name: &str) -> Result<String, String> {
+        let key = value::parse_path(name)
+            .map(|segments| value::render_path(&segments))
+            .map_err(|e| e.message)?;
+        if let Some(done) = self.resolved.get(&key) {
+            return Ok(done.clone());
+        }
+        if let Some(start) = self.stack.iter().position(|k| *k == key) {
+            let mut cycle = self.stack[start..].to_vec();
+            cycle.push(key);
+            return Err(format!("reference cycle `{}`", cycle.join("` -> `")));
+        }
+        let text = match self.original.get_path(&key) {
+            Some(Value::String(text)) => text.clone(),
+            Some(Value::Integer(i)) => return Ok(i.to_string()),
+            Some(Value::Float(f)) => return Ok(f.to_string()),
+            Some(Value::Bool(b)) => return Ok(b.to_string()),
+            Some(Value::Null) | None => return Ok(String::new()),
+            Some(other) => return Err(format!("`{key}` is {} and can't be inserted into a string", other.kind())),
+        };
+        self.stack.push(key.clone());
+        let expanded = self.expand_text(&text);
+        self.stack.pop();
+        let expanded = expanded?;
+        self.resolved.insert(key, expanded.clone());
+        Ok(expanded)
+    }
+}
+
+/// Finds the `}` closing a `${`, allowing references nested inside defaults.
+fn closing_brace(body: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in body.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
@@ -15,6 +15,87 @@ compile_error!("You must enable at least one format feature: `json`, `json5`, `t
 #[cfg(feature = "derive")]
 extern crate fast_config_derive;
 
+mod access;
+mod alias;
+mod compat;
+mod convert;
+mod env;
+mod fields;
+mod file;
+mod format_dependant;
+mod includes;
+mod interpolate;
+mod location;
+mod migrate;
+mod overrides;
+mod profiles;
+mod recover;
+mod reference;
+mod render;
+#[cfg(feature = "schema")]
+mod schema;
+mod template;
+mod unknown;
+mod validate;
+mod value;
+
+pub use alias::Alias;
+pub use compat::NumberPolicy;
+pub use compat::check_format;
+pub use convert::convert;
+pub use env::EnvVar;
+pub use fields::ConfigFields;
+pub use fields::FieldAttributes;
+pub use fields::FieldDescriptor;
+pub use includes::Includes;
+pub use location::Location;
+pub use migrate::Document;
+pub use migrate::Migration;
+pub use migrate::Versioning;
+pub use profiles::Profiles;
+pub use recover::Problem;
+pub use recover::Recovered;
+pub use reference::FieldReference;
+pub use reference::MarkdownStyle;
+pub use reference::Reference;
+pub use unknown::Loaded;
+pub use unknown::UnknownFields;
+pub use unknown::UnknownKeys;
+pub use value::Datetime;
+pub use value::Error as ValueError;
+pub use value::Map;
+pub use value::Value;
+pub use validate::Bound;
+pub use validate::Constraint;
+pub use validate::Rule;
+pub use validate::Validate;
+pub use validate::Violation;
+pub use validate::Violations;
+#[cfg(feature = "schema")]
+pub use schema::Schema;
+#[cfg(feature = "clap")]
+pub use overrides::SetArgs;
+/// Re-exported for `#[derive(JsonSchema)]`, which can point at it with `#[schemars(crate = "fast_config::schemars")]`.
+#[cfg(feature = "schema")]
+pub use schemars;
+
+/// Items used by the code generated from `#[derive(FastConfig)]`.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::env::apply as apply_env;
+    pub use crate::file::save;
+    pub use crate::overrides::Flag;
+    pub use crate::overrides::apply_flags;
+    pub use crate::overrides::flag_text;
+    pub use crate::overrides::flag_value;
+    pub use crate::validate::Length;
+    pub use crate::validate::bound;
+    #[cfg(feature = "clap")]
+    pub use clap;
+    #[cfg(feature = "regex")]
+    pub use regex;
+}
+
 /// Derive macro available if serde is built with `features = ["derive"]`.
 #[cfg(feature = "derive")]
 pub use fast_config_derive::FastConfig;
@@ -32,6 +113,29 @@ pub enum Format {
     YAML,
 }
 
+impl Format {
+    /// Guesses the format from a file extension such as `toml` or `yml`. <br/>
+    /// Returns `None` for unknown extensions and for formats whose feature isn't enabled.
+    pub fn from_extension(extension: &str) -> Option<Format> {
+        match extension.to_ascii_lowercase().as_str() {
+            #[cfg(feature = "json")]
+            "json" => Some(Format::JSON),
+            #[cfg(feature = "json5")]
+            "json5" => Some(Format::JSON5),
+            #[cfg(feature = "toml")]
+            "toml" => Some(Format::TOML),
+            #[cfg(feature = "yaml")]
+            "yaml" | "yml" => Some(Format::YAML),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format from the extension of a file path.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Format> {
+        Format::from_extension(path.as_ref().extension()?.to_str()?)
+    }
+}
+
 /// The main result error type of the crate. <br/>
 /// Each type has it's own documentation.
 #[derive(thiserror::Error, Debug)]
@@ -57,6 +161,155 @@ pub enum Error {
     #[cfg(feature = "yaml")]
     #[error(transparent)]
     Yaml(#[from] serde_yml::Error),
+
+    /// An environment variable override couldn't be applied to the config.
+    #[error("environment variable `{name}`: {message}")]
+    Env { name: String, message: String },
+
+    /// A `key.path=value` override couldn't be parsed or applied to the config.
+    #[error("invalid override for `{key}`: {message}")]
+    Override { key: String, message: String },
+
+    /// A `${...}` reference couldn't be expanded, see [`FastConfig::interpolate`].
+    #[error("cannot expand `{key}`: {message}")]
+    Interpolation { key: String, message: String },
+
+    /// A value doesn't match the type it's being read into.
+    #[error("invalid value for `{key}`: {message}")]
+    Deserialize { key: String, message: String },
+
+    /// A key path doesn't lead to any field of the config, see [`FastConfig::get_path`],
+    /// or can't be set in a [`Value`], see [`Value::set_path`].
+    #[error("nothing at `{key}`")]
+    UnknownPath { key: String },
+
+    /// An included or drop-in file couldn't be loaded, see [`FastConfig::includes`].
+    #[error("cannot include `{}`: {message}", path.display())]
+    Include { path: std::path::PathBuf, message: String },
+
+    /// A profile couldn't be selected or saved, see [`FastConfig::profiles`].
+    #[error("profile `{profile}`: {message}")]
+    Profile { profile: String, message: String },
+
+    /// The config was read fine but failed validation, see [`Validate`].
+    #[error("invalid config: {0}")]
+    Validation(Violations),
+
+    /// An older config couldn't be upgraded to the current version, see [`Versioning`].
+    #[error("cannot migrate the config from version {version}: {message}")]
+    Migration { version: u32, message: String },
+
+    /// The config has keys no field asks for, when loaded with [`UnknownKeys::Deny`].
+    #[error("unknown keys: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    UnknownKeys(Vec<Problem>),
+
+    /// The document holds values the format can't, such as nulls in TOML, see [`check_format`] and [`convert`].
+    #[error(
+        "cannot be written as {format:?}: {}",
+        problems.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+    )]
+    Unrepresentable { format: Format, problems: Vec<Problem> },
+
+    /// Any of the above, raised while reading or writing a file. <br/>
+    /// Use [`Error::file`], [`Error::key`], [`Error::location`] and [`Error::inner`] rather than matching on it.
+    #[error(transparent)]
+    File(Box<FileError>),
+}
+
+/// An error raised while reading or writing a file, along with where it happened.
+#[derive(thiserror::Error, Debug)]
+#[error("{}{}: {source}", path.display(), location.map(|l| format!(":{l}")).unwrap_or_default())]
+pub struct FileError {
+    pub path: std::path::PathBuf,
+    /// The dotted key path of the offending value, such as `servers[2].port`.
+    pub key: Option<String>,
+    pub location: Option<Location>,
+    /// The line of the file that `location` points into.
+    pub snippet: Option<String>,
+    /// A suggestion such as ``did you mean `timeout_ms`?``.
+    pub help: Option<String>,
+    pub source: Error,
+}
+
+impl Error {
+    /// The file that was being read or written, if any.
+    pub fn file(&self) -> Option<&Path> {
+        match self {
+            Error::File(file) => file.source.file().or(Some(&file.path)),
+            Error::Include { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// The dotted key path of the offending value, such as `servers[2].port`. <br/>
+    /// For validation errors, this is the path of the first violation.
+    pub fn key(&self) -> Option<&str> {
+        let key = match self {
+            Error::File(file) => file.key.as_deref().or_else(|| file.source.key()),
+            Error::Override { key, .. } | Error::Interpolation { key, .. } | Error::Deserialize { key, .. } => Some(key.as_str()),
+            Error::Validation(violations) => violations.iter().next().map(|violation| violation.path.as_str()),
+            Error::UnknownKeys(keys) => keys.first().map(|key| key.path.as_str()),
+            _ => None,
+        };
+        key.filter(|key| !key.is_empty())
+    }
+
+    /// Where in the file the error happened, with 1-based lines and character columns whatever the format.
+    pub fn location(&self) -> Option<Location> {
+        match self {
+            Error::File(file) => file.location.or_else(|| file.source.location()),
+            other => location::of_format_error(other, None),
+        }
+    }
+
+    /// The 1-based line of [`Error::location`].
+    pub fn line(&self) -> Option<usize> {
+        self.location().map(|location| location.line)
+    }
+
+    /// The 1-based column of [`Error::location`].
+    pub fn column(&self) -> Option<usize> {
+        self.location().map(|location| location.column)
+    }
+
+    /// A suggestion for fixing the error, such as ``did you mean `timeout_ms`?`` for misspelled keys.
+    pub fn help(&self) -> Option<&str> {
+        match self {
+            Error::File(file) => file.help.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// A report for the people editing the config, showing the offending line with a caret under the bad token,
+    /// the expected type and suggestions for misspelled keys.
+    ///
+    /// ```text
+    /// error: invalid type: string "high", expected u16
+    ///   --> config.toml:2:1
+    ///   |
+    /// 2 | port = "high"
+    ///   |        ^^^^^^
+    ///   = expected: u16
+    /// ```
+    pub fn render(&self) -> String {
+        render::render(self)
+    }
+
+    /// The error without the file context around it, to match on.
+    pub fn inner(&self) -> &Error {
+        match self {
+            Error::File(file) => file.source.inner(),
+            other => other,
+        }
+    }
+
+    /// Like [`Error::inner`], taking ownership.
+    pub fn into_inner(self) -> Error {
+        match self {
+            Error::File(file) => file.source.into_inner(),
+            other => other,
+        }
+    }
 }
 
 pub trait FastConfig
@@ -66,6 +319,216 @@ where
     fn load(&mut self, path: impl AsRef<Path>, format: Format) -> Result<(), Error>;
     fn save(&self, path: impl AsRef<Path>, format: Format) -> Result<(), Error>;
     fn save_pretty(&self, path: impl AsRef<Path>, format: Format) -> Result<(), Error>;
+
+    /// Prefix of the environment variables that override values, set with `#[fast_config(env_prefix = "...")]`. <br/>
+    /// With a prefix of `MYAPP`, `MYAPP_SERVER__PORT` overrides `server.port`.
+    fn env_prefix() -> Option<&'static str> {
+        None
+    }
+    /// Environment variables pinned to specific keys with `#[fast_config(env = "...")]`.
+    fn env_vars() -> Vec<EnvVar> {
+        Vec::new()
+    }
+    /// Overrides the current values with environment variables. <br/>
+    /// Called automatically by `load` and `new`; does nothing unless the type opted in.
+    fn apply_env(&mut self) -> Result<(), Error> {
+        env::apply(self, &[])
+    }
+    /// Applies `key.path=value` assignments on top of the current values, such as the ones passed with `--set`. <br/>
+    /// Values are parsed like inline TOML/JSON, so `servers[0].port=8080` and `tags=["a", "b"]` both work. <br/>
+    /// Fails with [`Error::UnknownPath`] when a path doesn't lead to a field, leaving the config as it was.
+    fn apply_overrides<I>(&mut self, assignments: I) -> Result<(), Error>
+    where
+        Self: ConfigFields,
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        overrides::apply(self, assignments)
+    }
+    /// Whether `from_string` expands `${...}` references before deserializing, set with `#[fast_config(interpolate)]`. <br/>
+    /// `${key.path}` reads another key of the same document *(or an environment variable of that name)*,
+    /// `${env:NAME}` only reads the environment, and `${NAME:-fallback}` provides a default.
+    fn interpolate() -> bool {
+        false
+    }
+    /// How `load` and `new` resolve `include = [...]` keys and `<name>.d/` drop-in directories,
+    /// set with `#[fast_config(includes)]`. See [`Includes`].
+    fn includes() -> Option<Includes> {
+        None
+    }
+    /// How profile-structured documents (`[default]`, `[dev]`, `[prod]`, ...) are read and saved,
+    /// set with `#[fast_config(profiles)]`. See [`Profiles`].
+    fn profiles() -> Option<Profiles> {
+        None
+    }
+    /// How files written by older versions are upgraded before being read,
+    /// set with `#[fast_config(version = 3)]`. See [`Versioning`].
+    fn versioning() -> Option<Versioning> {
+        None
+    }
+    /// Old keys still accepted in place of renamed or moved ones,
+    /// set with `#[fast_config(deprecated_alias = "...")]`. See [`Alias`].
+    fn aliases() -> Vec<Alias> {
+        Vec::new()
+    }
+    /// Lists the profiles of a profile-structured file, leaving out the version, `$schema` and include keys.
+    fn list_profiles(path: impl AsRef<Path>, format: Format) -> Result<Vec<String>, Error> {
+        profiles::list::<Self>(path.as_ref(), format)
+    }
+    /// Like `new`, but reads the given profile instead of the one selected by the environment.
+    fn new_profile(path: impl AsRef<Path>, format: Format, profile: &str) -> Result<Self, Error> {
+        file::read(path.as_ref(), format, Some(profile))
+    }
+    /// Saves into the given profile's section with pretty formatting, keeping the file's other profiles. <br/>
+    /// Only the values that differ from the default profile are written, unless saving the default profile itself.
+    fn save_profile(&self, path: impl AsRef<Path>, format: Format, profile: &str) -> Result<(), Error> {
+        file::write(self, path.as_ref(), format, true, Some(profile))
+    }
+    /// Runs the [`Validate`] implementation wired in with `#[fast_config(validate)]`. <br/>
+    /// Called automatically by `load`, `new` and `from_string`.
+    fn check(&self) -> Result<(), Error> {
+        Ok(())
+    }
+    /// The rules put on fields with `#[fast_config(range(...))]` and the like, including those of `nested` fields.
+    fn constraints() -> Vec<Constraint> {
+        Vec::new()
+    }
+    /// Whether `save` and `save_pretty` refuse to write a config that fails [`FastConfig::check`],
+    /// set with `#[fast_config(validate(on_save))]`.
+    fn check_on_save() -> bool {
+        false
+    }
+    /// Whether `save` and `save_pretty` read back what they are about to write and refuse to write it
+    /// when any value wouldn't come back the same, failing with [`Error::Unrepresentable`]. <br/>
+    /// Set with `#[fast_config(verify_save)]`.
+    fn verify_on_save() -> bool {
+        false
+    }
+    /// How `save` and `to_string` write numbers the format has no exact form for, such as a `u64` past `i64::MAX`
+    /// in TOML or NaN in JSON. <br/>
+    /// Set with `#[fast_config(numbers = "native")]`, `"error"` or `"string"`.
+    fn numbers() -> NumberPolicy {
+        NumberPolicy::Error
+    }
+    /// The function `new`, `load` and `from_string` call with each unknown and deprecated key they find,
+    /// which they otherwise leave for `new_checked` to report. <br/>
+    /// Set with `#[fast_config(on_warning = "path::to::function")]`, taking a `&Problem`.
+    fn on_warning() -> Option<fn(&Problem)> {
+        None
+    }
+    /// The command-line flags of every field, going into `nested` structs, see `#[fast_config(clap)]`.
+    #[doc(hidden)]
+    fn cli_flags() -> Vec<__private::Flag> {
+        Vec::new()
+    }
+    /// The `#[fast_config(unknown)]` field keeping the keys no field asked for, so `save` writes them back.
+    fn unknown_fields(&self) -> Option<&UnknownFields> {
+        None
+    }
+    /// Like [`FastConfig::unknown_fields`], for filling the field in while loading.
+    fn unknown_fields_mut(&mut self) -> Option<&mut UnknownFields> {
+        None
+    }
+    /// The default config, with each field's doc comment, allowed values and rules written as comments above it
+    /// and `Option` fields that default to `None` written as commented-out examples. <br/>
+    /// JSON has no comments, so it gets the plain default config.
+    fn template(format: Format) -> Result<String, Error>
+    where
+        Self: Default + ConfigFields,
+    {
+        template::render::<Self>(format)
+    }
+    /// Writes [`FastConfig::template`] to `path`, such as on the first run.
+    fn write_template(path: impl AsRef<Path>, format: Format) -> Result<(), Error>
+    where
+        Self: Default + ConfigFields,
+    {
+        file::write_template::<Self>(path.as_ref(), format)
+    }
+    /// The value at a dotted key path such as `server.port` or `servers[0].host`, written the way
+    /// [`FastConfig::set_path`] reads it: `8080`, `localhost`, `["a", "b"]`. Strings in lists and tables are quoted. <br/>
+    /// Fails with [`Error::UnknownPath`] when the path doesn't lead to a field. <br/>
+    /// The path is checked against the fields the derive describes in [`ConfigFields::FIELDS`], and the value read
+    /// through serde, so it's the one `save` would write there.
+    fn get_path(&self, path: &str) -> Result<String, Error>
+    where
+        Self: ConfigFields,
+    {
+        Ok(match access::get(self, path)? {
+            Value::String(text) => text,
+            other => value::to_inline(&other),
+        })
+    }
+    /// Like [`FastConfig::get_path`], reading the value into any type.
+    fn get_path_as<V: serde::de::DeserializeOwned>(&self, path: &str) -> Result<V, Error>
+    where
+        Self: ConfigFields,
+    {
+        value::from_value(access::get(self, path)?).map_err(|e| Error::Deserialize {
+            key: path.to_owned(),
+            message: e.message,
+        })
+    }
+    /// Sets the value at a dotted key path from text, read like a `--set key.path=value` override. <br/>
+    /// Fails with [`Error::UnknownPath`] when the path doesn't lead to a field
+    /// and with [`Error::Deserialize`] when the value doesn't fit it, leaving the config as it was.
+    fn set_path(&mut self, path: &str, text: &str) -> Result<(), Error>
+    where
+        Self: ConfigFields,
+    {
+        let value = value::parse_inline(text).map_err(|e| Error::Deserialize {
+            key: path.to_owned(),
+            message: e.message,
+        })?;
+        access::set(self, path, value)
+    }
+    /// Like [`FastConfig::set_path`], taking the value as any type.
+    fn set_path_to<V: Serialize>(&mut self, path: &str, value: V) -> Result<(), Error>
+    where
+        Self: ConfigFields,
+    {
+        let value = value::to_value(&value).map_err(std::io::Error::other)?;
+        access::set(self, path, value)
+    }
+    /// The config as an untyped [`Value`], holding what `save` would write:
+    /// the keys kept by its `#[fast_config(unknown)]` field and its version included.
+    fn to_value(&self) -> Result<Value, Error> {
+        Ok(file::to_document(self).map_err(std::io::Error::other)?)
+    }
+    /// Reads the config out of an untyped [`Value`] the way `from_string` reads a document,
+    /// migrating, selecting the active profile and checking it.
+    fn from_value(value: Value) -> Result<Self, Error> {
+        let profile = Self::profiles().map(|profiles| profiles.active());
+        let config = file::from_document::<Self>(value, profile.as_deref())?;
+        config.check()?;
+        Ok(config)
+    }
+    /// Every field with its type, default, doc comment, environment variable and rules,
+    /// for writing the documentation of the config with [`Reference::to_markdown`].
+    fn reference() -> Result<Reference, Error>
+    where
+        Self: Default + ConfigFields,
+    {
+        reference::build::<Self>()
+    }
+    /// The JSON Schema of the config, for editors to complete and check files with. <br/>
+    /// Field rules such as `range` and `one_of` show up in it as `minimum`, `enum` and the like.
+    #[cfg(feature = "schema")]
+    fn json_schema() -> serde_json::Value
+    where
+        Self: schemars::JsonSchema,
+    {
+        schema::generate::<Self>()
+    }
+    /// Like `save_pretty`, but also writes the JSON Schema next to the file as `<name>.schema.json`
+    /// and points the file at it, so editors pick it up.
+    #[cfg(feature = "schema")]
+    fn save_with_schema(&self, path: impl AsRef<Path>, format: Format) -> Result<(), Error>
+    where
+        Self: schemars::JsonSchema,
+    {
+        schema::save(self, path.as_ref(), format)
+    }
     #[cfg(any(
         feature = "json",
         feature = "json5",
@@ -73,17 +536,14 @@ where
         feature = "yaml"
     ))]
     fn from_string(content: &str, format: Format) -> Result<Self, Error> {
-        let result = match format {
-            #[cfg(feature = "json")]
-            Format::JSON => serde_json::from_str::<Self>(content)?,
-            #[cfg(feature = "json5")]
-            Format::JSON5 => json5::from_str::<Self>(content)?,
-            #[cfg(feature = "toml")]
-            Format::TOML => toml::from_str::<Self>(content)?,
-            #[cfg(feature = "yaml")]
-            Format::YAML => serde_yml::from_str::<Self>(content)?,
-        };
-        Ok(result)
+        if let Some(warn) = Self::on_warning() {
+            let loaded = Self::from_string_checked(content, format, UnknownKeys::Warn)?;
+            loaded.warnings.iter().for_each(warn);
+            return Ok(loaded.value);
+        }
+        let config = file::parse::<Self>(content, format)?;
+        config.check()?;
+        Ok(config)
     }
     #[cfg(any(
         feature = "json",
@@ -92,17 +552,7 @@ where
         feature = "yaml"
     ))]
     fn to_string(&self, format: Format) -> Result<String, Error> {
-        let result = match format {
-            #[cfg(feature = "json")]
-            Format::JSON => serde_json::to_string(self)?,
-            #[cfg(feature = "json5")]
-            Format::JSON5 => json5::to_string(self)?,
-            #[cfg(feature = "toml")]
-            Format::TOML => toml::to_string(self)?,
-            #[cfg(feature = "yaml")]
-            Format::YAML => serde_yml::to_string(self)?,
-        };
-        Ok(result)
+        file::to_string(self, format, false)
     }
     #[cfg(any(
         feature = "json",
@@ -111,22 +561,52 @@ where
         feature = "yaml"
     ))]
     fn to_string_pretty(&self, format: Format) -> Result<String, Error> {
-        let result = match format {
-            #[cfg(feature = "json")]
-            Format::JSON => serde_json::to_string_pretty(self)?,
-            #[cfg(feature = "json5")]
-            Format::JSON5 => json5::to_string(self)?,
-            #[cfg(feature = "toml")]
-            Format::TOML => toml::to_string_pretty(self)?,
-            #[cfg(feature = "yaml")]
-            Format::YAML => serde_yml::to_string(self)?,
-        };
-        Ok(result)
+        file::to_string(self, format, true)
     }
     fn new(path: impl AsRef<Path>, format: Format) -> Result<Self, Error> {
-        let content = std::fs::read_to_string(path)?;
-        let config = Self::from_string(&content, format)?;
-        Ok(config)
+        if let Some(warn) = Self::on_warning() {
+            let loaded = Self::new_checked(path, format, UnknownKeys::Warn)?;
+            loaded.warnings.iter().for_each(warn);
+            return Ok(loaded.value);
+        }
+        let profile = Self::profiles().map(|profiles| profiles.active());
+        file::read(path.as_ref(), format, profile.as_deref())
+    }
+    /// Like `new`, but keeps going past values of the wrong type and unknown keys,
+    /// returning every problem found along with the config read without them. <br/>
+    /// Only problems that can't be recovered from, such as syntax errors, are returned as `Err`.
+    fn new_recovering(path: impl AsRef<Path>, format: Format) -> Result<Recovered<Self>, Error> {
+        let profile = Self::profiles().map(|profiles| profiles.active());
+        file::read_recovering(path.as_ref(), format, profile.as_deref())
+    }
+    /// Like `from_string`, but keeps going past values of the wrong type and unknown keys,
+    /// see [`FastConfig::new_recovering`].
+    #[cfg(any(
+        feature = "json",
+        feature = "json5",
+        feature = "toml",
+        feature = "yaml"
+    ))]
+    fn from_string_recovering(content: &str, format: Format) -> Result<Recovered<Self>, Error> {
+        file::parse_recovering(content, format)
+    }
+    /// Like `new`, but deals with keys no field asks for as `unknown_keys` says,
+    /// at every nesting level and without needing `#[serde(deny_unknown_fields)]`. <br/>
+    /// Keys read into maps or `#[serde(flatten)]` fields are never unknown.
+    fn new_checked(path: impl AsRef<Path>, format: Format, unknown_keys: UnknownKeys) -> Result<Loaded<Self>, Error> {
+        let profile = Self::profiles().map(|profiles| profiles.active());
+        file::read_checked(path.as_ref(), format, profile.as_deref(), unknown_keys)
+    }
+    /// Like `from_string`, but deals with keys no field asks for as `unknown_keys` says,
+    /// see [`FastConfig::new_checked`].
+    #[cfg(any(
+        feature = "json",
+        feature = "json5",
+        feature = "toml",
+        feature = "yaml"
+    ))]
+    fn from_string_checked(content: &str, format: Format, unknown_keys: UnknownKeys) -> Result<Loaded<Self>, Error> {
+        file::parse_checked(content, format, unknown_keys)
     }
 }
 #[cfg(test)]
@@ -0,0 +1,410 @@
+//! Finding where a key path sits in the text of a config file.
+//!
+//! None of the format crates report positions for keys, so each format gets a small scanner of its own. <br/>
+//! The scanners are forgiving: anything they don't understand simply isn't indexed.
+
+use crate::Error;
+use crate::Format;
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// A 1-based line and column in a config file. <br/>
+/// Columns count characters, not bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Where each key and list item of a document starts, by key path.
+pub(crate) struct Locations(HashMap<String, Location>);
+
+impl Locations {
+    pub(crate) fn new(content: &str, format: Format) -> Self {
+        let offsets = match format {
+            #[cfg(feature = "json")]
+            Format::JSON => json(content),
+            #[cfg(feature = "json5")]
+            Format::JSON5 => json(content),
+            #[cfg(feature = "toml")]
+            Format::TOML => toml(content),
+            #[cfg(feature = "yaml")]
+            Format::YAML => yaml(content),
+        };
+        let lines = LineIndex::new(content);
+        let mut locations = HashMap::new();
+        for (path, offset) in offsets {
+            locations.entry(path).or_insert_with(|| lines.location(content, offset));
+        }
+        Self(locations)
+    }
+
+    /// Where the key or list item at `path` starts, such as `servers[2].port`.
+    pub(crate) fn get(&self, path: &str) -> Option<Location> {
+        self.0.get(path).copied()
+    }
+
+    /// Every key path found, with where it starts.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&str, Location)> {
+        self.0.iter().map(|(path, location)| (path.as_str(), *location))
+    }
+
+    /// Like [`Locations::get`], falling back to the closest parent that could be found.
+    pub(crate) fn closest(&self, path: &str) -> Option<Location> {
+        let mut path = path;
+        loop {
+            if let Some(location) = self.get(path) {
+                return Some(location);
+            }
+            path = &path[..parent_end(path)?];
+        }
+    }
+}
+
+/// Turns a byte offset into `content` into a line and column.
+#[cfg(any(feature = "toml", feature = "yaml"))]
+fn at_offset(content: &str, offset: usize) -> Location {
+    LineIndex::new(content).location(content, offset)
+}
+
+/// The position a format error reports, normalized to 1-based lines and character columns. <br/>
+/// Some formats only report byte offsets, which can't be turned into a column without the `content` they point into.
+#[allow(unused_variables)]
+pub(crate) fn of_format_error(error: &Error, content: Option<&str>) -> Option<Location> {
+    match error {
+        #[cfg(feature = "json")]
+        Error::Json(error) if error.line() > 0 => {
+            let line = error.line();
+            // serde_json counts bytes, starting at 1
+            let column = content
+                .and_then(|content| content.lines().nth(line - 1))
+                .and_then(|text| text.get(..error.column().saturating_sub(1)))
+                .map_or(error.column(), |text| text.chars().count() + 1);
+            Some(Location { line, column: column.max(1) })
+        }
+        #[cfg(feature = "json5")]
+        Error::Json5(error) => error.position().map(|position| Location {
+            line: position.line + 1,
+            column: position.column + 1,
+        }),
+        #[cfg(feature = "toml")]
+        Error::TomlDeserialize(error) => Some(at_offset(content?, error.span()?.start)),
+        #[cfg(feature = "yaml")]
+        Error::Yaml(error) => error.location().map(|location| match content {
+            Some(content) => at_offset(content, location.index()),
+            None => Location { line: location.line(), column: location.column() },
+        }),
+        _ => None,
+    }
+}
+
+/// Where the parent of a key path ends, or `None` for the root.
+fn parent_end(path: &str) -> Option<usize> {
+    if path.is_empty() {
+        return None;
+    }
+    Some(path.rfind(['.', '[']).unwrap_or(0))
+}
+
+fn child_key(path: &str, key: &str) -> String {
+    match path.is_empty() {
+        true => key.to_owned(),
+        false => format!("{path}.{key}"),
+    }
+}
+
+/// Byte offsets of line starts, to turn offsets into line and column numbers.
+struct LineIndex(Vec<usize>);
+
+impl LineIndex {
+    fn new(content: &str) -> Self {
+        let starts = std::iter::once(0).chain(content.match_indices('\n').map(|(i, _)| i + 1));
+        Self(starts.collect())
+    }
+
+    fn location(&self, content: &str, offset: usize) -> Location {
+        let line = self.0.partition_point(|&start| start <= offset).max(1);
+        let start = self.0[line - 1];
+        let column = content.get(start..offset).map_or(0, |text| text.chars().count());
+        Location { line, column: column + 1 }
+    }
+}
+
+/// Indexes JSON and JSON5, which share their structure; JSON5's comments,
+/// unquoted keys and single-quoted strings are understood too.
+#[cfg(any(feature = "json", feature = "json5"))]
+fn json(content: &str) -> Vec<(String, usize)> {
+    let mut scanner = JsonScanner { text: content.as_bytes(), pos: 0, found: Vec::new() };
+    scanner.skip_trivia();
+    scanner.found.push((String::new(), scanner.pos));
+    scanner.value(String::new(), 0);
+    scanner.found
+}
+
+#[cfg(any(feature = "json", feature = "json5"))]
+struct JsonScanner<'a> {
+    text: &'a [u8],
+    pos: usize,
+    found: Vec<(String, usize)>,
+}
+
+#[cfg(any(feature = "json", feature = "json5"))]
+impl JsonScanner<'_> {
+    const MAX_DEPTH: usize = 128;
+
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.pos).copied()
+    }
+
+    fn skip_trivia(&mut self) {
+        loop {
+            match (self.peek(), self.text.get(self.pos + 1)) {
+                (Some(c), _) if c.is_ascii_whitespace() => self.pos += 1,
+                (Some(b'/'), Some(b'/')) => {
+                    while self.peek().is_some_and(|c| c != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                (Some(b'/'), Some(b'*')) => {
+                    self.pos += 2;
+                    while self.pos < self.text.len() && !self.text[self.pos..].starts_with(b"*/") {
+                        self.pos += 1;
+                    }
+                    self.pos += 2;
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// Walks the value at the current position, recording where its keys and items start.
+    fn value(&mut self, path: String, depth: usize) -> Option<()> {
+        if depth > Self::MAX_DEPTH {
+            return None;
+        }
+        self.skip_trivia();
+        match self.peek()? {
+            b'{' => {
+                self.pos += 1;
+                loop {
+                    self.skip_trivia();
+                    if self.peek()? == b'}' {
+                        self.pos += 1;
+                        return Some(());
+                    }
+                    let start = self.pos;
+                    let key = self.key()?;
+                    let child = child_key(&path, &key);
+                    self.found.push((child.clone(), start));
+                    self.skip_trivia();
+                    if self.peek()? != b':' {
+                        return None;
+                    }
+                    self.pos += 1;
+                    self.value(child, depth + 1)?;
+                    self.separator();
+                }
+            }
+            b'[' => {
+                self.pos += 1;
+                for index in 0.. {
+                    self.skip_trivia();
+                    if self.peek()? == b']' {
+                        self.pos += 1;
+                        return Some(());
+                    }
+                    let child = format!("{path}[{index}]");
+                    self.found.push((child.clone(), self.pos));
+                    self.value(child, depth + 1)?;
+                    self.separator();
+                }
+                None
+            }
+            b'"' | b'\'' => self.string().map(drop),
+            _ => {
+                while self.peek().is_some_and(|c| !matches!(c, b',' | b'}' | b']' | b'/') && !c.is_ascii_whitespace()) {
+                    self.pos += 1;
+                }
+                Some(())
+            }
+        }
+    }
+
+    fn separator(&mut self) {
+        self.skip_trivia();
+        if self.peek() == Some(b',') {
+            self.pos += 1;
+        }
+    }
+
+    fn key(&mut self) -> Option<String> {
+        if matches!(self.peek()?, b'"' | b'\'') {
+            return self.string();
+        }
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == b'_' || c == b'$' || c >= 0x80) {
+            self.pos += 1;
+        }
+        match self.pos > start {
+            true => Some(String::from_utf8_lossy(&self.text[start..self.pos]).into_owned()),
+            false => None,
+        }
+    }
+
+    /// Reads a quoted string, only unescaping what keys realistically contain.
+    fn string(&mut self) -> Option<String> {
+        let quote = self.peek()?;
+        self.pos += 1;
+        let mut out = Vec::new();
+        loop {
+            match self.peek()? {
+                c if c == quote => {
+                    self.pos += 1;
+                    return Some(String::from_utf8_lossy(&out).into_owned());
+                }
+                b'\\' => {
+                    out.push(*self.text.get(self.pos + 1)?);
+                    self.pos += 2;
+                }
+                c => {
+                    out.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Indexes TOML through the span-aware parser of the `toml` crate.
+#[cfg(feature = "toml")]
+fn toml(content: &str) -> Vec<(String, usize)> {
+    use toml::de::DeTable;
+    use toml::de::DeValue;
+
+    fn walk(value: &DeValue, path: &str, found: &mut Vec<(String, usize)>) {
+        match value {
+            DeValue::Table(table) => {
+                for (key, value) in table.iter() {
+                    let child = child_key(path, key.get_ref());
+                    found.push((child.clone(), key.span().start));
+                    walk(value.get_ref(), &child, found);
+                }
+            }
+            DeValue::Array(array) => {
+                for (index, value) in array.iter().enumerate() {
+                    let child = format!("{path}[{index}]");
+                    found.push((child.clone(), value.span().start));
+                    walk(value.get_ref(), &child, found);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut found = vec![(String::new(), 0)];
+    if let Ok(table) = DeTable::parse(content) {
+        walk(&DeValue::Table(table.into_inner()), "", &mut found);
+    }
+    found
+}
+
+/// Indexes the block style of YAML line by line: `key: value` mappings and `- item` sequences. <br/>
+/// Flow collections such as `[1, 2]` are located as a whole.
+#[cfg(feature = "yaml")]
+fn yaml(content: &str) -> Vec<(String, usize)> {
+    /// A block that lines can belong to, opened by a `key:` or a `- ` item.
+    struct Block {
+        indent: usize,
+        path: String,
+        item: bool,
+    }
+
+    let mut found = vec![(String::new(), 0)];
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut counters: HashMap<String, usize> = HashMap::new();
+    // Lines of a `|` or `>` block scalar, which are text rather than keys
+    let mut scalar_indent: Option<usize> = None;
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let text = line.trim_end_matches(['\n', '\r']);
+        let trimmed = text.trim_start_matches(' ');
+        let mut column = text.len() - trimmed.len();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(indent) = scalar_indent {
+            if column > indent {
+                continue;
+            }
+            scalar_indent = None;
+        }
+        if column == 0 && (trimmed.starts_with("---") || trimmed.starts_with("...")) {
+            blocks.clear();
+            continue;
+        }
+        let mut rest = trimmed;
+        while let Some(top) = blocks.last() {
+            let sibling = top.indent == column && !top.item && !rest.starts_with('-');
+            match top.indent > column || sibling {
+                true => blocks.pop(),
+                false => break,
+            };
+        }
+        let mut path = blocks.last().map(|block| block.path.clone()).unwrap_or_default();
+        while rest == "-" || rest.starts_with("- ") {
+            let counter = counters.entry(path.clone()).or_default();
+            path = format!("{path}[{counter}]");
+            *counter += 1;
+            found.push((path.clone(), start + column));
+            let after = rest[1..].trim_start_matches(' ');
+            column += rest.len() - after.len();
+            rest = after;
+            blocks.push(Block { indent: column, path: path.clone(), item: true });
+        }
+        let Some((key, value)) = yaml_key(rest) else {
+            continue;
+        };
+        let child = child_key(&path, &key);
+        found.push((child.clone(), start + column));
+        let value = value.split(" #").next().unwrap_or_default().trim();
+        if value.starts_with('|') || value.starts_with('>') {
+            scalar_indent = Some(column);
+        } else if value.is_empty() || value.starts_with('&') || value.starts_with('!') {
+            counters.remove(&child);
+            blocks.push(Block { indent: column, path: child, item: false });
+        }
+    }
+    found
+}
+
+/// Splits `key: value` into its key and the text after the colon.
+#[cfg(feature = "yaml")]
+fn yaml_key(text: &str) -> Option<(String, &str)> {
+    let (key, rest) = match text.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let end = text[1..].find(quote)? + 1;
+            (text[1..end].to_owned(), text[end + 1..].trim_start_matches(' '))
+        }
+        _ => {
+            let end = text
+                .match_indices(':')
+                .map(|(i, _)| i)
+                .find(|&i| matches!(text.as_bytes().get(i + 1), None | Some(b' ' | b'\t')))?;
+            (text[..end].trim_end().to_owned(), &text[end..])
+        }
+    };
+    let value = rest.strip_prefix(':')?;
+    if !value.is_empty() && !value.starts_with([' ', '\t']) {
+        return None;
+    }
+    Some((key, value))
+}
@@ -0,0 +1,196 @@
+use crate::Error;
+use crate::Format;
+use crate::format_dependant;
+use crate::value;
+use crate::value::Map;
+use crate::value::Value;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use std::path::Path;
+
+/// How versioned configs are upgraded before being read, set with `#[fast_config(version = 3)]`.
+///
+/// The version is stored under [`Versioning::key`] at the top of the file, and files without it are taken to be version 1.
+/// Older files go through every [`Migration`] between their version and [`Versioning::current`] in turn,
+/// and `save` always writes the current version.
+#[derive(Debug, Clone)]
+pub struct Versioning {
+    /// The version the config type reads and writes.
+    pub current: u32,
+    /// The key holding the version. Defaults to `version`.
+    pub key: String,
+    /// The steps from each older version to the next one, in any order.
+    pub migrations: Vec<Migration>,
+    /// Whether `load` and `new` write the upgraded file back,
+    /// keeping the original next to it as `<file>.v<version>.bak`. Defaults to `false`. <br/>
+    /// The file is only rewritten once it loaded and passed validation, and older backups are never overwritten. <br/>
+    /// Files including others are left as they are, since the files they include would keep the older keys.
+    pub rewrite: bool,
+}
+
+impl Default for Versioning {
+    fn default() -> Self {
+        Self {
+            current: 1,
+            key: "version".into(),
+            migrations: Vec::new(),
+            rewrite: false,
+        }
+    }
+}
+
+/// Upgrades a document from version `from` to the next one, registered with `#[fast_config(migrate(v1 = "..."))]`.
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub from: u32,
+    pub migrate: fn(&mut Document) -> Result<(), String>,
+}
+
+/// The untyped document a [`Migration`] works on, addressed with dotted key paths such as `servers[0].port`.
+///
+/// ```rust
+/// fn v1_to_v2(document: &mut Document) -> Result<(), String> {
+///     document.rename("log_level", "logging.level");
+///     if let Some(seconds) = document.get::<u64>("timeout") {
+///         document.set("timeout_ms", seconds * 1000)?;
+///         document.remove("timeout");
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document(pub(crate) Value);
+
+impl Document {
+    pub fn contains(&self, path: &str) -> bool {
+        self.0.get_path(path).is_some()
+    }
+
+    /// The value at `path`, or `None` if it's missing or not a `T`.
+    pub fn get<T: DeserializeOwned>(&self, path: &str) -> Option<T> {
+        value::from_value(self.0.get_path(path)?.clone()).ok()
+    }
+
+    /// Replaces the value at `path`, creating any missing tables along the way.
+    pub fn set(&mut self, path: &str, value: impl Serialize) -> Result<(), String> {
+        let value = value::to_value(&value).map_err(|e| e.to_string())?;
+        self.0.set_path_matching(path, value, false).map(|_| ()).map_err(|e| e.to_string())
+    }
+
+    /// Removes the key at `path`, returning whether it was there.
+    pub fn remove(&mut self, path: &str) -> bool {
+        self.0.remove_path(path).is_some()
+    }
+
+    /// Moves the value at `from` to `to`, which may be at another nesting level, returning whether it was there.
+    pub fn rename(&mut self, from: &str, to: &str) -> bool {
+        let Some(moved) = self.0.remove_path(from) else {
+            return false;
+        };
+        self.0.set_path_matching(to, moved, false).is_ok()
+    }
+
+    /// The whole document, for changes the methods above don't cover.
+    pub fn value(&self) -> &Value {
+        &self.0
+    }
+
+    pub fn value_mut(&mut self) -> &mut Value {
+        &mut self.0
+    }
+}
+
+/// Runs the migrations from the document's version up to the current one, returning the version it started at.
+pub(crate) fn migrate(document: &mut Value, versioning: &Versioning) -> Result<u32, Error> {
+    let stored = version(document, versioning)?;
+    if stored > versioning.current {
+        return Err(Error::Migration {
+            version: stored,
+            message: format!("it is newer than the supported version {}", versioning.current),
+        });
+    }
+    let mut upgraded = Document(std::mem::take(document));
+    for version in stored..versioning.current {
+        let migration = versioning
+            .migrations
+            .iter()
+            .find(|migration| migration.from == version)
+            .ok_or_else(|| Error::Migration {
+                version,
+                message: "no migration to the next version is registered".into(),
+            })?;
+        (migration.migrate)(&mut upgraded).map_err(|message| Error::Migration { version, message })?;
+    }
+    *document = upgraded.0;
+    if stored < versioning.current {
+        stamp(document, versioning);
+    }
+    Ok(stored)
+}
+
+/// The version stored in the document, 1 when it has none.
+fn version(document: &Value, versioning: &Versioning) -> Result<u32, Error> {
+    let Value::Table(map) = document else {
+        return Ok(1);
+    };
+    match map.get(&versioning.key) {
+        None => Ok(1),
+        Some(Value::Integer(version)) => u32::try_from(*version).map_err(|_| Error::Deserialize {
+            key: versioning.key.clone(),
+            message: format!("invalid config version {version}"),
+        }),
+        Some(other) => Err(Error::Deserialize {
+            key: versioning.key.clone(),
+            message: format!("expected the config version as an integer, found {}", other.kind()),
+        }),
+    }
+}
+
+/// Writes the current version into the document.
+pub(crate) fn stamp(document: &mut Value, versioning: &Versioning) {
+    if document.is_null() {
+        *document = Value::Table(Map::new());
+    }
+    if let Value::Table(map) = document {
+        map.insert(versioning.key.clone(), Value::Integer(versioning.current.into()));
+    }
+}
+
+/// Takes the version off the document once it's been migrated, since no field reads it.
+pub(crate) fn unstamp(document: &mut Value, versioning: &Versioning) {
+    if let Value::Table(map) = document {
+        map.remove(&versioning.key);
+    }
+}
+
+/// Writes the file's `upgraded` document over it, keeping its original `content` at version `stored` as a backup.
+pub(crate) fn rewrite(path: &Path, content: &str, format: Format, stored: u32, upgraded: &Value) -> Result<(), Error> {
+    let upgraded = format_dependant::to_string_pretty(&upgraded.in_format(format), format)?;
+    back_up(path, stored, content)?;
+    std::fs::write(path, upgraded)?;
+    Ok(())
+}
+
+/// Writes the copy of a file from before its migration next to it, such as `config.toml.v1.bak`,
+/// or `config.toml.v1.2.bak` and so on when an older backup is already there.
+fn back_up(path: &Path, version: u32, content: &str) -> Result<(), Error> {
+    let mut copy = 1;
+    loop {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        match copy {
+            1 => name.push(format!(".v{version}.bak")),
+            copy => name.push(format!(".v{version}.{copy}.bak")),
+        }
+        let backup = path.with_file_name(name);
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&backup) {
+            Ok(mut file) => {
+                std::io::Write::write_all(&mut file, content.as_bytes())?;
+                return Ok(());
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => copy += 1,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
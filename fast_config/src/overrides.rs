@@ -0,0 +1,145 @@
+use crate::ConfigFields;
+use crate::Error;
+use crate::FastConfig;
+use crate::access;
+use crate::value;
+use crate::value::Coercion;
+use crate::value::Value;
+
+use serde::de::DeserializeOwned;
+
+/// Applies `key.path=value` assignments to `config`, see [`FastConfig::apply_overrides`]. <br/>
+/// Paths are resolved like [`FastConfig::set_path`] does, so a misspelled key fails rather than being ignored.
+pub(crate) fn apply<T, I>(config: &mut T, assignments: I) -> Result<(), Error>
+where
+    T: FastConfig + ConfigFields,
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let (mut patches, mut known) = (Vec::new(), Vec::new());
+    for assignment in assignments {
+        let assignment = assignment.as_ref();
+        let Some((key, text)) = assignment.split_once('=') else {
+            return Err(Error::Override {
+                key: assignment.trim().to_owned(),
+                message: "expected an assignment in the form `key.path=value`".into(),
+            });
+        };
+        let key = key.trim();
+        value::parse_path(key).map_err(|e| Error::Override {
+            key: key.to_owned(),
+            message: e.message,
+        })?;
+        known.push(access::resolve(T::FIELDS, key)?);
+        let value = value::parse_inline(text).map_err(|e| Error::Override {
+            key: key.to_owned(),
+            message: e.message,
+        })?;
+        patches.push((key.to_owned(), value));
+    }
+    patch(config, &patches, &known)
+}
+
+/// Writes the values into `config` at their key paths, failing with [`Error::UnknownPath`] on paths that
+/// turn out not to lead to anything, and with [`Error::Override`] on values that don't fit. <br/>
+/// `known` tells, for each path, whether it ends on a field the derive describes.
+fn patch<T: FastConfig>(config: &mut T, patches: &[(String, Value)], known: &[bool]) -> Result<(), Error> {
+    if patches.is_empty() {
+        return Ok(());
+    }
+    let updated = value::patched(config, patches, false).map_err(|(i, e)| Error::Override {
+        key: match e.path.is_empty() {
+            true => patches[i].0.clone(),
+            false => e.path,
+        },
+        message: e.message,
+    })?;
+    let unknown = patches.iter().zip(known).find(|((key, _), known)| !**known && !access::written(&updated, key));
+    if let Some(((key, _), _)) = unknown {
+        return Err(access::unknown_path(key));
+    }
+    *config = updated;
+    Ok(())
+}
+
+/// A command-line flag for a field, or for a field of a `nested` struct, see `#[fast_config(clap)]`.
+#[doc(hidden)]
+#[derive(Debug, Clone)]
+pub struct Flag {
+    /// The Rust names of the fields leading to it, joined by dots, such as `server.max_connections`.
+    pub name: String,
+    /// The dotted key path the flag sets, after serde renames.
+    pub path: String,
+    pub doc: Option<&'static str>,
+    /// Whether the field is a `bool` or `Option<bool>`, whose flag may be given without a value.
+    pub switch: bool,
+    /// Reads the flag's text as a value of the field's type, see [`flag_value`].
+    pub parse: fn(&str) -> Result<Value, String>,
+}
+
+/// Reads a flag's text as a value of `T`: as the text itself when `T` takes strings, such as `String` or an enum,
+/// or else as inline TOML/JSON such as `8080` or `[a, b]`. <br/>
+/// Fails with a message clap prints along with the usage when the value doesn't fit `T`.
+#[doc(hidden)]
+pub fn flag_value<T: DeserializeOwned>(text: &str) -> Result<Value, String> {
+    let literal = Value::String(text.to_owned());
+    if value::from_value::<T>(literal.clone()).is_ok() {
+        return Ok(literal);
+    }
+    let parsed = value::parse_inline(text).map_err(|e| e.message)?;
+    // Read as text, the way `apply` reads it, so `a,b` fits a list and `yes` a boolean
+    let coercion = Coercion { paths: vec![Vec::new()], numbers: false };
+    match value::from_value_coercing::<T>(parsed.clone(), coercion) {
+        Ok(_) => Ok(parsed),
+        Err(e) => Err(e.message),
+    }
+}
+
+/// Like [`flag_value`], for fields serde reads with a function of its own, whose type can't be checked up front.
+#[doc(hidden)]
+pub fn flag_text(text: &str) -> Result<Value, String> {
+    value::parse_inline(text).map_err(|e| e.message)
+}
+
+/// Writes the values of the flags that were passed into `config`, see `#[fast_config(clap)]`.
+#[doc(hidden)]
+pub fn apply_flags<T: FastConfig>(config: &mut T, values: &[(String, Value)]) -> Result<(), Error> {
+    // The paths come from the fields themselves
+    patch(config, values, &vec![true; values.len()])
+}
+
+/// A repeatable `--set key.path=value` argument to flatten into a clap parser.
+///
+/// ```rust
+/// #[derive(clap::Parser)]
+/// struct Cli {
+///     #[command(flatten)]
+///     overrides: fast_config::SetArgs,
+/// }
+///
+/// let cli = Cli::parse();
+/// cli.overrides.apply(&mut config)?;
+/// ```
+#[cfg(feature = "clap")]
+#[derive(clap::Args, Debug, Clone, Default, PartialEq)]
+pub struct SetArgs {
+    /// Override a config value for this run, such as `--set server.port=8080`
+    #[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_assignment)]
+    pub set: Vec<String>,
+}
+
+#[cfg(feature = "clap")]
+impl SetArgs {
+    /// Applies every `--set` assignment to `config`.
+    pub fn apply<T: FastConfig + ConfigFields>(&self, config: &mut T) -> Result<(), Error> {
+        config.apply_overrides(&self.set)
+    }
+}
+
+#[cfg(feature = "clap")]
+fn parse_assignment(assignment: &str) -> Result<String, String> {
+    match assignment.split_once('=') {
+        Some((key, _)) if !key.trim().is_empty() => Ok(assignment.to_owned()),
+        _ => Err("expected an assignment in the form `key.path=value`".into()),
+    }
+}
@@ -0,0 +1,143 @@
+use crate::Error;
+use crate::FastConfig;
+use crate::Format;
+use crate::file;
+use crate::migrate;
+use crate::format_dependant;
+use crate::value::Map;
+use crate::value::Value;
+
+use std::path::Path;
+
+/// How profile-structured documents are read and written, set with `#[fast_config(profiles)]`.
+///
+/// Such documents have one top-level section per profile (`[default]`, `[dev]`, `[prod]`, ...).
+/// The selected profile is deep-merged over the default one before deserializing,
+/// and saving writes only the values that differ from the default profile into the selected section.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profiles {
+    /// The environment variable selecting the profile. Defaults to `APP_PROFILE`.
+    pub env: Option<String>,
+    /// The profile every other one is merged over. Defaults to `default`.
+    pub default: String,
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        Self {
+            env: Some("APP_PROFILE".into()),
+            default: "default".into(),
+        }
+    }
+}
+
+impl Profiles {
+    /// The profile selected by the environment variable, or the default one when it isn't set.
+    pub fn active(&self) -> String {
+        self.env
+            .as_ref()
+            .and_then(|env| std::env::var(env).ok())
+            .filter(|profile| !profile.trim().is_empty())
+            .unwrap_or_else(|| self.default.clone())
+    }
+
+    /// Deep-merges `profile` over the default section of a profile-structured document.
+    pub(crate) fn select(&self, document: Value, profile: &str) -> Result<Value, Error> {
+        let Value::Table(mut sections) = document else {
+            return Err(Error::Profile {
+                profile: profile.to_owned(),
+                message: format!("expected a table of profiles, found {}", document.kind()),
+            });
+        };
+        let mut selected = sections
+            .remove(&self.default)
+            .unwrap_or(Value::Table(Map::new()));
+        if profile != self.default {
+            let overlay = sections.remove(profile).ok_or_else(|| Error::Profile {
+                profile: profile.to_owned(),
+                message: format!("no such profile, available profiles are {}", self.names(&sections)),
+            })?;
+            selected.merge(overlay);
+        }
+        Ok(selected)
+    }
+
+    fn names(&self, sections: &Map) -> String {
+        let names = std::iter::once(&self.default)
+            .chain(sections.iter().map(|(name, _)| name))
+            .map(|name| format!("`{name}`"))
+            .collect::<Vec<_>>();
+        names.join(", ")
+    }
+}
+
+/// Lists the top-level sections of a profile-structured file,
+/// leaving out the version, `$schema` and include keys that sit next to them.
+pub(crate) fn list<T: FastConfig>(path: &Path, format: Format) -> Result<Vec<String>, Error> {
+    let content = std::fs::read_to_string(path).map_err(|e| file::in_untyped_file(e.into(), path, None, None))?;
+    let mut document = format_dependant::from_str::<Value>(&content, format)
+        .map_err(|e| file::in_untyped_file(e, path, Some(&content), None))?;
+    #[cfg(feature = "schema")]
+    crate::schema::strip(&mut document);
+    if let Some(versioning) = T::versioning() {
+        migrate::unstamp(&mut document, &versioning);
+    }
+    if let (Some(includes), Value::Table(sections)) = (T::includes(), &mut document) {
+        sections.remove(&includes.key);
+    }
+    match document {
+        Value::Table(sections) => Ok(sections.iter().map(|(name, _)| name.clone()).collect()),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Builds the document written by `save` for the given profile, keeping every other section of the existing file.
+pub(crate) fn document_for_save<T: FastConfig>(
+    config: &T,
+    path: &Path,
+    format: Format,
+    profiles: &Profiles,
+    profile: &str,
+) -> Result<Value, Error> {
+    let full = file::to_value(config).map_err(|e| Error::Profile {
+        profile: profile.to_owned(),
+        message: e.to_string(),
+    })?;
+    let mut sections = match path.exists() {
+        true => match format_dependant::from_str::<Value>(&std::fs::read_to_string(path)?, format)? {
+            Value::Table(sections) => sections,
+            _ => Map::new(),
+        },
+        false => Map::new(),
+    };
+    let section = match profile == profiles.default {
+        true => full,
+        false => {
+            let base = sections.get(&profiles.default).cloned().unwrap_or_default();
+            difference(full, &base).unwrap_or(Value::Table(Map::new()))
+        }
+    };
+    sections.insert(profile.to_owned(), section);
+    let mut document = Value::Table(sections);
+    if let Some(versioning) = T::versioning() {
+        migrate::stamp(&mut document, &versioning);
+    }
+    Ok(document)
+}
+
+/// The parts of `value` that differ from `base`, recursing into tables.
+fn difference(value: Value, base: &Value) -> Option<Value> {
+    match (value, base) {
+        (Value::Table(map), Value::Table(base)) => {
+            let changed = map
+                .into_iter()
+                .filter_map(|(key, value)| match base.get(&key) {
+                    Some(base) => difference(value, base).map(|value| (key, value)),
+                    None => Some((key, value)),
+                })
+                .collect::<Map>();
+            (!changed.is_empty()).then_some(Value::Table(changed))
+        }
+        (value, base) => (value != *base).then_some(value),
+    }
+}
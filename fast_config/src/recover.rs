@@ -0,0 +1,118 @@
+use crate::location::Location;
+use crate::location::Locations;
+use crate::value;
+use crate::value::Value;
+
+use serde::de::DeserializeOwned;
+
+use std::fmt;
+
+/// What an error-recovering load found, see [`FastConfig::from_string_recovering`](crate::FastConfig::from_string_recovering).
+#[derive(Debug)]
+pub struct Recovered<T> {
+    /// The config read with every problematic key left out, when the rest could still be read. <br/>
+    /// Keys left out fall back to their `#[serde(default)]`, so this is only `Some` when the types have defaults to fall back on.
+    pub value: Option<T>,
+    /// Every problem found, in the order they were found.
+    pub problems: Vec<Problem>,
+}
+
+impl<T> Recovered<T> {
+    /// The value, if it was read without any problem.
+    pub fn into_result(self) -> Result<T, Vec<Problem>> {
+        match (self.value, self.problems.is_empty()) {
+            (Some(value), true) => Ok(value),
+            (_, _) => Err(self.problems),
+        }
+    }
+}
+
+/// A single problem found while reading a config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// The dotted key path of the offending value, such as `servers[2].port`.
+    pub path: String,
+    pub message: String,
+    /// Where the key was found in the file, or its closest parent for missing keys.
+    pub location: Option<Location>,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(location) = self.location {
+            write!(f, "{location}: ")?;
+        }
+        match self.path.is_empty() {
+            true => write!(f, "{}", self.message),
+            false => write!(f, "`{}`: {}", self.path, self.message),
+        }
+    }
+}
+
+/// Deserializes the document again and again, leaving out whatever failed last time, until it goes through
+/// or there is nothing left to leave out. Errors caused by leaving something out aren't reported.
+pub(crate) fn deserialize<T: DeserializeOwned>(
+    mut document: Value,
+    coercion: value::Coercion,
+) -> (Option<T>, Vec<value::Error>) {
+    let mut errors = Vec::new();
+    let mut removed: Vec<String> = Vec::new();
+    loop {
+        let error = match value::from_value_coercing::<T>(document.clone(), coercion.clone()) {
+            Ok(config) => return (Some(config), errors),
+            Err(error) => error,
+        };
+        if !removed.iter().any(|path| contains(&error.path, path)) {
+            errors.push(error.clone());
+        }
+        // Missing keys can't be removed, so their parent goes instead
+        let mut path = error.path.as_str();
+        while !document.clear_path(path) {
+            match path.rfind(['.', '[']) {
+                Some(end) => path = &path[..end],
+                None if !path.is_empty() => path = "",
+                None => return (None, errors),
+            }
+        }
+        removed.push(path.to_owned());
+    }
+}
+
+/// Whether `path` is `inner` or one of its parents.
+fn contains(path: &str, inner: &str) -> bool {
+    path.is_empty()
+        || inner == path
+        || inner
+            .strip_prefix(path)
+            .is_some_and(|rest| rest.starts_with(['.', '[']))
+}
+
+/// Attaches locations to errors found in a document, looking the keys up under the given prefixes in turn
+/// (such as the profile sections the document was selected from).
+pub(crate) fn problems(errors: Vec<value::Error>, locations: Option<&Locations>, prefixes: &[String]) -> Vec<Problem> {
+    errors
+        .into_iter()
+        .map(|error| {
+            let location = locations.and_then(|locations| locate(locations, prefixes, &error.path));
+            Problem { path: error.path, message: error.message, location }
+        })
+        .collect()
+}
+
+pub(crate) fn locate(locations: &Locations, prefixes: &[String], path: &str) -> Option<Location> {
+    let candidates = prefixes.iter().map(|prefix| match (prefix.is_empty(), path.is_empty()) {
+        (true, _) => path.to_owned(),
+        (false, true) => prefix.clone(),
+        (false, false) => format!("{prefix}.{path}"),
+    });
+    let candidates = candidates.collect::<Vec<_>>();
+    candidates
+        .iter()
+        .find_map(|candidate| locations.get(candidate))
+        .or_else(|| candidates.first().and_then(|candidate| locations.closest(candidate)))
+}
+
+/// Puts problems in reading order, since formats that don't keep the key order find them in another one.
+pub(crate) fn sort(problems: &mut [Problem]) {
+    problems.sort_by_key(|problem| problem.location.map_or((usize::MAX, 0), |l| (l.line, l.column)));
+}
@@ -0,0 +1,149 @@
+//! A Markdown reference of every field of a config, see [`FastConfig::reference`](crate::FastConfig::reference).
+
+use crate::ConfigFields;
+use crate::Error;
+use crate::FastConfig;
+use crate::env;
+use crate::fields;
+use crate::file;
+use crate::template;
+use crate::value;
+
+/// Every field of a config, with what a reader of its documentation needs to know about it.
+///
+/// ```rust
+/// let markdown = MyConfig::reference()?.to_markdown(MarkdownStyle::Table);
+/// assert_eq!(std::fs::read_to_string("docs/configuration.md")?, markdown);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub fields: Vec<FieldReference>,
+}
+
+/// A field of a [`Reference`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldReference {
+    /// The dotted key path of the field, such as `server.port`.
+    pub path: String,
+    /// The field's Rust type, such as `Option<u16>`.
+    pub type_name: String,
+    /// The default value written inline, such as `8080` or `["info"]`. <br/>
+    /// `None` for fields left unset by default and for nested tables, whose fields have their own defaults.
+    pub default: Option<String>,
+    pub doc: Option<String>,
+    /// The environment variable overriding the field, either pinned to it or made from the prefix.
+    pub env: Option<String>,
+    /// The allowed values and rules of the field, such as `Range: 1 to 65535`.
+    pub rules: Vec<String>,
+}
+
+/// How [`Reference::to_markdown`] lays out the fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkdownStyle {
+    /// A single table with a row per field.
+    Table,
+    /// A `##` heading per field, followed by its doc comment and a list of its details.
+    Sections,
+}
+
+impl Reference {
+    pub fn to_markdown(&self, style: MarkdownStyle) -> String {
+        match style {
+            MarkdownStyle::Table => self.table(),
+            MarkdownStyle::Sections => self.sections(),
+        }
+    }
+
+    fn table(&self) -> String {
+        let mut out = String::from("| Key | Type | Default | Environment | Description |\n|---|---|---|---|---|\n");
+        for field in &self.fields {
+            // Doc paragraphs and rules are kept apart with line breaks, as a row can't span lines
+            let mut description = field.doc.as_deref().map(paragraphs).unwrap_or_default();
+            description.extend(field.rules.iter().cloned());
+            let cells = [
+                code(&field.path),
+                code(&field.type_name),
+                field.default.as_deref().map(code).unwrap_or_default(),
+                field.env.as_deref().map(code).unwrap_or_default(),
+                description.join("<br>"),
+            ];
+            let cells = cells.iter().map(|cell| cell.replace('|', "\\|")).collect::<Vec<_>>();
+            out.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+        out
+    }
+
+    fn sections(&self) -> String {
+        let mut out = String::new();
+        for field in &self.fields {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&format!("## {}\n\n", code(&field.path)));
+            if let Some(doc) = &field.doc {
+                out.push_str(&format!("{}\n\n", doc.trim_end()));
+            }
+            out.push_str(&format!("- Type: {}\n", code(&field.type_name)));
+            if let Some(default) = &field.default {
+                out.push_str(&format!("- Default: {}\n", code(default)));
+            }
+            if let Some(env) = &field.env {
+                out.push_str(&format!("- Environment: {}\n", code(env)));
+            }
+            for rule in &field.rules {
+                out.push_str(&format!("- {rule}\n"));
+            }
+        }
+        out
+    }
+}
+
+/// Gathers the fields of the config with their defaults, environment variables and rules.
+pub(crate) fn build<T: FastConfig + ConfigFields + Default>() -> Result<Reference, Error> {
+    let defaults = file::to_value(&T::default()).map_err(std::io::Error::other)?;
+    let (_, variants) = value::sample::<T>();
+    let constraints = T::constraints();
+    let flat = fields::flatten(T::FIELDS);
+    let fields = flat
+        .iter()
+        .map(|(path, field)| {
+            let prefix = format!("{path}.");
+            let table = flat.iter().any(|(other, _)| other.starts_with(&prefix));
+            let default = defaults.get_path(path).filter(|value| !table && !value.is_null()).map(value::to_inline);
+            let prefixed = T::env_prefix().filter(|_| !table).map(|prefix| env::var_name(prefix, path));
+            let variants = variants.iter().filter(|(variant, _)| variant == path);
+            let variants = variants.map(|(_, names)| format!("Allowed values: {}", names.join(", ")));
+            let rules = constraints.iter().filter_map(template::field_note);
+            let rules = rules.filter(|(field, _)| field == path).map(|(_, note)| note);
+            FieldReference {
+                path: path.clone(),
+                type_name: field.type_name.to_owned(),
+                default,
+                doc: field.doc.map(str::to_owned),
+                env: field.attributes.env.map(str::to_owned).or(prefixed),
+                rules: variants.chain(rules).collect(),
+            }
+        })
+        .collect();
+    Ok(Reference { fields })
+}
+
+/// The paragraphs of a doc comment, with the lines of each joined back together.
+fn paragraphs(doc: &str) -> Vec<String> {
+    doc.split("\n\n")
+        .map(|paragraph| paragraph.lines().map(str::trim).collect::<Vec<_>>().join(" "))
+        .filter(|paragraph| !paragraph.is_empty())
+        .collect()
+}
+
+/// Puts text in a code span, with enough backticks around it to hold the ones inside.
+fn code(text: &str) -> String {
+    let mut fence = String::from("`");
+    while text.contains(fence.as_str()) {
+        fence.push('`');
+    }
+    match text.starts_with('`') || text.ends_with('`') {
+        true => format!("{fence} {text} {fence}"),
+        false => format!("{fence}{text}{fence}"),
+    }
+}
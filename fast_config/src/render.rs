@@ -0,0 +1,156 @@
+//! Turning errors into rustc-style reports for the people editing the config.
+
+use crate::Error;
+use crate::FileError;
+use crate::value::Value;
+
+use std::fmt::Write;
+
+/// Renders the error with the offending line of the file, a caret under the bad token,
+/// the type that was expected and a suggestion for misspelled keys, when known.
+pub(crate) fn render(error: &Error) -> String {
+    let mut out = format!("error: {}\n", headline(error));
+    let Error::File(file) = error else {
+        return out;
+    };
+    let FileError { path, location, snippet, help, .. } = file.as_ref();
+    let position = location.map(|l| format!(":{l}")).unwrap_or_default();
+    let _ = writeln!(out, "  --> {}{position}", path.display());
+    if let (Some(location), Some(snippet)) = (location, snippet) {
+        let number = location.line.to_string();
+        let gutter = " ".repeat(number.len());
+        let (start, width) = token(snippet, location.column - 1, is_type_error(error));
+        let _ = writeln!(out, "{gutter} |");
+        let _ = writeln!(out, "{number} | {snippet}");
+        let _ = writeln!(out, "{gutter} | {}{}", " ".repeat(start), "^".repeat(width.max(1)));
+    }
+    if let Some(expected) = expected(&headline(error)) {
+        let _ = writeln!(out, "  = expected: {expected}");
+    }
+    if let Some(help) = help {
+        let _ = writeln!(out, "  = help: {help}");
+    }
+    out
+}
+
+/// The message of the error without the snippet or position some format crates add, since the report shows both.
+fn headline(error: &Error) -> String {
+    let message = error.inner().to_string();
+    let message = message.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or_default();
+    match message.rsplit_once(" at line ") {
+        Some((message, position)) if position.contains(" column ") => message.to_owned(),
+        _ => message.to_owned(),
+    }
+}
+
+fn is_type_error(error: &Error) -> bool {
+    let message = headline(error);
+    message.contains("invalid type") || message.contains("invalid value") || message.contains("invalid length")
+}
+
+/// The character range to underline in `line`, starting from the key at `column`. <br/>
+/// For type errors, the value after the key is underlined instead.
+fn token(line: &str, column: usize, value: bool) -> (usize, usize) {
+    let chars = line.chars().collect::<Vec<_>>();
+    let mut start = column.min(chars.len());
+    let mut end = scalar_end(&chars, start);
+    if value {
+        let mut after = end;
+        while after < chars.len() && matches!(chars[after], ' ' | '\t' | ':' | '=') {
+            after += 1;
+        }
+        if after < chars.len() && after > end {
+            start = after;
+            end = scalar_end(&chars, start);
+        }
+    }
+    (start, end - start)
+}
+
+/// Where the key or scalar starting at `start` ends, quotes included.
+fn scalar_end(chars: &[char], start: usize) -> usize {
+    match chars.get(start) {
+        Some(&quote @ ('"' | '\'')) => {
+            let mut end = start + 1;
+            while end < chars.len() && chars[end] != quote {
+                end += if chars[end] == '\\' { 2 } else { 1 };
+            }
+            (end + 1).min(chars.len())
+        }
+        Some('[' | '{') => chars.len(),
+        _ => {
+            let mut end = start;
+            while end < chars.len() && !matches!(chars[end], ' ' | '\t' | ':' | '=' | ',' | '}' | ']' | '#') {
+                end += 1;
+            }
+            end
+        }
+    }
+}
+
+/// The expected type serde mentions in messages such as `invalid type: string "a", expected u16`.
+fn expected(message: &str) -> Option<&str> {
+    let (_, expected) = message.rsplit_once(", expected ")?;
+    if expected.starts_with('`') || expected.starts_with("one of") {
+        return None;
+    }
+    Some(expected.trim())
+}
+
+/// Suggests the known key a misspelled one was meant to be. <br/>
+/// For missing keys, also returns the path of the misspelled key found in their place.
+pub(crate) fn help(message: &str, key: Option<&str>, document: Option<&Value>) -> Option<(String, Option<String>)> {
+    if let Some(rest) = message.split("unknown field `").nth(1) {
+        let (unknown, expected) = rest.split_once('`')?;
+        let known = expected.split('`').skip(1).step_by(2);
+        let suggestion = closest(unknown, known)?;
+        return Some((format!("did you mean `{suggestion}`?"), None));
+    }
+    let missing = message.split("missing field `").nth(1)?.split('`').next()?;
+    let parent = key?.rsplit_once(['.', '[']).map(|(parent, _)| parent);
+    let table = match parent {
+        Some(parent) => document?.get_path(parent)?,
+        None => document?,
+    };
+    let Value::Table(table) = table else {
+        return None;
+    };
+    let found = closest(missing, table.iter().map(|(key, _)| key.as_str()))?;
+    let path = match parent {
+        Some(parent) => format!("{parent}.{found}"),
+        None => found.to_owned(),
+    };
+    Some((format!("found `{found}`, did you mean `{missing}`?"), Some(path)))
+}
+
+/// The candidate closest to `name`, if it's close enough to be a typo.
+pub(crate) fn closest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    candidates
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (distance(name, candidate), candidate))
+        .filter(|(distance, candidate)| *distance <= (name.chars().count().max(candidate.chars().count()) / 3).max(1))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Edit distance where swapping two neighbouring characters, as in `prot` → `port`, counts as a single edit.
+fn distance(a: &str, b: &str) -> usize {
+    let (a, b) = (a.chars().collect::<Vec<_>>(), b.chars().collect::<Vec<_>>());
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
@@ -0,0 +1,391 @@
+//! Checking documents against a JSON Schema.
+//!
+//! Covers the keywords that describe config files: types, `enum` and `const`, number and length bounds, `pattern`,
+//! `properties` and `required`, `items` and `prefixItems`, the `allOf`/`anyOf`/`oneOf`/`not` and `if`/`then`/`else`
+//! combinators, and `$ref`s within the schema. <br/>
+//! Annotations such as `format` and `description` aren't checked, and `pattern` needs the `regex` feature.
+
+use crate::render;
+use crate::validate::Violations;
+use crate::value;
+use crate::value::Datetime;
+use crate::value::Segment;
+use crate::value::Value;
+
+use serde_json::Value as Json;
+
+/// How many `$ref`s deep a schema may point before the rest is taken as valid, so that cycles end.
+const MAX_REFS: usize = 64;
+
+/// Every way the document doesn't match the schema.
+pub(crate) fn check(schema: &Json, document: &Value) -> Violations {
+    let mut violations = Violations::new();
+    Checker { root: schema }.check(schema, document, &mut Vec::new(), 0, &mut violations);
+    violations
+}
+
+struct Checker<'a> {
+    root: &'a Json,
+}
+
+impl Checker<'_> {
+    fn check(&self, schema: &Json, value: &Value, path: &mut Vec<Segment>, refs: usize, violations: &mut Violations) {
+        let here = value::render_path(path);
+        let schema = match schema {
+            Json::Bool(true) => return,
+            Json::Bool(false) => return violations.push(here, "is not allowed"),
+            Json::Object(schema) => schema,
+            _ => return,
+        };
+        if let Some(reference) = schema.get("$ref").and_then(Json::as_str)
+            && refs < MAX_REFS
+            && let Some(target) = self.resolve(reference)
+        {
+            self.check(target, value, path, refs + 1, violations);
+        }
+        if let Some(types) = schema.get("type")
+            && !types_of(types).any(|kind| is_kind(value, kind))
+        {
+            let expected = types_of(types).map(describe).collect::<Vec<_>>().join(" or ");
+            // Nothing else about a value of the wrong type is worth reporting
+            return violations.push(here, format!("expected {expected}, found {}", value.kind()));
+        }
+        if let Some(allowed) = schema.get("enum").and_then(Json::as_array)
+            && !allowed.iter().any(|allowed| equals(value, allowed))
+        {
+            let allowed = allowed.iter().map(display).collect::<Vec<_>>().join(", ");
+            violations.push(&here, format!("must be one of {allowed}"));
+        }
+        if let Some(expected) = schema.get("const")
+            && !equals(value, expected)
+        {
+            violations.push(&here, format!("must be {}", display(expected)));
+        }
+        match value {
+            Value::Integer(_) | Value::Float(_) => {
+                for message in check_number(schema, number(value)) {
+                    violations.push(&here, message);
+                }
+            }
+            Value::String(string) | Value::Datetime(Datetime(string)) => {
+                if let Some(message) = check_length(schema, "minLength", "maxLength", string.chars().count()) {
+                    violations.push(&here, message);
+                }
+                if let Some(message) = check_pattern(schema, string) {
+                    violations.push(&here, message);
+                }
+            }
+            Value::Array(items) => {
+                if let Some(message) = check_length(schema, "minItems", "maxItems", items.len()) {
+                    violations.push(&here, message);
+                }
+                if schema.get("uniqueItems") == Some(&Json::Bool(true))
+                    && items.iter().enumerate().any(|(i, item)| items[..i].contains(item))
+                {
+                    violations.push(&here, "must not contain duplicates");
+                }
+                self.check_items(schema, items, path, refs, violations);
+            }
+            Value::Table(map) => {
+                if let Some(message) = check_length(schema, "minProperties", "maxProperties", map.len()) {
+                    violations.push(&here, message);
+                }
+                self.check_properties(schema, map, path, refs, violations);
+            }
+            Value::Null | Value::Bool(_) => {}
+        }
+        self.check_combinators(schema, value, path, refs, violations);
+    }
+
+    fn check_items(
+        &self,
+        schema: &serde_json::Map<String, Json>,
+        items: &[Value],
+        path: &mut Vec<Segment>,
+        refs: usize,
+        violations: &mut Violations,
+    ) {
+        // Before 2020-12, `items` as a list did what `prefixItems` does now
+        let (prefix, rest) = match (schema.get("prefixItems"), schema.get("items")) {
+            (Some(Json::Array(prefix)), rest) => (prefix.as_slice(), rest),
+            (None, Some(Json::Array(prefix))) => (prefix.as_slice(), schema.get("additionalItems")),
+            (_, rest) => (&[][..], rest),
+        };
+        for (index, item) in items.iter().enumerate() {
+            let Some(item_schema) = prefix.get(index).or(rest) else {
+                continue;
+            };
+            path.push(Segment::Index(index));
+            self.check(item_schema, item, path, refs, violations);
+            path.pop();
+        }
+        if let Some(contains) = schema.get("contains")
+            && !items.iter().any(|item| self.matches(contains, item, path, refs))
+        {
+            violations.push(value::render_path(path), "must contain a matching item");
+        }
+    }
+
+    fn check_properties(
+        &self,
+        schema: &serde_json::Map<String, Json>,
+        map: &value::Map,
+        path: &mut Vec<Segment>,
+        refs: usize,
+        violations: &mut Violations,
+    ) {
+        let properties = schema.get("properties").and_then(Json::as_object);
+        let patterns = schema.get("patternProperties").and_then(Json::as_object);
+        for required in schema.get("required").and_then(Json::as_array).into_iter().flatten() {
+            if let Some(key) = required.as_str()
+                && !map.contains_key(key)
+            {
+                path.push(Segment::Key(key.to_owned()));
+                violations.push(value::render_path(path), "is missing");
+                path.pop();
+            }
+        }
+        for (key, item) in map.iter() {
+            path.push(Segment::Key(key.clone()));
+            let mut known = false;
+            if let Some(property) = properties.and_then(|properties| properties.get(key)) {
+                self.check(property, item, path, refs, violations);
+                known = true;
+            }
+            for (pattern, property) in patterns.into_iter().flatten() {
+                match matches_pattern(pattern, key) {
+                    Some(true) => self.check(property, item, path, refs, violations),
+                    Some(false) => continue,
+                    // Without the `regex` feature, keys that might match a pattern aren't reported as unknown
+                    None => {}
+                }
+                known = true;
+            }
+            match schema.get("additionalProperties") {
+                _ if known => {}
+                Some(Json::Bool(false)) => {
+                    let fields = properties.into_iter().flatten().map(|(field, _)| field.as_str());
+                    let message = match render::closest(key, fields) {
+                        Some(field) => format!("unknown key, did you mean `{field}`?"),
+                        None => "unknown key".to_owned(),
+                    };
+                    violations.push(value::render_path(path), message);
+                }
+                Some(additional) => self.check(additional, item, path, refs, violations),
+                None => {}
+            }
+            path.pop();
+        }
+    }
+
+    fn check_combinators(
+        &self,
+        schema: &serde_json::Map<String, Json>,
+        value: &Value,
+        path: &mut Vec<Segment>,
+        refs: usize,
+        violations: &mut Violations,
+    ) {
+        for branch in schema.get("allOf").and_then(Json::as_array).into_iter().flatten() {
+            self.check(branch, value, path, refs, violations);
+        }
+        if let Some(branches) = schema.get("anyOf").and_then(Json::as_array)
+            && !branches.iter().any(|branch| self.matches(branch, value, path, refs))
+        {
+            self.explain(branches, value, path, refs, violations);
+        }
+        if let Some(branches) = schema.get("oneOf").and_then(Json::as_array) {
+            match branches.iter().filter(|branch| self.matches(branch, value, path, refs)).count() {
+                0 => self.explain(branches, value, path, refs, violations),
+                1 => {}
+                _ => violations.push(value::render_path(path), "matches more than one of the allowed schemas"),
+            }
+        }
+        if let Some(not) = schema.get("not")
+            && self.matches(not, value, path, refs)
+        {
+            violations.push(value::render_path(path), "must not match the `not` schema");
+        }
+        if let Some(condition) = schema.get("if") {
+            let branch = match self.matches(condition, value, path, refs) {
+                true => schema.get("then"),
+                false => schema.get("else"),
+            };
+            if let Some(branch) = branch {
+                self.check(branch, value, path, refs, violations);
+            }
+        }
+    }
+
+    /// Reports why none of the branches matched. When only one of them takes values of this type,
+    /// such as the non-null branch of an `Option`, what's wrong according to it is more useful than a plain mismatch.
+    fn explain(
+        &self,
+        branches: &[Json],
+        value: &Value,
+        path: &mut Vec<Segment>,
+        refs: usize,
+        violations: &mut Violations,
+    ) {
+        let mut candidates = branches.iter().filter(|branch| match self.target(branch, refs).get("type") {
+            Some(types) => types_of(types).any(|kind| is_kind(value, kind)),
+            None => true,
+        });
+        match (candidates.next(), candidates.next()) {
+            (Some(branch), None) => self.check(branch, value, path, refs, violations),
+            _ => violations.push(value::render_path(path), "does not match any of the allowed schemas"),
+        }
+    }
+
+    fn matches(&self, schema: &Json, value: &Value, path: &mut Vec<Segment>, refs: usize) -> bool {
+        let mut violations = Violations::new();
+        self.check(schema, value, path, refs, &mut violations);
+        violations.is_empty()
+    }
+
+    /// The schema a `$ref` points to, for references within the schema such as `#/$defs/Server`.
+    fn resolve(&self, reference: &str) -> Option<&Json> {
+        let pointer = reference.strip_prefix('#')?;
+        let pointer = pointer.replace("%25", "%").replace("%22", "\"");
+        self.root.pointer(&pointer)
+    }
+
+    /// The schema a branch stands for, following its `$ref` if it only is one.
+    fn target<'s>(&'s self, schema: &'s Json, refs: usize) -> &'s Json {
+        match schema.get("$ref").and_then(Json::as_str) {
+            Some(reference) if refs < MAX_REFS => self.resolve(reference).unwrap_or(schema),
+            _ => schema,
+        }
+    }
+}
+
+fn check_number(schema: &serde_json::Map<String, Json>, n: f64) -> Vec<String> {
+    let bound = |key: &str| schema.get(key).and_then(Json::as_f64);
+    let mut messages = Vec::new();
+    if bound("minimum").is_some_and(|min| n < min) || bound("maximum").is_some_and(|max| n > max) {
+        messages.push(bounds("must be", schema.get("minimum"), schema.get("maximum")));
+    }
+    if bound("exclusiveMinimum").is_some_and(|min| n <= min) {
+        messages.push(format!("must be greater than {}", schema["exclusiveMinimum"]));
+    }
+    if bound("exclusiveMaximum").is_some_and(|max| n >= max) {
+        messages.push(format!("must be less than {}", schema["exclusiveMaximum"]));
+    }
+    if let Some(factor) = bound("multipleOf")
+        && factor > 0.0
+        && ((n / factor).round() * factor - n).abs() > f64::EPSILON * n.abs().max(1.0)
+    {
+        messages.push(format!("must be a multiple of {}", schema["multipleOf"]));
+    }
+    messages
+}
+
+fn check_length(schema: &serde_json::Map<String, Json>, min_key: &str, max_key: &str, length: usize) -> Option<String> {
+    let bound = |key: &str| schema.get(key).and_then(Json::as_u64).map(|bound| bound as usize);
+    match bound(min_key).is_some_and(|min| length < min) || bound(max_key).is_some_and(|max| length > max) {
+        true => Some(bounds("length must be", schema.get(min_key), schema.get(max_key))),
+        false => None,
+    }
+}
+
+#[cfg(feature = "regex")]
+fn check_pattern(schema: &serde_json::Map<String, Json>, string: &str) -> Option<String> {
+    let pattern = schema.get("pattern").and_then(Json::as_str)?;
+    match regex::Regex::new(pattern) {
+        Ok(regex) if regex.is_match(string) => None,
+        Ok(_) => Some(format!("must match `{pattern}`")),
+        Err(_) => Some(format!("has an invalid pattern `{pattern}`")),
+    }
+}
+
+#[cfg(not(feature = "regex"))]
+fn check_pattern(_schema: &serde_json::Map<String, Json>, _string: &str) -> Option<String> {
+    None
+}
+
+/// Phrases bounds the way field rules do, as in `must be between 1 and 65535`.
+fn bounds(prefix: &str, min: Option<&Json>, max: Option<&Json>) -> String {
+    match (min, max) {
+        (Some(min), Some(max)) => format!("{prefix} between {min} and {max}"),
+        (Some(min), None) => format!("{prefix} at least {min}"),
+        (None, Some(max)) => format!("{prefix} at most {max}"),
+        (None, None) => unreachable!("only called when a bound was crossed"),
+    }
+}
+
+fn types_of(types: &Json) -> impl Iterator<Item = &str> {
+    let types = match types {
+        Json::Array(types) => types.as_slice(),
+        single => std::slice::from_ref(single),
+    };
+    types.iter().filter_map(Json::as_str)
+}
+
+fn is_kind(value: &Value, kind: &str) -> bool {
+    match (kind, value) {
+        ("null", Value::Null) | ("boolean", Value::Bool(_)) | ("string", Value::String(_)) => true,
+        // Datetimes are written as strings in formats other than TOML, where schemas see them
+        ("string", Value::Datetime(_)) => true,
+        ("integer", Value::Integer(_)) | ("number", Value::Integer(_) | Value::Float(_)) => true,
+        ("integer", Value::Float(float)) => float.fract() == 0.0,
+        ("array", Value::Array(_)) | ("object", Value::Table(_)) => true,
+        _ => false,
+    }
+}
+
+/// Names a JSON Schema type the way [`Value::kind`] names values.
+fn describe(kind: &str) -> &str {
+    match kind {
+        "boolean" => "a boolean",
+        "integer" => "an integer",
+        "number" => "a number",
+        "string" => "a string",
+        "array" => "a list",
+        "object" => "a table",
+        other => other,
+    }
+}
+
+fn number(value: &Value) -> f64 {
+    match value {
+        Value::Integer(integer) => *integer as f64,
+        Value::Float(float) => *float,
+        _ => f64::NAN,
+    }
+}
+
+fn equals(value: &Value, json: &Json) -> bool {
+    match (value, json) {
+        (Value::Null, Json::Null) => true,
+        (Value::Bool(a), Json::Bool(b)) => a == b,
+        (Value::Integer(a), Json::Number(b)) if b.is_i64() || b.is_u64() => {
+            b.as_i64().map(i128::from).or(b.as_u64().map(i128::from)) == Some(*a)
+        }
+        (Value::Integer(_) | Value::Float(_), Json::Number(b)) => b.as_f64() == Some(number(value)),
+        (Value::String(a) | Value::Datetime(Datetime(a)), Json::String(b)) => a == b,
+        (Value::Array(a), Json::Array(b)) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| equals(a, b)),
+        (Value::Table(a), Json::Object(b)) => {
+            a.len() == b.len() && b.iter().all(|(key, b)| a.get(key).is_some_and(|a| equals(a, b)))
+        }
+        _ => false,
+    }
+}
+
+/// Shows a value from the schema in backticks, without the quotes around strings.
+fn display(json: &Json) -> String {
+    match json {
+        Json::String(string) => format!("`{string}`"),
+        other => format!("`{other}`"),
+    }
+}
+
+/// Whether the key matches a `patternProperties` pattern, or `None` when that can't be told.
+#[cfg(feature = "regex")]
+fn matches_pattern(pattern: &str, key: &str) -> Option<bool> {
+    regex::Regex::new(pattern).ok().map(|regex| regex.is_match(key))
+}
+
+#[cfg(not(feature = "regex"))]
+fn matches_pattern(_pattern: &str, _key: &str) -> Option<bool> {
+    None
+}
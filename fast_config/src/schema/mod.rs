@@ -0,0 +1,269 @@
+mod check;
+
+use crate::Error;
+use crate::FastConfig;
+use crate::Format;
+use crate::file;
+use crate::format_dependant;
+use crate::location::Locations;
+use crate::recover;
+use crate::recover::Problem;
+use crate::validate::Bound;
+use crate::validate::Constraint;
+use crate::validate::Rule;
+use crate::validate::Violations;
+use crate::value::Map;
+use crate::value::Value;
+
+use schemars::JsonSchema;
+use schemars::generate::SchemaSettings;
+use serde_json::Value as Json;
+
+use std::path::Path;
+use std::path::PathBuf;
+
+/// A JSON Schema that config files are checked against without their Rust type,
+/// such as one written by [`FastConfig::save_with_schema`] or by hand.
+///
+/// ```rust
+/// let schema = Schema::from_file("config.schema.json")?;
+/// for problem in schema.problems(&std::fs::read_to_string("config.toml")?, Format::TOML)? {
+///     println!("{problem}");   // 3:8: `server.port`: must be between 1 and 65535
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema(serde_json::Value);
+
+impl Schema {
+    pub fn new(schema: serde_json::Value) -> Self {
+        Self(schema)
+    }
+
+    /// Reads a schema written as JSON.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let read = || -> Result<Self, Error> {
+            let content = std::fs::read_to_string(path)?;
+            let schema = serde_json::from_str(&content).map_err(std::io::Error::other)?;
+            Ok(Self(schema))
+        };
+        read().map_err(|e| file::in_untyped_file(e, path, None, None))
+    }
+
+    /// The schema of a config type, see [`FastConfig::json_schema`].
+    pub fn of<T: FastConfig + JsonSchema>() -> Self {
+        Self(generate::<T>())
+    }
+
+    pub fn as_json(&self) -> &serde_json::Value {
+        &self.0
+    }
+
+    /// Every way the content doesn't match the schema, in the order they appear in the file,
+    /// each with the key path and where it is. <br/>
+    /// Only content that can't be parsed at all is returned as `Err`.
+    pub fn problems(&self, content: &str, format: Format) -> Result<Vec<Problem>, Error> {
+        let mut document = format_dependant::from_str::<Value>(content, format)?;
+        strip(&mut document);
+        let violations = check::check(&self.0, &document);
+        let locations = Locations::new(content, format);
+        let mut problems = violations
+            .iter()
+            .map(|violation| Problem {
+                path: violation.path.clone(),
+                message: violation.message.clone(),
+                location: recover::locate(&locations, &[String::new()], &violation.path),
+            })
+            .collect::<Vec<_>>();
+        recover::sort(&mut problems);
+        Ok(problems)
+    }
+
+    /// Checks the content against the schema, failing with [`Error::Validation`] listing every violation.
+    pub fn validate_str(&self, content: &str, format: Format) -> Result<(), Error> {
+        let mut document = format_dependant::from_str::<Value>(content, format)?;
+        strip(&mut document);
+        check::check(&self.0, &document).into_result().map_err(Error::Validation)
+    }
+
+    /// Checks a file against the schema. Fails with [`Error::File`] pointing at the first violation
+    /// in the file, the same way loading the config type would, and listing every violation in its source.
+    pub fn validate_file(&self, path: impl AsRef<Path>, format: Format) -> Result<(), Error> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| file::in_untyped_file(e.into(), path, None, None))?;
+        let problems = self.problems(&content, format).map_err(|e| file::in_untyped_file(e, path, Some(&content), None))?;
+        let Some(first) = problems.first().cloned() else {
+            return Ok(());
+        };
+        let mut violations = Violations::new();
+        for problem in problems {
+            violations.push(problem.path, problem.message);
+        }
+        Err(file::in_untyped_file(Error::Validation(violations), path, Some(&content), Some(first)))
+    }
+}
+
+/// The JSON Schema of what the config type reads, with the field rules put on the properties they constrain.
+pub(crate) fn generate<T: FastConfig + JsonSchema>() -> Json {
+    let mut schema = SchemaSettings::draft2020_12()
+        .for_deserialize()
+        .with(|settings| settings.inline_subschemas = true)
+        .into_generator()
+        .into_root_schema_for::<T>()
+        .to_value();
+    for constraint in T::constraints() {
+        if let Some(node) = property(&mut schema, &constraint.path) {
+            constrain(node, constraint);
+        }
+    }
+    schema
+}
+
+/// Where the schema of a config file goes, such as `config.schema.json` for `config.toml`.
+pub(crate) fn path_for(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{stem}.schema.json"))
+}
+
+/// Writes the schema next to the config, then the config itself pointing at it.
+pub(crate) fn save<T: FastConfig + JsonSchema>(config: &T, path: &Path, format: Format) -> Result<(), Error> {
+    let schema_path = path_for(path);
+    let schema = serde_json::to_string_pretty(&generate::<T>()).map_err(std::io::Error::other)?;
+    if let Some(parent_dir) = schema_path.parent() {
+        std::fs::create_dir_all(parent_dir)?;
+    }
+    std::fs::write(&schema_path, schema)?;
+    // The schema describes a single profile, not the file holding all of them
+    if T::profiles().is_some() {
+        return config.save_pretty(path, format);
+    }
+    let name = schema_path.file_name().unwrap_or_default().to_string_lossy();
+    file::write_referencing(config, path, format, &format!("./{name}"))
+}
+
+/// Serializes the document with a reference to its schema, in the way editors of the format look for it.
+pub(crate) fn referencing(document: Value, format: Format, schema: &str) -> Result<String, Error> {
+    let header: Option<String> = match format {
+        #[cfg(feature = "json")]
+        Format::JSON => None,
+        #[cfg(feature = "json5")]
+        Format::JSON5 => None,
+        #[cfg(feature = "toml")]
+        Format::TOML => Some(format!("#:schema {schema}\n")),
+        #[cfg(feature = "yaml")]
+        Format::YAML => Some(format!("# yaml-language-server: $schema={schema}\n")),
+    };
+    let document = match (&header, document) {
+        (None, Value::Table(map)) => {
+            let reference = (String::from("$schema"), Value::String(schema.to_owned()));
+            Value::Table(std::iter::once(reference).chain(map).collect::<Map>())
+        }
+        (_, document) => document,
+    };
+    let content = format_dependant::to_string_pretty(&document.in_format(format), format)?;
+    Ok(header.unwrap_or_default() + &content)
+}
+
+/// Takes off the `$schema` key a file may start with to point editors at its schema.
+pub(crate) fn strip(document: &mut Value) {
+    if let Value::Table(map) = document {
+        map.remove("$schema");
+    }
+}
+
+/// The schema of the property at a dotted key path, where `key[]` stands for the items of a list
+/// and `*` for the values of a map.
+fn property<'a>(schema: &'a mut Json, path: &str) -> Option<&'a mut Json> {
+    path.split('.').try_fold(schema, |node, key| {
+        if key == "*" {
+            return non_null(node).get_mut("additionalProperties");
+        }
+        match key.strip_suffix("[]") {
+            Some(key) => non_null(non_null(node).get_mut("properties")?.get_mut(key)?).get_mut("items"),
+            None => non_null(node).get_mut("properties")?.get_mut(key),
+        }
+    })
+}
+
+/// The schema of the value inside an `Option`, which is written as a choice between it and `null`.
+fn non_null(node: &mut Json) -> &mut Json {
+    let is_null = |branch: &Json| branch.get("type").and_then(Json::as_str) == Some("null");
+    let choice = ["anyOf", "oneOf"].into_iter().find_map(|key| {
+        let branches = node.get(key)?.as_array()?;
+        match branches.len() == 2 && branches.iter().any(is_null) {
+            true => Some((key, branches.iter().position(|branch| !is_null(branch))?)),
+            false => None,
+        }
+    });
+    match choice {
+        Some((key, index)) => &mut node[key][index],
+        None => node,
+    }
+}
+
+/// The kind of value a schema describes, leaving out `null`.
+fn kind(node: &Json) -> Option<&str> {
+    match node.get("type")? {
+        Json::String(kind) => Some(kind),
+        Json::Array(kinds) => kinds.iter().filter_map(Json::as_str).find(|kind| *kind != "null"),
+        _ => None,
+    }
+}
+
+/// Puts a field rule on the schema of the field, when JSON Schema can express it.
+fn constrain(node: &mut Json, constraint: Constraint) {
+    let node = non_null(node);
+    let (min_key, max_key) = match kind(node) {
+        Some("string") => ("minLength", "maxLength"),
+        Some("array") => ("minItems", "maxItems"),
+        Some("object") => ("minProperties", "maxProperties"),
+        _ => ("", ""),
+    };
+    let Some(node) = node.as_object_mut() else {
+        return;
+    };
+    match constraint.rule {
+        Rule::Range { min, max } => {
+            if let Some(min) = min {
+                node.insert("minimum".into(), number(min));
+            }
+            if let Some(max) = max {
+                node.insert("maximum".into(), number(max));
+            }
+        }
+        Rule::Length { min, max } if !min_key.is_empty() => {
+            if let Some(min) = min {
+                node.insert(min_key.into(), min.into());
+            }
+            if let Some(max) = max {
+                node.insert(max_key.into(), max.into());
+            }
+        }
+        Rule::NonEmpty if !min_key.is_empty() => {
+            node.insert(min_key.into(), 1.into());
+        }
+        Rule::Regex(pattern) => {
+            node.insert("pattern".into(), pattern.into());
+        }
+        Rule::OneOf(allowed) => {
+            let allowed = allowed.iter().map(|value| serde_json::from_str(value)).collect::<Result<Vec<Json>, _>>();
+            if let Ok(allowed) = allowed {
+                node.insert("enum".into(), allowed.into());
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Writes whole numbers without a fraction, as `8080` rather than `8080.0`.
+fn number(bound: Bound) -> Json {
+    match bound {
+        Bound::Integer(n) => match (i64::try_from(n), u64::try_from(n)) {
+            (Ok(n), _) => n.into(),
+            (_, Ok(n)) => n.into(),
+            _ => (n as f64).into(),
+        },
+        Bound::Float(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => (n as i64).into(),
+        Bound::Float(n) => n.into(),
+    }
+}
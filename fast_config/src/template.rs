@@ -0,0 +1,331 @@
+//! Writing a documented config file of the default values, see [`FastConfig::template`](crate::FastConfig::template).
+
+use crate::ConfigFields;
+use crate::Error;
+use crate::FastConfig;
+use crate::Format;
+use crate::fields;
+use crate::file;
+use crate::validate::Constraint;
+use crate::validate::Rule;
+use crate::value;
+use crate::value::Map;
+use crate::value::Value;
+
+use std::collections::HashMap;
+
+/// A key of the template, with the comment lines going above it.
+struct Entry {
+    key: String,
+    notes: Vec<String>,
+    /// Whether the key is only an example, written as a comment. <br/>
+    /// `Option` fields that default to `None` are, so that leaving the file as it is keeps them unset.
+    commented: bool,
+    node: Node,
+}
+
+enum Node {
+    Value(Value),
+    Table(Vec<Entry>),
+}
+
+/// The default config, documented with the doc comments, allowed values and rules of its fields. <br/>
+/// JSON has no comments, so it gets the plain default config without the examples.
+pub(crate) fn render<T: FastConfig + ConfigFields + Default>(format: Format) -> Result<String, Error> {
+    let defaults = file::to_document(&T::default()).map_err(std::io::Error::other)?;
+    let (sample, variants) = value::sample::<T>();
+    let sample = sample.and_then(|sample| file::to_value(&sample).ok());
+    let notes = notes::<T>(&variants);
+    let table = |document: &Value| match document {
+        Value::Table(map) => Some(map.clone()),
+        _ => None,
+    };
+    let mut entries = entries(table(&defaults).as_ref(), sample.as_ref().and_then(table).as_ref(), "", &notes, false);
+    // The version goes first, where migrations look for it
+    if let Some(versioning) = T::versioning()
+        && let Some(index) = entries.iter().position(|entry| entry.key == versioning.key)
+    {
+        let version = entries.remove(index);
+        entries.insert(0, version);
+    }
+    let mut out = String::new();
+    match format {
+        #[cfg(feature = "json")]
+        Format::JSON => object(&entries, format, false, &mut out)?,
+        #[cfg(feature = "json5")]
+        Format::JSON5 => object(&entries, format, true, &mut out)?,
+        #[cfg(feature = "toml")]
+        Format::TOML => toml(&entries, &[], &mut out)?,
+        #[cfg(feature = "yaml")]
+        Format::YAML => yaml(&entries, 0, &mut out)?,
+    }
+    Ok(out)
+}
+
+/// Merges the defaults with the sample, which fills in the keys the defaults leave unset.
+fn entries(
+    defaults: Option<&Map>,
+    sample: Option<&Map>,
+    path: &str,
+    notes: &HashMap<String, Vec<String>>,
+    commented: bool,
+) -> Vec<Entry> {
+    let sample_keys = sample.into_iter().flat_map(Map::iter).map(|(key, _)| key);
+    let default_keys = defaults.into_iter().flat_map(Map::iter).map(|(key, _)| key);
+    let mut keys: Vec<&String> = sample_keys.collect();
+    for key in default_keys {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    let set = |map: Option<&Map>, key: &str| map.and_then(|map| map.get(key)).filter(|value| !value.is_null()).cloned();
+    let mut merged = Vec::new();
+    for key in keys {
+        let path = match path.is_empty() {
+            true => key.clone(),
+            false => format!("{path}.{key}"),
+        };
+        let (value, commented) = match (set(defaults, key), set(sample, key)) {
+            (Some(value), _) => (value, commented),
+            (None, Some(example)) => (example, true),
+            (None, None) => continue,
+        };
+        let sample = match set(sample, key) {
+            Some(Value::Table(sample)) => Some(sample),
+            _ => None,
+        };
+        // Enum variants are written whole, as YAML tags them
+        let node = match value {
+            Value::Table(map) if map.is_variant() => Node::Value(Value::Table(map)),
+            Value::Table(map) if !map.is_empty() || sample.as_ref().is_some_and(|sample| !sample.is_empty()) => {
+                Node::Table(entries(Some(&map), sample.as_ref(), &path, notes, commented))
+            }
+            other => Node::Value(other),
+        };
+        merged.push(Entry {
+            key: key.clone(),
+            notes: notes.get(&path).cloned().unwrap_or_default(),
+            commented,
+            node,
+        });
+    }
+    merged
+}
+
+/// The comment lines of each key path: the doc comment, then the allowed values and the rules of the field.
+fn notes<T: FastConfig + ConfigFields>(variants: &value::Variants) -> HashMap<String, Vec<String>> {
+    let mut notes: HashMap<String, Vec<String>> = HashMap::new();
+    for (path, field) in fields::flatten(T::FIELDS) {
+        if let Some(doc) = field.doc {
+            notes.entry(path).or_default().extend(doc.lines().map(str::to_owned));
+        }
+    }
+    for (path, names) in variants {
+        notes.entry(path.clone()).or_default().push(format!("Allowed values: {}", names.join(", ")));
+    }
+    for constraint in T::constraints() {
+        if let Some((path, note)) = field_note(&constraint) {
+            notes.entry(path.to_owned()).or_default().push(note);
+        }
+    }
+    notes
+}
+
+/// A field rule put in words, such as `Range: 1 to 65535`. Custom checks can't be described.
+fn note(rule: &Rule) -> Option<String> {
+    match rule {
+        Rule::Range { min, max } => bounds("Range", min.map(|n| n.to_string()), max.map(|n| n.to_string())),
+        Rule::Length { min, max } => bounds("Length", min.map(|n| n.to_string()), max.map(|n| n.to_string())),
+        Rule::Regex(pattern) => Some(format!("Must match: {pattern}")),
+        Rule::OneOf(allowed) => {
+            let allowed = allowed.iter().map(|value| unquote(value)).collect::<Vec<_>>();
+            Some(format!("Allowed values: {}", allowed.join(", ")))
+        }
+        Rule::NonEmpty => Some("Must not be empty".to_owned()),
+        Rule::PathExists => Some("Must be a path that exists".to_owned()),
+        Rule::Custom(_) => None,
+    }
+}
+
+/// The field a constraint is on and its rule put in words, marking rules put on the items of the field.
+pub(crate) fn field_note(constraint: &Constraint) -> Option<(&str, String)> {
+    let note = note(&constraint.rule)?;
+    let path = &constraint.path;
+    match path.strip_suffix("[]").or_else(|| path.strip_suffix(".*")) {
+        Some(field) => Some((field, format!("Each: {note}"))),
+        None => Some((path, note)),
+    }
+}
+
+fn bounds(name: &str, min: Option<String>, max: Option<String>) -> Option<String> {
+    match (min, max) {
+        (Some(min), Some(max)) => Some(format!("{name}: {min} to {max}")),
+        (Some(min), None) => Some(format!("{name}: at least {min}")),
+        (None, Some(max)) => Some(format!("{name}: at most {max}")),
+        (None, None) => None,
+    }
+}
+
+/// Shows a `one_of` value, which is written as JSON, without the quotes around strings.
+fn unquote(json: &str) -> String {
+    match json.strip_prefix('"').and_then(|json| json.strip_suffix('"')) {
+        Some(string) => string.replace("\\\"", "\"").replace("\\\\", "\\"),
+        None => json.to_owned(),
+    }
+}
+
+/// Writes a line, behind `comment` when the key is only an example.
+fn line(out: &mut String, indent: &str, comment: Option<&str>, text: &str) {
+    out.push_str(indent);
+    if let Some(comment) = comment {
+        out.push_str(comment);
+        out.push(' ');
+    }
+    out.push_str(text);
+    out.push('\n');
+}
+
+/// Leaves a blank line above documented keys, other than the first one of their table.
+fn spacing(out: &mut String, index: usize, entry: &Entry) {
+    if index > 0 && !entry.notes.is_empty() {
+        out.push('\n');
+    }
+}
+
+fn write_notes(out: &mut String, indent: &str, comment: &str, notes: &[String]) {
+    for note in notes {
+        line(out, indent, Some(comment), note);
+    }
+}
+
+#[cfg(feature = "toml")]
+fn toml(entries: &[Entry], header: &[&str], out: &mut String) -> Result<(), Error> {
+    let values = entries.iter().filter_map(|entry| match &entry.node {
+        Node::Value(value) => Some((entry, value)),
+        Node::Table(_) => None,
+    });
+    for (index, (entry, value)) in values.enumerate() {
+        spacing(out, index, entry);
+        write_notes(out, "", "#", &entry.notes);
+        let text = format!("{} = {}", toml_key(&entry.key), toml::Value::try_from(value)?);
+        line(out, "", entry.commented.then_some("#"), &text);
+    }
+    for entry in entries {
+        if let Node::Table(children) = &entry.node {
+            let header = header.iter().copied().chain([entry.key.as_str()]).collect::<Vec<_>>();
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            write_notes(out, "", "#", &entry.notes);
+            let text = format!("[{}]", header.iter().map(|part| toml_key(part)).collect::<Vec<_>>().join("."));
+            line(out, "", entry.commented.then_some("#"), &text);
+            toml(children, &header, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes a key bare when TOML allows it, and quoted otherwise.
+#[cfg(feature = "toml")]
+fn toml_key(key: &str) -> String {
+    match !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        true => key.to_owned(),
+        false => toml::Value::String(key.to_owned()).to_string(),
+    }
+}
+
+#[cfg(feature = "yaml")]
+fn yaml(entries: &[Entry], depth: usize, out: &mut String) -> Result<(), Error> {
+    let indent = "  ".repeat(depth);
+    for (index, entry) in entries.iter().enumerate() {
+        spacing(out, index, entry);
+        let comment = entry.commented.then_some("#");
+        let key = serde_yml::to_string(&entry.key)?.trim_end().to_owned();
+        write_notes(out, &indent, "#", &entry.notes);
+        match &entry.node {
+            Node::Table(children) => {
+                line(out, &indent, comment, &format!("{key}:"));
+                yaml(children, depth + 1, out)?;
+            }
+            Node::Value(value) => {
+                let text = serde_yml::to_string(&value.in_format(Format::YAML))?;
+                let mut lines = text.lines();
+                let block = match value {
+                    Value::Array(items) => !items.is_empty(),
+                    Value::Table(map) => !map.is_variant() && !map.is_empty(),
+                    _ => false,
+                };
+                match block {
+                    true => line(out, &indent, comment, &format!("{key}:")),
+                    false => line(out, &indent, comment, &format!("{key}: {}", lines.next().unwrap_or_default())),
+                }
+                // Lists, tables and multi-line strings go on the lines below, one level deeper,
+                // and so does the content of a variant, whose tag stays next to the key
+                let variant = matches!(value, Value::Table(map) if map.is_variant());
+                let nested = "  ".repeat(depth + usize::from(block || variant));
+                for rest in lines {
+                    line(out, &nested, comment, rest);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes a JSON object, with comments and commented-out examples for JSON5 and without them for JSON.
+#[cfg(any(feature = "json", feature = "json5"))]
+fn object(entries: &[Entry], format: Format, comments: bool, out: &mut String) -> Result<(), Error> {
+    out.push_str("{\n");
+    json(entries, 1, format, comments, out)?;
+    out.push_str("}\n");
+    Ok(())
+}
+
+#[cfg(any(feature = "json", feature = "json5"))]
+fn json(entries: &[Entry], depth: usize, format: Format, comments: bool, out: &mut String) -> Result<(), Error> {
+    let indent = "  ".repeat(depth);
+    let entries = entries.iter().filter(|entry| comments || !entry.commented).collect::<Vec<_>>();
+    for (index, entry) in entries.iter().enumerate() {
+        // JSON5 allows a comma after the last key, which keeps the file valid when examples are uncommented
+        let comma = match comments || index + 1 < entries.len() {
+            true => ",",
+            false => "",
+        };
+        let comment = entry.commented.then_some("//");
+        let key = to_json(&entry.key)?;
+        if comments {
+            spacing(out, index, entry);
+            write_notes(out, &indent, "//", &entry.notes);
+        }
+        match &entry.node {
+            Node::Table(children) => {
+                line(out, &indent, comment, &format!("{key}: {{"));
+                json(children, depth + 1, format, comments, out)?;
+                line(out, &indent, comment, &format!("}}{comma}"));
+            }
+            Node::Value(value) => {
+                let text = to_json(&value.in_format(format))?;
+                let mut lines = text.lines();
+                let first = lines.next().unwrap_or_default();
+                let mut lines = lines.peekable();
+                match lines.peek() {
+                    None => line(out, &indent, comment, &format!("{key}: {first}{comma}")),
+                    Some(_) => line(out, &indent, comment, &format!("{key}: {first}")),
+                }
+                while let Some(rest) = lines.next() {
+                    let rest = match lines.peek() {
+                        None => format!("{rest}{comma}"),
+                        Some(_) => rest.to_owned(),
+                    };
+                    line(out, &indent, comment, &rest);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(any(feature = "json", feature = "json5"))]
+fn to_json(value: &impl serde::Serialize) -> Result<String, Error> {
+    Ok(serde_json::to_string_pretty(value).map_err(std::io::Error::other)?)
+}
@@ -0,0 +1,146 @@
+use super::*;
+
+use fast_config::Loaded;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct Logging {
+    pub level: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+#[serde(default)]
+pub struct Server {
+    #[fast_config(deprecated_alias = "hostname")]
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+#[serde(default)]
+#[fast_config(deprecated_alias(from = "log_level", to = "logging.level", since = "2.0"))]
+pub struct Data {
+    #[fast_config(deprecated_alias = "timeout", since = "1.4")]
+    pub timeout_ms: u64,
+    pub logging: Logging,
+    #[fast_config(nested)]
+    pub server: Server,
+}
+
+fn expected() -> Data {
+    Data {
+        timeout_ms: 500,
+        logging: Logging { level: "debug".into() },
+        server: Server { host: "example.com".into(), port: 80 },
+    }
+}
+
+/// `(path, line, column, message)` of every warning.
+fn found<T>(loaded: &Loaded<T>) -> Vec<(&str, usize, usize, &str)> {
+    loaded
+        .warnings
+        .iter()
+        .map(|w| {
+            let location = w.location.expect("every deprecated key is located");
+            (w.path.as_str(), location.line, location.column, w.message.as_str())
+        })
+        .collect()
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn deprecated_keys_are_read_and_warned_about_toml() {
+    let content = "timeout = 500\nlog_level = \"debug\"\n\n[server]\nhostname = \"example.com\"\nport = 80\n";
+    assert_eq!(Data::from_string(content, TOML).unwrap(), expected());
+
+    let loaded = Data::from_string_checked(content, TOML, fast_config::UnknownKeys::Deny).unwrap();
+    assert_eq!(
+        found(&loaded),
+        vec![
+            ("timeout", 1, 1, "deprecated since 1.4, use `timeout_ms` instead"),
+            ("log_level", 2, 1, "deprecated since 2.0, use `logging.level` instead"),
+            ("server.hostname", 5, 1, "deprecated, use `server.host` instead"),
+        ]
+    );
+    assert_eq!(loaded.value, expected());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn deprecated_keys_are_read_and_warned_about_json() {
+    let content = r#"{
+  "timeout": 500,
+  "log_level": "debug",
+  "server": { "hostname": "example.com", "port": 80 }
+}"#;
+    let loaded = Data::from_string_checked(content, JSON, fast_config::UnknownKeys::Warn).unwrap();
+    let paths = found(&loaded).into_iter().map(|(path, line, column, _)| (path, line, column)).collect::<Vec<_>>();
+    assert_eq!(paths, vec![("timeout", 2, 3), ("log_level", 3, 3), ("server.hostname", 4, 15)]);
+    assert_eq!(loaded.value, expected());
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn deprecated_keys_are_read_and_warned_about_yaml() {
+    let content = "timeout: 500\nlog_level: debug\nserver:\n  hostname: example.com\n  port: 80\n";
+    let loaded = Data::from_string_checked(content, YAML, fast_config::UnknownKeys::Warn).unwrap();
+    let paths = found(&loaded).into_iter().map(|(path, line, column, _)| (path, line, column)).collect::<Vec<_>>();
+    assert_eq!(paths, vec![("timeout", 1, 1), ("log_level", 2, 1), ("server.hostname", 4, 3)]);
+    assert_eq!(loaded.value, expected());
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn new_keys_win_over_deprecated_ones() {
+    let content = "timeout = 1\ntimeout_ms = 500\nlog_level = \"debug\"\n\n[server]\nhost = \"example.com\"\nport = 80\n";
+    let loaded = Data::from_string_checked(content, TOML, fast_config::UnknownKeys::Warn).unwrap();
+    assert_eq!(loaded.value, expected());
+    assert_eq!(loaded.warnings.len(), 2);
+}
+
+#[cfg(feature = "json5")]
+#[test]
+fn only_new_keys_are_saved() {
+    let c = MANAGER.setup();
+    let path = c.path.join("aliases/saved.json5");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, "{ timeout: 500, log_level: 'debug', server: { hostname: 'example.com', port: 80 } }").unwrap();
+
+    let config = Data::new(&path, JSON5).unwrap();
+    config.save(&path, JSON5).unwrap();
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(!saved.contains("timeout\"") && !saved.contains("log_level") && !saved.contains("hostname"));
+    assert_eq!(Data::new(&path, JSON5).unwrap(), expected());
+}
+
+thread_local! {
+    static WARNINGS: std::cell::RefCell<Vec<String>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+fn record(warning: &fast_config::Problem) {
+    WARNINGS.with_borrow_mut(|warnings| warnings.push(warning.to_string()));
+}
+
+/// Hands the deprecated keys `new_checked` would warn about to `record`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+#[serde(default)]
+#[fast_config(on_warning = "record")]
+pub struct Warned {
+    #[fast_config(deprecated_alias = "timeout", since = "1.4")]
+    pub timeout_ms: u64,
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn plain_loads_hand_deprecated_keys_to_the_hook() {
+    let c = MANAGER.setup();
+    let path = c.path.join("aliases").join("warned.toml");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, "timeout = 500\n").unwrap();
+    assert_eq!(Warned::new(&path, TOML).unwrap(), Warned { timeout_ms: 500 });
+    let mut warned = Warned::default();
+    warned.load(&path, TOML).unwrap();
+    assert_eq!(Warned::from_string("timeout_ms = 5\n", TOML).unwrap(), Warned { timeout_ms: 5 });
+    let warning = "1:1: `timeout`: deprecated since 1.4, use `timeout_ms` instead";
+    assert_eq!(WARNINGS.take(), [warning, warning]);
+}
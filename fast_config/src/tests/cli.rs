@@ -0,0 +1,131 @@
+use super::*;
+
+use clap::Parser;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub enum Level {
+    Info,
+    Debug,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+pub struct Server {
+    /// Port to listen on
+    pub port: u16,
+    pub tags: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+#[fast_config(clap, env_prefix = "FAST_CONFIG_CLI")]
+pub struct Data {
+    /// Name shown in the title bar
+    pub name: String,
+    pub max_connections: u32,
+    pub level: Level,
+    pub verbose: bool,
+    #[serde(rename = "allowList")]
+    pub allow_list: Vec<String>,
+    #[fast_config(nested)]
+    pub server: Server,
+}
+
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    config: DataArgs,
+}
+
+fn data() -> Data {
+    Data {
+        name: "test".into(),
+        max_connections: 10,
+        level: Level::Info,
+        verbose: false,
+        allow_list: Vec::new(),
+        server: Server { port: 80, tags: None },
+    }
+}
+
+#[test]
+fn generated_flags_are_optional() {
+    let cli = Cli::parse_from(["app"]);
+    assert_eq!(cli.config, DataArgs::default());
+
+    let mut config = data();
+    cli.config.apply(&mut config).unwrap();
+    assert_eq!(config, data());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn precedence_is_file_then_env_then_cli() {
+    let c = MANAGER.setup();
+    let path = c.path.join("config_cli.json");
+    data().save(&path, JSON).unwrap();
+
+    let _env = EnvGuard::set(&[("FAST_CONFIG_CLI_NAME", "env"), ("FAST_CONFIG_CLI_MAX_CONNECTIONS", "20")]);
+    let cli = Cli::parse_from([
+        "app",
+        "--max-connections",
+        "30",
+        "--level",
+        "Debug",
+        "--verbose",
+        "--allow-list",
+        "a,b",
+    ]);
+    let mut config = Data::new(&path, JSON).unwrap();
+    cli.config.apply(&mut config).unwrap();
+    assert_eq!(
+        config,
+        Data {
+            name: "env".into(),
+            max_connections: 30,
+            level: Level::Debug,
+            verbose: true,
+            allow_list: vec!["a".into(), "b".into()],
+            server: Server { port: 80, tags: None },
+        }
+    );
+}
+
+#[test]
+fn doc_comments_become_help() {
+    use clap::CommandFactory;
+    let help = Cli::command().render_help().to_string();
+    assert!(help.contains("--name <NAME>"), "{help}");
+    assert!(help.contains("Name shown in the title bar"), "{help}");
+}
+
+#[test]
+fn invalid_flag_values_are_reported() {
+    use clap::error::ErrorKind;
+
+    for args in [["app", "--max-connections", "lots"], ["app", "--level", "Loud"], ["app", "--server.port", "99999"]] {
+        let error = Cli::try_parse_from(args).map(|_| ()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ValueValidation, "{error}");
+        assert!(error.to_string().contains(args[1]), "{error}");
+    }
+}
+
+#[test]
+fn strings_are_taken_literally() {
+    let cli = Cli::parse_from(["app", "--name", "null", "--server.tags", "[x]"]);
+    let mut config = data();
+    cli.config.apply(&mut config).unwrap();
+    assert_eq!(config.name, "null");
+    assert_eq!(config.server.tags.as_deref(), Some("[x]"));
+}
+
+#[test]
+fn nested_fields_get_their_own_flags() {
+    use clap::CommandFactory;
+    let help = Cli::command().render_help().to_string();
+    assert!(help.contains("--server.port <PORT>"), "{help}");
+    assert!(help.contains("Port to listen on"), "{help}");
+
+    let cli = Cli::parse_from(["app", "--server.port", "8080"]);
+    let mut config = data();
+    cli.config.apply(&mut config).unwrap();
+    assert_eq!(config.server, Server { port: 8080, tags: None });
+}
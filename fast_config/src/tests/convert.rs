@@ -0,0 +1,83 @@
+use super::*;
+
+use fast_config::Error;
+use fast_config::Format;
+
+/// Writes `content` as `src`, converts it to `dst` and returns what was written.
+fn convert(src: (&str, Format), content: &str, dst: (&str, Format)) -> Result<String, Error> {
+    let c = MANAGER.setup();
+    let dir = c.path.join("convert");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join(src.0), content).unwrap();
+    fast_config::convert(dir.join(src.0), src.1, dir.join(dst.0), dst.1)?;
+    Ok(std::fs::read_to_string(dir.join(dst.0)).unwrap())
+}
+
+fn unrepresentable(error: &Error) -> Vec<(String, Option<usize>)> {
+    match error.inner() {
+        Error::Unrepresentable { problems, .. } => problems
+            .iter()
+            .map(|problem| (problem.path.clone(), problem.location.map(|l| l.line)))
+            .collect(),
+        other => panic!("{other}"),
+    }
+}
+
+#[cfg(all(feature = "json", feature = "toml"))]
+#[test]
+fn json_converts_to_toml() {
+    let content = r#"{ "name": "app", "mixed": [1, "x"], "server": { "port": 80, "hosts": ["a", "b"] } }"#;
+    let written = convert(("basic.json", JSON), content, ("basic.toml", TOML)).unwrap();
+    assert!(written.starts_with("name = \"app\""), "{written}");
+    let document = fast_config::Value::from_string(content, JSON).unwrap();
+    assert_eq!(fast_config::Value::from_string(&written, TOML).unwrap(), document);
+}
+
+#[cfg(all(feature = "toml", feature = "yaml"))]
+#[test]
+fn comments_are_carried_over() {
+    let content = "# Written by hand\n\n# The name\nname = \"app\"\nport = 80 # not kept\n\n\
+                   # Server settings\n[server]\n# Where to listen\nhost = \"localhost\"\n";
+    let written = convert(("comments.toml", TOML), content, ("comments.yaml", YAML)).unwrap();
+    assert_eq!(
+        written,
+        "# Written by hand\n\n# The name\nname: app\nport: 80\n\
+         # Server settings\nserver:\n  # Where to listen\n  host: localhost\n"
+    );
+}
+
+#[cfg(all(feature = "yaml", feature = "json5"))]
+#[test]
+fn comments_of_list_items_are_carried_over() {
+    let content = "servers:\n# The first one\n- host: a\n  # Its port\n  port: 1\n";
+    let written = convert(("items.yaml", YAML), content, ("items.json5", JSON5)).unwrap();
+    assert!(written.contains("  servers: [\n    // The first one\n    {\n      host: \"a\",\n"), "{written}");
+    assert!(written.contains("      // Its port\n      port: 1,\n"), "{written}");
+}
+
+#[cfg(all(feature = "json", feature = "toml"))]
+#[test]
+fn nulls_cannot_be_written_as_toml() {
+    let content = "{\n  \"name\": null,\n  \"tags\": [\"a\", null],\n  \"port\": 80\n}\n";
+    let error = convert(("nulls.json", JSON), content, ("nulls.toml", TOML)).unwrap_err();
+    assert_eq!(unrepresentable(&error), [("name".into(), Some(2)), ("tags[1]".into(), Some(3))]);
+    assert_eq!(error.line(), Some(2));
+    assert!(!MANAGER.setup().path.join("convert/nulls.toml").exists());
+}
+
+#[cfg(all(feature = "yaml", feature = "json"))]
+#[test]
+fn yaml_keys_must_be_strings() {
+    let content = "ports:\n  1: a\n  true: b\n  name: c\n";
+    let error = convert(("keys.yaml", YAML), content, ("keys.json", JSON)).unwrap_err();
+    assert_eq!(unrepresentable(&error), [("ports.1".into(), Some(2)), ("ports.true".into(), Some(3))]);
+}
+
+#[cfg(all(feature = "json5", feature = "json"))]
+#[test]
+fn special_floats_cannot_be_written_as_json() {
+    let content = "{ ratio: NaN, limit: Infinity, ok: 1.5 }";
+    let error = convert(("floats.json5", JSON5), content, ("floats.json", JSON)).unwrap_err();
+    let paths = unrepresentable(&error).into_iter().map(|(path, _)| path).collect::<Vec<_>>();
+    assert_eq!(paths, ["ratio", "limit"]);
+}
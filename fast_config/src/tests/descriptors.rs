@@ -0,0 +1,86 @@
+use super::*;
+
+use fast_config::ConfigFields;
+use fast_config::FieldAttributes;
+use fast_config::FieldDescriptor;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+pub struct Server {
+    /// The address to listen on.
+    pub host: String,
+    #[fast_config(range(min = 1, max = 65535), env = "PORT")]
+    pub port: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+#[serde(rename_all = "kebab-case")]
+pub struct Data {
+    #[fast_config(one_of("light", "dark"), deprecated_alias = "colors", since = "2.0")]
+    pub color_theme: String,
+    pub log_file: Option<String>,
+    #[fast_config(nested)]
+    pub server: Server,
+    #[fast_config(nested)]
+    pub backup: Option<Server>,
+    #[serde(skip)]
+    pub cache: Vec<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+pub struct Wrapper<T> {
+    #[fast_config(nested)]
+    pub inner: T,
+}
+
+const NONE: FieldAttributes = FieldAttributes {
+    env: None,
+    nested: false,
+    deprecated_alias: None,
+    since: None,
+    rules: &[],
+};
+
+#[test]
+fn fields_are_described_statically() {
+    let keys = Data::FIELDS.iter().map(|field| field.key).collect::<Vec<_>>();
+    assert_eq!(keys, ["color-theme", "log-file", "server", "backup"]);
+
+    assert_eq!(
+        Data::FIELDS[0],
+        FieldDescriptor {
+            name: "color_theme",
+            key: "color-theme",
+            type_name: "String",
+            doc: None,
+            optional: false,
+            repeated: false,
+            children: &[],
+            attributes: FieldAttributes {
+                deprecated_alias: Some("colors"),
+                since: Some("2.0"),
+                rules: &[r#"one_of("light", "dark")"#],
+                ..NONE
+            },
+        }
+    );
+    assert_eq!((Data::FIELDS[1].type_name, Data::FIELDS[1].optional), ("Option<String>", true));
+
+    let port = Data::field("server.port").unwrap();
+    assert_eq!(port.attributes.env, Some("PORT"));
+    assert_eq!(port.attributes.rules, ["range(min = 1, max = 65535)"]);
+    assert_eq!(Data::field("server.host").unwrap().doc, Some("The address to listen on."));
+    assert_eq!(Data::field("server.missing"), None);
+}
+
+#[test]
+fn nested_fields_have_children() {
+    let backup = Data::field("backup").unwrap();
+    assert!(backup.optional && backup.attributes.nested && !backup.repeated);
+    assert_eq!(backup.children, Server::FIELDS);
+    assert_eq!(Data::field("backup.port"), Server::field("port"));
+
+    // Generic nested fields are described by the type they are given
+    assert_eq!(Wrapper::<Server>::FIELDS[0].type_name, "T");
+    assert_eq!(Wrapper::<Server>::FIELDS[0].children, Server::FIELDS);
+    assert_eq!(Wrapper::<Data>::field("inner.server.host"), Server::field("host"));
+}
@@ -0,0 +1,121 @@
+use super::*;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub enum Mode {
+    Fast,
+    Safe,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+pub struct Server {
+    pub host: String,
+    #[fast_config(env = "FAST_CONFIG_ENV_PORT")]
+    pub port: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+#[fast_config(env_prefix = "FAST_CONFIG_ENV")]
+pub struct Data {
+    pub name: String,
+    pub mode: Mode,
+    pub tags: Vec<String>,
+    pub verbose: bool,
+    #[fast_config(nested)]
+    pub server: Server,
+}
+
+fn data() -> Data {
+    Data {
+        name: "test".into(),
+        mode: Mode::Fast,
+        tags: vec!["a".into()],
+        verbose: false,
+        server: Server {
+            host: "localhost".into(),
+            port: 80,
+        },
+    }
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn env_overrides_after_load() {
+    let c = MANAGER.setup();
+    let path = c.path.join("config_env.json");
+    data().save(&path, JSON).unwrap();
+
+    let _env = EnvGuard::set(&[
+        ("FAST_CONFIG_ENV_MODE", "Safe"),
+        ("FAST_CONFIG_ENV_TAGS", "x, y,z"),
+        ("FAST_CONFIG_ENV_VERBOSE", "true"),
+        ("FAST_CONFIG_ENV_SERVER__HOST", "example.com"),
+        ("FAST_CONFIG_ENV_PORT", "8080"),
+    ]);
+    let loaded = Data::new(&path, JSON).unwrap();
+    let mut expected = data();
+    expected.mode = Mode::Safe;
+    expected.tags = vec!["x".into(), "y".into(), "z".into()];
+    expected.verbose = true;
+    expected.server = Server {
+        host: "example.com".into(),
+        port: 8080,
+    };
+    assert_eq!(loaded, expected);
+
+    let mut config = data();
+    config.load(&path, JSON).unwrap();
+    assert_eq!(config, expected);
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+#[fast_config(env_prefix = "FAST_CONFIG_STRICT")]
+pub struct Strict {
+    pub retries: u8,
+}
+
+#[test]
+fn env_errors_name_the_variable() {
+    let _env = EnvGuard::set(&[("FAST_CONFIG_STRICT_RETRIES", "many")]);
+    let mut config = Strict { retries: 3 };
+    match config.apply_env() {
+        Err(fast_config::Error::Env { name, message }) => {
+            assert_eq!(name, "FAST_CONFIG_STRICT_RETRIES");
+            assert!(message.contains("retries"), "{message}");
+        }
+        other => panic!("expected an env error, got {other:?}"),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+#[serde(rename_all = "camelCase")]
+pub struct Upstream {
+    pub host_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+#[serde(rename_all = "camelCase")]
+#[fast_config(env_prefix = "FAST_CONFIG_RENAMED")]
+pub struct Renamed {
+    pub max_conn: u32,
+    #[serde(rename = "backend")]
+    #[fast_config(nested)]
+    pub upstream: Upstream,
+}
+
+#[test]
+fn env_names_match_renamed_keys() {
+    let _env = EnvGuard::set(&[
+        ("FAST_CONFIG_RENAMED_MAX_CONN", "5"),
+        ("FAST_CONFIG_RENAMED_BACKEND__HOST_NAME", "example.com"),
+        // Matches no field, so it is left alone
+        ("FAST_CONFIG_RENAMED_UPSTREAM__HOST_NAME", "ignored"),
+    ]);
+    let mut config = Renamed::default();
+    config.apply_env().unwrap();
+    assert_eq!(config, Renamed { max_conn: 5, upstream: Upstream { host_name: "example.com".into() } });
+
+    // The reference lists the names that match
+    let reference = Renamed::reference().unwrap();
+    let env = reference.fields.iter().filter_map(|field| field.env.as_deref()).collect::<Vec<_>>();
+    assert_eq!(env, ["FAST_CONFIG_RENAMED_MAX_CONN", "FAST_CONFIG_RENAMED_BACKEND__HOST_NAME"]);
+}
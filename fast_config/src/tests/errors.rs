@@ -0,0 +1,164 @@
+use super::*;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Server {
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+pub struct Data {
+    pub name: String,
+    pub servers: Vec<Server>,
+}
+
+/// Writes `content` and checks that loading it fails at `servers[1].port`, on the given line and column.
+fn assert_context(file: &str, content: &str, format: fast_config::Format, line: usize, column: usize) {
+    let c = MANAGER.setup();
+    let path = c.path.join(file);
+    std::fs::create_dir_all(&c.path).unwrap();
+    std::fs::write(&path, content).unwrap();
+    let error = Data::new(&path, format).unwrap_err();
+    assert_eq!(error.file(), Some(path.as_path()));
+    assert_eq!(error.key(), Some("servers[1].port"), "{error}");
+    assert_eq!((error.line(), error.column()), (Some(line), Some(column)), "{error}");
+    assert!(error.to_string().starts_with(&format!("{}:{line}:{column}: ", path.display())), "{error}");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn type_errors_have_context_json() {
+    let content = "{\n  \"name\": \"app\",\n  \"servers\": [\n    { \"host\": \"a\", \"port\": 80 },\n    { \"host\": \"ü\", \"port\": true }\n  ]\n}";
+    assert_context("config_errors.json", content, JSON, 5, 20);
+}
+
+#[cfg(feature = "json5")]
+#[test]
+fn type_errors_have_context_json5() {
+    let content = "{\n  name: 'app',\n  servers: [\n    { host: 'a', port: 80 },\n    { host: 'b', port: true },\n  ],\n}";
+    assert_context("config_errors.json5", content, JSON5, 5, 18);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn type_errors_have_context_toml() {
+    let content = "name = \"app\"\n\n[[servers]]\nhost = \"a\"\nport = 80\n\n[[servers]]\nhost = \"b\"\nport = true\n";
+    assert_context("config_errors.toml", content, TOML, 9, 1);
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn type_errors_have_context_yaml() {
+    let content = "name: app\nservers:\n  - host: a\n    port: 80\n  - host: b\n    port: true\n";
+    assert_context("config_errors.yaml", content, YAML, 6, 5);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn syntax_errors_use_the_parser_location() {
+    let c = MANAGER.setup();
+    let path = c.path.join("config_errors_syntax.json");
+    std::fs::create_dir_all(&c.path).unwrap();
+    std::fs::write(&path, "{\n  \"name\": \"app\",\n  \"servers\": [,]\n}").unwrap();
+    let error = Data::new(&path, JSON).unwrap_err();
+    assert_eq!(error.key(), None);
+    assert_eq!(error.line(), Some(3));
+    assert!(matches!(error.inner(), fast_config::Error::Json(_)));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn io_errors_name_the_file() {
+    let path = PathBuf::from("../config/does_not_exist.json");
+    let error = Data::new(&path, JSON).unwrap_err();
+    assert_eq!(error.file(), Some(path.as_path()));
+    assert_eq!(error.location(), None);
+    assert!(matches!(error.into_inner(), fast_config::Error::Io(_)));
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+#[serde(deny_unknown_fields)]
+pub struct Timeouts {
+    pub timeout_ms: u64,
+    pub port: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+pub struct LooseTimeouts {
+    pub timeout_ms: u64,
+    pub port: u16,
+}
+
+fn load_error<T: FastConfig + std::fmt::Debug>(file: &str, content: &str, format: fast_config::Format) -> fast_config::Error {
+    let c = MANAGER.setup();
+    let path = c.path.join(file);
+    std::fs::create_dir_all(&c.path).unwrap();
+    std::fs::write(&path, content).unwrap();
+    T::new(&path, format).unwrap_err()
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn render_points_at_the_bad_value() {
+    let error = load_error::<Timeouts>("config_render_type.toml", "timeout_ms = 100\nport = \"high\"\n", TOML);
+    let rendered = error.render();
+    let lines = rendered.lines().collect::<Vec<_>>();
+    assert!(lines[1].ends_with("config_render_type.toml:2:1"), "{rendered}");
+    assert_eq!(lines[3], "2 | port = \"high\"");
+    assert_eq!(lines[4], "  |        ^^^^^^");
+    assert_eq!(lines[5], "  = expected: u16");
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn render_suggests_known_keys() {
+    let error = load_error::<Timeouts>("config_render_unknown.yaml", "timeout_ms: 100\nprot: 80\n", YAML);
+    assert_eq!(error.help(), Some("did you mean `port`?"));
+    assert!(error.render().contains("2 | prot: 80\n  | ^^^^\n"), "{}", error.render());
+    let error = load_error::<Timeouts>("config_render_unknown.yaml", "timeout: 100\nport: 80\n", YAML);
+    assert_eq!(error.help(), Some("did you mean `timeout_ms`?"));
+
+    // Without `deny_unknown_fields`, the misspelled key is found next to the missing one
+    let error = load_error::<LooseTimeouts>("config_render_missing.yaml", "port: 80\ntimeout: 100\n", YAML);
+    assert_eq!(error.help(), Some("found `timeout`, did you mean `timeout_ms`?"));
+    assert_eq!(error.line(), Some(2));
+    assert!(error.render().ends_with("  = help: found `timeout`, did you mean `timeout_ms`?\n"));
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+pub struct Plain {
+    pub port: u16,
+    pub tags: Vec<String>,
+    pub on: bool,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+#[fast_config(version = 1, interpolate)]
+pub struct Versioned {
+    pub port: u16,
+    pub tags: Vec<String>,
+    pub on: bool,
+    pub name: String,
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn files_are_read_as_strictly_with_any_option() {
+    let documents = [
+        ("port", r#"{"port": "8080", "tags": ["a", "b"], "on": true, "name": "app"}"#),
+        ("tags", r#"{"port": 8080, "tags": "a,b", "on": true, "name": "app"}"#),
+        ("on", r#"{"port": 8080, "tags": ["a", "b"], "on": "yes", "name": "app"}"#),
+        ("name", r#"{"port": 8080, "tags": ["a", "b"], "on": true, "name": 5}"#),
+    ];
+    for (field, content) in documents {
+        let error = Plain::from_string(content, JSON).unwrap_err();
+        assert!(error.to_string().contains("invalid type"), "{error}");
+        let error = Versioned::from_string(content, JSON).unwrap_err();
+        assert_eq!(error.key(), Some(field), "{error}");
+    }
+    // Strings that held a reference are text, and read as what the field asks for
+    let content = r#"{"port": "${PORT:-8080}", "tags": "${TAGS:-a,b}", "on": "${ON:-yes}", "name": "app"}"#;
+    let config = Versioned::from_string(content, JSON).unwrap();
+    assert_eq!((config.port, config.tags, config.on), (8080, vec!["a".to_owned(), "b".to_owned()], true));
+}
@@ -0,0 +1,153 @@
+use super::*;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Server {
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+#[fast_config(includes)]
+pub struct Data {
+    pub name: String,
+    pub tags: Vec<String>,
+    pub server: Server,
+}
+
+fn write(path: &std::path::Path, content: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+#[cfg(all(feature = "toml", feature = "yaml", feature = "json"))]
+#[test]
+fn includes_and_drop_ins_are_merged_in_order() {
+    let c = MANAGER.setup();
+    let dir = c.path.join("includes_merge");
+    write(
+        &dir.join("config.toml"),
+        r#"
+            include = ["extra.yaml"]
+            name = "base"
+            tags = ["base"]
+            [server]
+            host = "localhost"
+            port = 80
+        "#,
+    );
+    write(&dir.join("extra.yaml"), "include: nested/more.json\nname: extra\n");
+    write(&dir.join("nested/more.json"), r#"{ "server": { "port": 8080 } }"#);
+    write(&dir.join("config.d/20-tags.toml"), r#"tags = ["drop-in"]"#);
+    write(&dir.join("config.d/10-name.yaml"), "name: drop-in\nserver:\n  host: example.com\n");
+    write(&dir.join("config.d/README.txt"), "ignored");
+
+    let loaded = Data::new(dir.join("config.toml"), TOML).unwrap();
+    assert_eq!(
+        loaded,
+        Data {
+            name: "drop-in".into(),
+            tags: vec!["drop-in".into()],
+            server: Server {
+                host: "example.com".into(),
+                port: 8080,
+            },
+        }
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn include_cycles_are_detected() {
+    let c = MANAGER.setup();
+    let dir = c.path.join("includes_cycle");
+    write(&dir.join("a.json"), r#"{ "include": "b.json", "name": "a", "tags": [], "server": { "host": "", "port": 1 } }"#);
+    write(&dir.join("b.json"), r#"{ "include": ["a.json"] }"#);
+    match Data::new(dir.join("a.json"), JSON).map_err(fast_config::Error::into_inner) {
+        Err(fast_config::Error::Include { message, .. }) => assert!(message.contains("cycle"), "{message}"),
+        other => panic!("expected an include error, got {other:?}"),
+    }
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn includes_cannot_escape_the_root() {
+    let c = MANAGER.setup();
+    let dir = c.path.join("includes_traversal");
+    write(&dir.join("outside.json"), r#"{ "name": "outside" }"#);
+    write(
+        &dir.join("app/config.json"),
+        r#"{ "include": "../outside.json", "name": "a", "tags": [], "server": { "host": "", "port": 1 } }"#,
+    );
+    match Data::new(dir.join("app/config.json"), JSON).map_err(fast_config::Error::into_inner) {
+        Err(fast_config::Error::Include { path, message }) => {
+            assert!(path.ends_with("outside.json"));
+            assert!(message.contains("outside of the allowed directory"), "{message}");
+        }
+        other => panic!("expected an include error, got {other:?}"),
+    }
+}
+
+#[cfg(all(feature = "toml", feature = "yaml"))]
+#[test]
+fn errors_point_into_the_included_file() {
+    let c = MANAGER.setup();
+    let dir = c.path.join("includes_errors");
+    let main = r#"
+        include = ["extra.yaml"]
+        name = "base"
+        tags = []
+        [server]
+        host = "localhost"
+        port = 1
+    "#;
+    write(&dir.join("config.toml"), main);
+
+    write(&dir.join("extra.yaml"), "name: extra\nserver: [unclosed\n");
+    let error = Data::new(dir.join("config.toml"), TOML).unwrap_err();
+    assert!(error.file().unwrap().ends_with("extra.yaml"), "{error}");
+
+    write(&dir.join("extra.yaml"), "name: extra\nserver:\n  port: lots\n");
+    let error = Data::new(dir.join("config.toml"), TOML).unwrap_err();
+    assert!(error.file().unwrap().ends_with("extra.yaml"), "{error}");
+    assert_eq!(error.key(), Some("server.port"));
+    assert_eq!(error.location().map(|l| l.line), Some(3));
+    match error {
+        fast_config::Error::File(file) => assert_eq!(file.snippet.as_deref(), Some("  port: lots")),
+        other => panic!("expected a file error, got {other:?}"),
+    }
+
+    // Keys only the main file sets are still reported against it
+    write(&dir.join("extra.yaml"), "name: extra\n");
+    write(&dir.join("config.toml"), &main.replace("port = 1", "port = -1"));
+    let error = Data::new(dir.join("config.toml"), TOML).unwrap_err();
+    assert!(error.file().unwrap().ends_with("config.toml"), "{error}");
+    assert_eq!(error.key(), Some("server.port"));
+}
+
+#[cfg(all(feature = "toml", feature = "yaml"))]
+#[test]
+fn saves_keep_the_includes() {
+    let c = MANAGER.setup();
+    let dir = c.path.join("includes_save");
+    let path = dir.join("config.toml");
+    write(&path, "include = [\"server.yaml\"]\nname = \"base\"\ntags = []\n");
+    write(&dir.join("server.yaml"), "server:\n  host: localhost\n  port: 80\n");
+    write(&dir.join("config.d/10-tags.toml"), "tags = [\"drop-in\"]\n");
+
+    let mut config = Data::new(&path, TOML).unwrap();
+    config.name = "changed".into();
+    config.save(&path, TOML).unwrap();
+    // Only the main file's values are written, below the include list
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "include = [\"server.yaml\"]\nname = \"changed\"\n");
+    assert_eq!(Data::new(&path, TOML).unwrap(), config);
+
+    // The included file would win over the changed port on the next load
+    config.server.port = 8080;
+    match config.save(&path, TOML).map_err(fast_config::Error::into_inner) {
+        Err(fast_config::Error::Include { path, message }) => {
+            assert!(path.ends_with("server.yaml"));
+            assert!(message.contains("sets `server.port`"), "{message}");
+        }
+        other => panic!("expected an include error, got {other:?}"),
+    }
+}
@@ -0,0 +1,90 @@
+use super::*;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Database {
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+#[fast_config(interpolate)]
+pub struct Data {
+    pub data_dir: String,
+    pub log_dir: String,
+    pub paths: Vec<String>,
+    pub database: Database,
+}
+
+fn expected() -> Data {
+    Data {
+        data_dir: "/srv/app".into(),
+        log_dir: "/srv/app/logs".into(),
+        paths: vec!["/srv/app/cache".into(), "${literal}".into()],
+        database: Database {
+            host: "db.internal".into(),
+            port: 5432,
+        },
+    }
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn interpolates_keys_env_and_defaults_toml() {
+    // `FAST_CONFIG_INTERPOLATE_PORT` is left unset, falling back to its default
+    let _env = EnvGuard::set(&[("FAST_CONFIG_INTERPOLATE_HOST", "db.internal")]);
+    let content = r#"
+        data_dir = "/srv/app"
+        log_dir = "${data_dir}/logs"
+        paths = ["${data_dir}/cache", "$${literal}"]
+
+        [database]
+        host = "${env:FAST_CONFIG_INTERPOLATE_HOST}"
+        port = "${FAST_CONFIG_INTERPOLATE_PORT:-5432}"
+    "#;
+    assert_eq!(Data::from_string(content, TOML).unwrap(), expected());
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn interpolates_chained_references_yaml() {
+    let content = r#"
+        log_dir: "${paths[0]}/../logs"
+        paths: ["${data_dir}/cache", "x"]
+        data_dir: /srv/app
+        database:
+          host: "${database.port}.example"
+          port: 5432
+    "#;
+    let loaded = Data::from_string(content, YAML).unwrap();
+    assert_eq!(loaded.log_dir, "/srv/app/cache/../logs");
+    assert_eq!(loaded.database.host, "5432.example");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn interpolation_errors_point_to_the_key() {
+    let cycle = r#"{
+        "data_dir": "${log_dir}",
+        "log_dir": "${data_dir}/logs",
+        "paths": [],
+        "database": { "host": "", "port": 1 }
+    }"#;
+    match Data::from_string(cycle, JSON) {
+        Err(fast_config::Error::Interpolation { key, message }) => {
+            assert_eq!(key, "data_dir");
+            assert!(message.contains("cycle"), "{message}");
+        }
+        other => panic!("expected an interpolation error, got {other:?}"),
+    }
+
+    let missing = r#"{
+        "data_dir": "",
+        "log_dir": "",
+        "paths": ["ok", "${FAST_CONFIG_INTERPOLATE_UNSET}"],
+        "database": { "host": "", "port": 1 }
+    }"#;
+    match Data::from_string(missing, JSON) {
+        Err(fast_config::Error::Interpolation { key, .. }) => assert_eq!(key, "paths[1]"),
+        other => panic!("expected an interpolation error, got {other:?}"),
+    }
+}
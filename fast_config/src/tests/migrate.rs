@@ -0,0 +1,192 @@
+use super::*;
+
+use fast_config::Document;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default)]
+pub struct Logging {
+    pub level: String,
+}
+
+/// Version 1 had `timeout` in seconds and a top-level `log_level`,
+/// version 2 moved `log_level` to `logging.level` and version 3 switched to `timeout_ms`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+#[fast_config(version = 3)]
+#[fast_config(migrate(v1 = "v1_to_v2", v2 = "v2_to_v3"))]
+pub struct Data {
+    pub name: String,
+    pub timeout_ms: u64,
+    pub logging: Logging,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+#[fast_config(version(current = 3, key = "schema", rewrite))]
+#[fast_config(migrate(v1 = "v1_to_v2", v2 = "v2_to_v3"))]
+pub struct Rewritten {
+    pub name: String,
+    pub timeout_ms: u64,
+    pub logging: Logging,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+#[fast_config(version(current = 3, rewrite))]
+#[fast_config(migrate(v1 = "v1_to_v2", v2 = "v2_to_v3"))]
+pub struct Checked {
+    pub name: String,
+    #[fast_config(range(min = 1))]
+    pub timeout_ms: u64,
+    pub logging: Logging,
+}
+
+fn v1_to_v2(document: &mut Document) -> Result<(), String> {
+    document.rename("log_level", "logging.level");
+    Ok(())
+}
+
+fn v2_to_v3(document: &mut Document) -> Result<(), String> {
+    let seconds = document.get::<u64>("timeout").ok_or("`timeout` must be a number of seconds")?;
+    document.set("timeout_ms", seconds * 1000)?;
+    document.remove("timeout");
+    Ok(())
+}
+
+fn expected() -> Data {
+    Data {
+        name: "app".into(),
+        timeout_ms: 5000,
+        logging: Logging { level: "debug".into() },
+    }
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn unversioned_files_go_through_every_migration() {
+    let content = "name = \"app\"\ntimeout = 5\nlog_level = \"debug\"\n";
+    assert_eq!(Data::from_string(content, TOML).unwrap(), expected());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn migrations_start_at_the_stored_version() {
+    let content = r#"{ "version": 2, "name": "app", "timeout": 5, "logging": { "level": "debug" } }"#;
+    assert_eq!(Data::from_string(content, JSON).unwrap(), expected());
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn current_files_are_read_as_they_are() {
+    let content = "version: 3\nname: app\ntimeout_ms: 5000\nlogging:\n  level: debug\n";
+    assert_eq!(Data::from_string(content, YAML).unwrap(), expected());
+}
+
+#[cfg(feature = "json5")]
+#[test]
+fn saving_writes_the_current_version() {
+    let c = MANAGER.setup();
+    let path = c.path.join("migrate/saved.json5");
+    expected().save(&path, JSON5).unwrap();
+    let saved: std::collections::BTreeMap<String, serde_json::Value> =
+        json5::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(saved["version"], 3);
+    // Read again without being migrated a second time
+    assert_eq!(Data::new(&path, JSON5).unwrap(), expected());
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn migrated_files_can_be_rewritten_with_a_backup() {
+    let c = MANAGER.setup();
+    let path = c.path.join("migrate/rewritten.toml");
+    let original = "name = \"app\"\ntimeout = 5\nlog_level = \"debug\"\n";
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, original).unwrap();
+
+    let config = Rewritten::new(&path, TOML).unwrap();
+    assert_eq!(config.timeout_ms, 5000);
+    assert_eq!(config.logging.level, "debug");
+    let backup = c.path.join("migrate/rewritten.toml.v1.bak");
+    assert_eq!(std::fs::read_to_string(backup).unwrap(), original);
+    let upgraded = std::fs::read_to_string(&path).unwrap();
+    assert!(upgraded.contains("schema = 3"));
+    assert!(upgraded.contains("timeout_ms = 5000"));
+    assert!(!upgraded.contains("log_level"));
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn files_are_only_rewritten_once_they_load() {
+    let c = MANAGER.setup();
+    let path = c.path.join("migrate/checked.toml");
+    let invalid = "name = \"app\"\ntimeout = 0\nlog_level = \"debug\"\n";
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, invalid).unwrap();
+
+    let error = Checked::new(&path, TOML).unwrap_err();
+    assert!(matches!(error.into_inner(), fast_config::Error::Validation(_)));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), invalid);
+    let backup = c.path.join("migrate/checked.toml.v1.bak");
+    assert!(!backup.exists());
+
+    // A backup that's already there is kept, and the new one goes next to it
+    let valid = invalid.replace("timeout = 0", "timeout = 5");
+    std::fs::write(&path, &valid).unwrap();
+    std::fs::write(&backup, "older").unwrap();
+    assert_eq!(Checked::new(&path, TOML).unwrap().timeout_ms, 5000);
+    assert_eq!(std::fs::read_to_string(&backup).unwrap(), "older");
+    assert_eq!(std::fs::read_to_string(c.path.join("migrate/checked.toml.v1.2.bak")).unwrap(), valid);
+    assert!(std::fs::read_to_string(&path).unwrap().contains("timeout_ms = 5000"));
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn migration_problems_are_reported() {
+    let newer = "version = 4\nname = \"app\"\n";
+    let error = Data::from_string(newer, TOML).unwrap_err();
+    assert!(matches!(error, fast_config::Error::Migration { version: 4, .. }));
+    assert!(error.to_string().contains("newer than the supported version 3"));
+
+    let broken = "version = 2\nname = \"app\"\ntimeout = \"soon\"\n";
+    let error = Data::from_string(broken, TOML).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "cannot migrate the config from version 2: `timeout` must be a number of seconds"
+    );
+}
+
+thread_local! {
+    static MIGRATIONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+fn counted_v1_to_v2(document: &mut Document) -> Result<(), String> {
+    MIGRATIONS.set(MIGRATIONS.get() + 1);
+    document.rename("log_level", "logging.level");
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+#[fast_config(version(current = 2, rewrite))]
+#[fast_config(migrate(v1 = "counted_v1_to_v2"))]
+pub struct Counted {
+    pub name: String,
+    pub logging: Logging,
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn rewritten_files_are_migrated_once() {
+    let c = MANAGER.setup();
+    let original = "name = \"app\"\nlog_level = \"debug\"\n";
+    let loads: [fn(&std::path::Path) -> Counted; 3] = [
+        |path| Counted::new(path, TOML).unwrap(),
+        |path| Counted::new_checked(path, TOML, fast_config::UnknownKeys::Deny).unwrap().value,
+        |path| Counted::new_recovering(path, TOML).unwrap().value.unwrap(),
+    ];
+    for (i, load) in loads.into_iter().enumerate() {
+        let path = c.path.join(format!("migrate/counted{i}.toml"));
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, original).unwrap();
+        MIGRATIONS.set(0);
+        assert_eq!(load(&path).logging.level, "debug");
+        assert_eq!(MIGRATIONS.get(), 1);
+        assert!(std::fs::read_to_string(&path).unwrap().contains("version = 2"));
+    }
+}
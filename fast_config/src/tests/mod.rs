@@ -8,10 +8,34 @@ pub use serde::Serialize;
 
 pub use std::path::PathBuf;
 
+mod aliases;
 mod associated;
+#[cfg(feature = "clap")]
+mod cli;
+mod convert;
+mod descriptors;
+mod env;
+mod errors;
 mod generics;
+mod includes;
+mod interpolate;
+mod migrate;
 mod nested;
+mod numbers;
+mod overrides;
+mod paths;
+mod profiles;
+mod recover;
+mod reference;
+#[cfg(feature = "schema")]
+mod schema;
 mod simple;
+mod template;
+mod unknown_fields;
+mod unknown_keys;
+mod validate;
+mod value;
+mod verify;
 
 struct Setup {
     path: PathBuf,
@@ -32,6 +56,28 @@ impl Drop for Setup {
     }
 }
 
+/// Sets environment variables for the rest of a test, removing them again when dropped. <br/>
+/// Each test uses its own prefix, so that no other test's config reads its variables.
+struct EnvGuard(Vec<&'static str>);
+impl EnvGuard {
+    fn set(vars: &[(&'static str, &str)]) -> EnvGuard {
+        for (name, value) in vars {
+            // SAFETY: the tests only touch the environment through `std::env`, which locks it
+            unsafe { std::env::set_var(name, value) };
+        }
+        EnvGuard(vars.iter().map(|(name, _)| *name).collect())
+    }
+}
+
+impl Drop for EnvGuard {
+    fn drop(&mut self) {
+        for name in &self.0 {
+            // SAFETY: see `EnvGuard::set`
+            unsafe { std::env::remove_var(name) };
+        }
+    }
+}
+
 struct Manager(std::sync::atomic::AtomicUsize);
 impl Manager {
     fn setup(&'static self) -> Setup {
@@ -0,0 +1,187 @@
+use super::*;
+
+use fast_config::Error;
+use fast_config::Format;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+#[fast_config(numbers = "native")]
+pub struct Data {
+    pub big: u64,
+    pub wide: i128,
+    pub ratio: f64,
+    pub limit: f64,
+    pub precise: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+#[fast_config(numbers = "string")]
+pub struct Strings {
+    pub big: u64,
+    pub wide: i128,
+    pub ratio: f64,
+    pub limit: f64,
+    pub small: u8,
+}
+
+/// Keeps the default policy, failing on numbers past the specification.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+pub struct Strict {
+    pub big: u64,
+    pub ratio: f64,
+}
+
+fn extremes() -> Data {
+    Data { big: u64::MAX, wide: i128::MIN, ratio: f64::NAN, limit: f64::NEG_INFINITY, precise: 0.1 + 0.2 }
+}
+
+fn unrepresentable(error: &Error) -> Vec<String> {
+    match error.inner() {
+        Error::Unrepresentable { problems, .. } => problems.iter().map(|problem| problem.path.clone()).collect(),
+        other => panic!("{other}"),
+    }
+}
+
+/// Writes the extremes with the native policy and checks every one of them is read back exactly.
+fn native_round_trip(format: Format) {
+    let written = extremes().to_string_pretty(format).unwrap();
+    let read = Data::from_string(&written, format).unwrap();
+    assert!(read.ratio.is_nan(), "{written}");
+    assert_eq!((read.big, read.wide, read.limit, read.precise), (u64::MAX, i128::MIN, f64::NEG_INFINITY, 0.1 + 0.2));
+}
+
+#[cfg(feature = "json5")]
+#[test]
+fn native_round_trip_json5() {
+    native_round_trip(JSON5);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn native_round_trip_toml() {
+    native_round_trip(TOML);
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn native_round_trip_yaml() {
+    native_round_trip(YAML);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_writes_what_it_always_has() {
+    let written = extremes().to_string(JSON).unwrap();
+    assert!(written.contains(r#""wide":-170141183460469231731687303715884105728"#), "{written}");
+    assert!(written.contains(r#""ratio":null,"limit":null"#), "{written}");
+    let fine = Data { precise: 0.1 + 0.2, ..Data::default() };
+    assert_eq!(Data::from_string(&fine.to_string(JSON).unwrap(), JSON).unwrap(), fine);
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+pub struct Single {
+    pub ratio: f32,
+    pub scales: Vec<f32>,
+}
+
+/// Checks `f32` values are written in their own shortest form, not widened to the `f64` nearest to them.
+fn f32_round_trip(format: Format) {
+    let config = Single { ratio: 1.1, scales: vec![0.1, 2.5e-7, f32::MIN_POSITIVE] };
+    let written = config.to_string(format).unwrap();
+    assert!(written.contains("1.1") && !written.contains("1.100000"), "{written}");
+    assert_eq!(Single::from_string(&written, format).unwrap(), config);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn f32_round_trip_json() {
+    f32_round_trip(JSON);
+}
+
+#[cfg(feature = "json5")]
+#[test]
+fn f32_round_trip_json5() {
+    f32_round_trip(JSON5);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn f32_round_trip_toml() {
+    f32_round_trip(TOML);
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn f32_round_trip_yaml() {
+    f32_round_trip(YAML);
+}
+
+fn string_round_trip(format: Format, expected: &[&str]) {
+    let config = Strings { big: u64::MAX, wide: i128::MAX, ratio: f64::NAN, limit: f64::INFINITY, small: 7 };
+    let written = config.to_string_pretty(format).unwrap();
+    for line in expected {
+        assert!(written.contains(line), "{written}");
+    }
+    let read = Strings::from_string(&written, format).unwrap();
+    assert!(read.ratio.is_nan(), "{written}");
+    assert_eq!((read.big, read.wide, read.limit, read.small), (u64::MAX, i128::MAX, f64::INFINITY, 7));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn string_round_trip_json() {
+    let expected = [
+        r#""big": 18446744073709551615"#,
+        r#""wide": "170141183460469231731687303715884105727""#,
+        r#""ratio": "NaN""#,
+        r#""limit": "Infinity""#,
+        r#""small": 7"#,
+    ];
+    string_round_trip(JSON, &expected);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn string_round_trip_toml() {
+    let expected = [
+        "big = \"18446744073709551615\"",
+        "wide = \"170141183460469231731687303715884105727\"",
+        "ratio = nan",
+    ];
+    string_round_trip(TOML, &expected);
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn string_round_trip_yaml() {
+    string_round_trip(YAML, &["big: 18446744073709551615", "ratio: .nan"]);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn default_policy_keeps_to_the_specification_toml() {
+    let error = Strict { big: u64::MAX, ratio: f64::NAN }.to_string(TOML).unwrap_err();
+    assert_eq!(unrepresentable(&error), ["big"]);
+    assert!(Strict { big: 1, ratio: f64::NAN }.to_string(TOML).is_ok());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn default_policy_keeps_to_the_specification_json() {
+    let error = Strict { big: u64::MAX, ratio: f64::NAN }.to_string(JSON).unwrap_err();
+    assert_eq!(unrepresentable(&error), ["ratio"]);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn saves_use_the_policy() {
+    let c = MANAGER.setup();
+    let path = c.path.join("numbers").join("strings.toml");
+    let config = Strings { big: u64::MAX, ..Strings::default() };
+    config.save(&path, TOML).unwrap();
+    assert!(std::fs::read_to_string(&path).unwrap().contains("big = \"18446744073709551615\""));
+    assert_eq!(Strings::new(&path, TOML).unwrap(), config);
+
+    let strict = Strict { big: u64::MAX, ratio: 0.5 };
+    let error = strict.save(c.path.join("numbers").join("strict.toml"), TOML).unwrap_err();
+    assert_eq!(unrepresentable(&error), ["big"]);
+}
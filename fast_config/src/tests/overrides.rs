@@ -0,0 +1,117 @@
+use super::*;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Server {
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+pub struct Data {
+    pub name: String,
+    pub ratio: f32,
+    pub tags: Vec<String>,
+    pub fallback: Option<u32>,
+    pub servers: Vec<Server>,
+}
+
+fn data() -> Data {
+    Data {
+        name: "test".into(),
+        ratio: 0.5,
+        tags: Vec::new(),
+        fallback: Some(1),
+        servers: vec![Server {
+            host: "localhost".into(),
+            port: 80,
+        }],
+    }
+}
+
+#[test]
+fn overrides_scalars_and_inline_structures() {
+    let mut config = data();
+    config
+        .apply_overrides([
+            "name=007",
+            "ratio = 1.5",
+            r#"tags=["a", 'b', c]"#,
+            "fallback=null",
+            "servers[0].port=8080",
+            r#"servers=[{host = "example.com", port = 443}, {host: other, port: 1}]"#,
+            "servers[1].host=\"quoted, with comma\"",
+        ])
+        .unwrap();
+    assert_eq!(config.name, "007");
+    assert_eq!(config.ratio, 1.5);
+    assert_eq!(config.tags, vec!["a", "b", "c"]);
+    assert_eq!(config.fallback, None);
+    assert_eq!(
+        config.servers,
+        vec![
+            Server {
+                host: "example.com".into(),
+                port: 443
+            },
+            Server {
+                host: "quoted, with comma".into(),
+                port: 1
+            },
+        ]
+    );
+}
+
+#[test]
+fn override_errors_name_the_key() {
+    let mut config = data();
+    match config.apply_overrides(["servers[0].port=99999"]) {
+        Err(fast_config::Error::Override { key, message }) => {
+            assert_eq!(key, "servers[0].port");
+            assert!(message.contains("u16"), "{message}");
+        }
+        other => panic!("expected an override error, got {other:?}"),
+    }
+    match config.apply_overrides(["ratio=fast"]) {
+        Err(fast_config::Error::Override { key, message }) => {
+            assert_eq!(key, "ratio");
+            assert!(message.contains("expected a float"), "{message}");
+        }
+        other => panic!("expected an override error, got {other:?}"),
+    }
+    assert!(config.apply_overrides(["name"]).is_err());
+    assert!(config.apply_overrides(["tags=[a, b"]).is_err());
+    assert_eq!(config, data());
+}
+
+#[cfg(feature = "clap")]
+#[test]
+fn clap_set_argument() {
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        overrides: fast_config::SetArgs,
+    }
+
+    let cli = Cli::parse_from(["app", "--set", "name=cli", "--set", "servers[0].port=1"]);
+    let mut config = data();
+    cli.overrides.apply(&mut config).unwrap();
+    assert_eq!(config.name, "cli");
+    assert_eq!(config.servers[0].port, 1);
+    assert!(Cli::try_parse_from(["app", "--set", "oops"]).is_err());
+}
+
+#[test]
+fn misspelled_keys_are_rejected() {
+    let mut config = data();
+    for key in ["nmae", "sever.port", "servers[0].prot"] {
+        match config.apply_overrides([format!("{key}=80")]) {
+            Err(fast_config::Error::UnknownPath { key: path }) => assert_eq!(path, key),
+            other => panic!("expected an unknown path error for `{key}`, got {other:?}"),
+        }
+    }
+    // Nothing is applied when any assignment fails
+    assert!(config.apply_overrides(["name=changed", "nmae=typo"]).is_err());
+    assert_eq!(config, data());
+}
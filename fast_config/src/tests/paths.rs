@@ -0,0 +1,138 @@
+use super::*;
+
+use fast_config::Error;
+use fast_config::UnknownFields;
+
+use std::collections::HashMap;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default)]
+pub struct Tls {
+    pub cert: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+#[serde(rename_all = "camelCase")]
+pub struct Server {
+    pub host: String,
+    pub port: u16,
+    pub tls: Tls,
+    pub max_clients: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+pub struct Data {
+    pub name: String,
+    pub tags: Vec<String>,
+    pub limits: HashMap<String, u32>,
+    #[fast_config(nested)]
+    pub server: Server,
+    #[fast_config(nested)]
+    pub mirrors: Vec<Server>,
+    #[serde(skip)]
+    #[fast_config(unknown)]
+    pub unknown: UnknownFields,
+}
+
+fn data() -> Data {
+    let server = |host: &str| Server {
+        host: host.into(),
+        port: 80,
+        tls: Tls { cert: "/etc/cert.pem".into() },
+        max_clients: None,
+    };
+    Data {
+        name: "app".into(),
+        tags: vec!["a".into(), "b".into()],
+        limits: HashMap::from([("cpu".into(), 2)]),
+        server: server("localhost"),
+        mirrors: vec![server("mirror")],
+        unknown: UnknownFields::default(),
+    }
+}
+
+#[test]
+fn values_are_read_by_path() {
+    let config = data();
+    assert_eq!(config.get_path("name").unwrap(), "app");
+    assert_eq!(config.get_path("server.port").unwrap(), "80");
+    assert_eq!(config.get_path("server.tls.cert").unwrap(), "/etc/cert.pem");
+    assert_eq!(config.get_path("server.maxClients").unwrap(), "null");
+    assert_eq!(config.get_path("tags").unwrap(), r#"["a", "b"]"#);
+    assert_eq!(config.get_path("tags[1]").unwrap(), "b");
+    assert_eq!(config.get_path("mirrors[0].host").unwrap(), "mirror");
+    assert_eq!(config.get_path_as::<u16>("server.port").unwrap(), 80);
+    assert_eq!(config.get_path_as::<Option<u32>>("server.maxClients").unwrap(), None);
+    assert_eq!(config.get_path_as::<Vec<String>>("tags").unwrap(), ["a", "b"]);
+}
+
+#[test]
+fn values_are_written_by_path() {
+    let mut config = data();
+    config.set_path("server.port", "8080").unwrap();
+    config.set_path("server.maxClients", "10").unwrap();
+    config.set_path("server.tls.cert", "/tmp/cert.pem").unwrap();
+    config.set_path("tags", "[x, 'y z']").unwrap();
+    config.set_path("limits.memory", "512").unwrap();
+    config.set_path_to("mirrors[0].port", 443).unwrap();
+    assert_eq!(config.server.port, 8080);
+    assert_eq!(config.server.max_clients, Some(10));
+    assert_eq!(config.server.tls.cert, "/tmp/cert.pem");
+    assert_eq!(config.tags, ["x", "y z"]);
+    assert_eq!(config.limits["memory"], 512);
+    assert_eq!(config.mirrors[0].port, 443);
+
+    // Values read back the way they were written
+    let tags = config.get_path("tags").unwrap();
+    config.set_path("tags", &tags).unwrap();
+    assert_eq!(config.tags, ["x", "y z"]);
+}
+
+#[test]
+fn unknown_paths_are_reported() {
+    let mut config = data();
+    for path in ["nmae", "server.max_clients", "server.tls.key", "tags[5]", "mirrors[0].nope", "server..port"] {
+        let is_unknown = |result: Result<(), Error>| matches!(result, Err(Error::UnknownPath { key }) if key == path);
+        assert!(is_unknown(config.get_path(path).map(drop)), "{path}");
+        assert!(is_unknown(config.set_path(path, "1")), "{path}");
+    }
+    assert_eq!(config, data());
+}
+
+#[test]
+fn unparsable_values_leave_the_config_untouched() {
+    let mut config = data();
+    let error = config.set_path("server.port", "99999").unwrap_err();
+    assert!(matches!(&error, Error::Deserialize { key, .. } if key == "server.port"), "{error}");
+    let error = config.set_path("tags", "[a, ").unwrap_err();
+    assert!(matches!(&error, Error::Deserialize { key, .. } if key == "tags"), "{error}");
+    assert!(matches!(config.get_path_as::<bool>("name"), Err(Error::Deserialize { .. })));
+    assert_eq!(config, data());
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn unknown_keys_are_kept_across_writes() {
+    let content = data().to_string(fast_config::Format::TOML).unwrap();
+    let content = content.replace("name = \"app\"", "name = \"app\"\nextra = 1");
+    let mut config = Data::from_string(&content, fast_config::Format::TOML).unwrap();
+    config.set_path("name", "renamed").unwrap();
+    assert_eq!(config.unknown.len(), 1);
+    assert!(config.to_string(fast_config::Format::TOML).unwrap().contains("extra = 1"));
+}
+
+#[test]
+fn generic_fields_are_reached_by_path() {
+    use super::generics::Data;
+    use super::generics::NestedData;
+
+    let mut config = Data {
+        string: "test".into(),
+        number: 1,
+        unsigned: 0,
+        boolean: true,
+        generic: NestedData::A,
+    };
+    config.set_path("generic", "C").unwrap();
+    assert_eq!(config.generic, NestedData::C);
+    assert_eq!(config.get_path("generic").unwrap(), "C");
+}
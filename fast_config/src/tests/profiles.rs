@@ -0,0 +1,117 @@
+use super::*;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Database {
+    pub host: String,
+    pub pool: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+#[fast_config(profiles(env = "FAST_CONFIG_PROFILES_SELECTED"))]
+pub struct Data {
+    pub debug: bool,
+    pub database: Database,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+#[fast_config(profiles, version = 1)]
+pub struct Versioned {
+    pub debug: bool,
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn selected_profile_is_merged_over_default_toml() {
+    let content = r#"
+        [default]
+        debug = true
+        database = { host = "localhost", pool = 4 }
+
+        [prod]
+        debug = false
+        database = { host = "db.internal" }
+    "#;
+    let default = Data::from_string(content, TOML).unwrap();
+    let env = EnvGuard::set(&[("FAST_CONFIG_PROFILES_SELECTED", "prod")]);
+    let selected = Data::from_string(content, TOML).unwrap();
+    drop(env);
+    assert_eq!(default.database.host, "localhost");
+    assert_eq!(
+        selected,
+        Data {
+            debug: false,
+            database: Database {
+                host: "db.internal".into(),
+                pool: 4,
+            },
+        }
+    );
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn list_and_save_profiles_yaml() {
+    let c = MANAGER.setup();
+    let path = c.path.join("config_profiles.yaml");
+    std::fs::create_dir_all(&c.path).unwrap();
+    std::fs::write(
+        &path,
+        "default:\n  debug: true\n  database:\n    host: localhost\n    pool: 4\ndev: {}\nstaging:\n  debug: false\n",
+    )
+    .unwrap();
+    assert_eq!(Data::list_profiles(&path, YAML).unwrap(), vec!["default", "dev", "staging"]);
+
+    let mut staging = Data::new_profile(&path, YAML, "staging").unwrap();
+    assert!(!staging.debug);
+    staging.database.pool = 16;
+    staging.save_profile(&path, YAML, "staging").unwrap();
+
+    // Only the differences land in the staging section, other profiles are kept
+    let saved: serde_yml::Value = serde_yml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(saved["staging"]["debug"], serde_yml::Value::Bool(false));
+    assert_eq!(saved["staging"]["database"]["pool"], serde_yml::Value::from(16));
+    assert!(saved["staging"]["database"].get("host").is_none());
+    assert_eq!(saved["default"]["database"]["pool"], serde_yml::Value::from(4));
+    assert!(saved.get("dev").is_some());
+
+    assert_eq!(Data::new_profile(&path, YAML, "staging").unwrap(), staging);
+    assert!(Data::new_profile(&path, YAML, "dev").unwrap().debug);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn unknown_profiles_are_reported() {
+    let content = r#"{ "default": { "debug": true, "database": { "host": "", "pool": 1 } } }"#;
+    let c = MANAGER.setup();
+    let path = c.path.join("config_profiles.json");
+    std::fs::create_dir_all(&c.path).unwrap();
+    std::fs::write(&path, content).unwrap();
+    match Data::new_profile(&path, JSON, "prod").map_err(fast_config::Error::into_inner) {
+        Err(fast_config::Error::Profile { profile, message }) => {
+            assert_eq!(profile, "prod");
+            assert!(message.contains("`default`"), "{message}");
+        }
+        other => panic!("expected a profile error, got {other:?}"),
+    }
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn listed_profiles_leave_out_other_keys() {
+    let c = MANAGER.setup();
+    let path = c.path.join("profiles").join("versioned.yaml");
+    Versioned { debug: true }.save_profile(&path, YAML, "default").unwrap();
+    Versioned { debug: false }.save_profile(&path, YAML, "prod").unwrap();
+    assert!(std::fs::read_to_string(&path).unwrap().contains("version: 1"));
+    assert_eq!(Versioned::list_profiles(&path, YAML).unwrap(), ["default", "prod"]);
+
+    #[cfg(feature = "schema")]
+    {
+        std::fs::write(&path, "$schema: versioned.schema.json
+version: 1
+default:
+  debug: true
+").unwrap();
+        assert_eq!(Versioned::list_profiles(&path, YAML).unwrap(), ["default"]);
+    }
+}
@@ -0,0 +1,152 @@
+use super::*;
+
+use fast_config::Location;
+use fast_config::Recovered;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Server {
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+#[serde(default, deny_unknown_fields)]
+pub struct Data {
+    pub name: String,
+    pub timeout: u32,
+    pub servers: Vec<Server>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+pub struct Strict {
+    pub name: String,
+    pub timeout: u32,
+    pub retries: u32,
+}
+
+/// `(path, line, column)` of every problem.
+fn found<T>(recovered: &Recovered<T>) -> Vec<(&str, usize, usize)> {
+    recovered
+        .problems
+        .iter()
+        .map(|p| {
+            let Location { line, column } = p.location.expect("every problem is located");
+            (p.path.as_str(), line, column)
+        })
+        .collect()
+}
+
+fn partial() -> Data {
+    Data {
+        name: "app".into(),
+        timeout: 0,
+        servers: vec![
+            Server { host: "a".into(), port: 80 },
+            Server { host: String::new(), port: 0 },
+        ],
+    }
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn every_problem_is_reported_json() {
+    let content = r#"{
+  "name": "app",
+  "timeout": "soon",
+  "colour": "red",
+  "servers": [
+    { "host": "a", "port": 80 },
+    { "hots": "b", "port": "high" }
+  ]
+}"#;
+    let recovered = Data::from_string_recovering(content, JSON).unwrap();
+    assert_eq!(
+        found(&recovered),
+        vec![
+            ("timeout", 3, 3),
+            ("colour", 4, 3),
+            ("servers[1].hots", 7, 7),
+            ("servers[1].port", 7, 20),
+        ]
+    );
+    assert!(recovered.problems[1].message.contains("unknown field `colour`"));
+    assert_eq!(recovered.value, Some(partial()));
+}
+
+#[cfg(feature = "json5")]
+#[test]
+fn every_problem_is_reported_json5() {
+    let content = "{\n  // The app\n  name: 'app',\n  timeout: 'soon',\n  servers: [\n    { host: 'a', port: 80 },\n    {\n      port: 'high',\n    },\n  ],\n}\n";
+    let recovered = Data::from_string_recovering(content, JSON5).unwrap();
+    assert_eq!(found(&recovered), vec![("timeout", 4, 3), ("servers[1].port", 8, 7)]);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn every_problem_is_reported_toml() {
+    let content = r#"
+name = "app"
+timeout = "soon"
+colour = "red"
+
+[[servers]]
+host = "a"
+port = 80
+
+[[servers]]
+hots = "b"
+port = "high"
+"#;
+    let recovered = Data::from_string_recovering(content, TOML).unwrap();
+    assert_eq!(
+        found(&recovered),
+        vec![
+            ("timeout", 3, 1),
+            ("colour", 4, 1),
+            ("servers[1].hots", 11, 1),
+            ("servers[1].port", 12, 1),
+        ]
+    );
+    assert_eq!(recovered.value, Some(partial()));
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn every_problem_is_reported_yaml() {
+    let c = MANAGER.setup();
+    let path = c.path.join("config_recover.yaml");
+    std::fs::create_dir_all(&c.path).unwrap();
+    std::fs::write(
+        &path,
+        "name: app\ntimeout: soon # seconds\ncolour: red\nservers:\n- host: a\n  port: 80\n-   hots: b\n    port: high\n",
+    )
+    .unwrap();
+    let recovered = Data::new_recovering(&path, YAML).unwrap();
+    assert_eq!(
+        found(&recovered),
+        vec![
+            ("timeout", 2, 1),
+            ("colour", 3, 1),
+            ("servers[1].hots", 7, 5),
+            ("servers[1].port", 8, 5),
+        ]
+    );
+    assert_eq!(recovered.value, Some(partial()));
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn no_value_without_defaults() {
+    let recovered = Strict::from_string_recovering("name = \"app\"\ntimeout = \"soon\"\nretries = 2\n", TOML).unwrap();
+    assert_eq!(found(&recovered), vec![("timeout", 2, 1)]);
+    assert!(recovered.value.is_none());
+
+    // Missing keys are located at their parent
+    let recovered = Strict::from_string_recovering("name = \"app\"\ntimeout = 1\n", TOML).unwrap();
+    assert_eq!(found(&recovered), vec![("retries", 1, 1)]);
+    assert_eq!(recovered.into_result().unwrap_err()[0].message, "missing field `retries`");
+
+    let fine = Strict::from_string_recovering("name = \"app\"\ntimeout = 1\nretries = 2\n", TOML).unwrap();
+    assert_eq!(fine.into_result().unwrap().retries, 2);
+}
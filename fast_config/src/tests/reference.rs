@@ -0,0 +1,113 @@
+use super::*;
+
+use fast_config::MarkdownStyle;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Debug,
+    Info,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+pub struct Server {
+    /// The address to listen on.
+    pub host: String,
+    #[fast_config(range(min = 1, max = 65535), env = "PORT")]
+    pub port: u16,
+}
+
+/// The settings of a server, documented in a checked-in page.
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+#[fast_config(env_prefix = "APP")]
+pub struct Data {
+    /// The name shown in the window title.
+    pub name: String,
+    /// How much to log.
+    ///
+    /// Set to `debug` when reporting a bug.
+    pub level: Level,
+    /// A file to log into as well.
+    pub log_file: Option<String>,
+    #[fast_config(length(max = 4))]
+    pub tags: Vec<String>,
+    #[fast_config(nested)]
+    pub server: Server,
+}
+
+impl Default for Data {
+    fn default() -> Self {
+        Self {
+            name: "app".into(),
+            level: Level::Info,
+            log_file: None,
+            tags: vec!["a|b".into()],
+            server: Server {
+                host: "localhost".into(),
+                port: 8080,
+            },
+        }
+    }
+}
+
+#[test]
+fn fields_carry_their_reference() {
+    let reference = Data::reference().unwrap();
+    let paths = reference.fields.iter().map(|field| field.path.as_str()).collect::<Vec<_>>();
+    assert_eq!(paths, ["name", "level", "log_file", "tags", "server", "server.host", "server.port"]);
+
+    let field = |path: &str| reference.fields.iter().find(|field| field.path == path).unwrap();
+    let port = field("server.port");
+    assert_eq!(port.type_name, "u16");
+    assert_eq!(port.default.as_deref(), Some("8080"));
+    assert_eq!(port.env.as_deref(), Some("PORT"));
+    assert_eq!(port.rules, ["Range: 1 to 65535"]);
+
+    let host = field("server.host");
+    assert_eq!(host.default.as_deref(), Some("\"localhost\""));
+    assert_eq!(host.env.as_deref(), Some("APP_SERVER__HOST"));
+
+    // Nested tables are described through their own fields
+    let server = field("server");
+    assert_eq!((&server.default, &server.env), (&None, &None));
+
+    let log_file = field("log_file");
+    assert_eq!(log_file.type_name, "Option<String>");
+    assert_eq!(log_file.default, None);
+    assert_eq!(field("level").rules, ["Allowed values: debug, info"]);
+}
+
+#[test]
+fn references_render_as_a_markdown_table() {
+    let expected = r#"| Key | Type | Default | Environment | Description |
+|---|---|---|---|---|
+| `name` | `String` | `"app"` | `APP_NAME` | The name shown in the window title. |
+| `level` | `Level` | `"info"` | `APP_LEVEL` | How much to log.<br>Set to `debug` when reporting a bug.<br>Allowed values: debug, info |
+| `log_file` | `Option<String>` |  | `APP_LOG_FILE` | A file to log into as well. |
+| `tags` | `Vec<String>` | `["a\|b"]` | `APP_TAGS` | Length: at most 4 |
+| `server` | `Server` |  |  |  |
+| `server.host` | `String` | `"localhost"` | `APP_SERVER__HOST` | The address to listen on. |
+| `server.port` | `u16` | `8080` | `PORT` | Range: 1 to 65535 |
+"#;
+    assert_eq!(Data::reference().unwrap().to_markdown(MarkdownStyle::Table), expected);
+}
+
+#[test]
+fn references_render_as_markdown_sections() {
+    let markdown = Data::reference().unwrap().to_markdown(MarkdownStyle::Sections);
+    let expected = r#"## `level`
+
+How much to log.
+
+Set to `debug` when reporting a bug.
+
+- Type: `Level`
+- Default: `"info"`
+- Environment: `APP_LEVEL`
+- Allowed values: debug, info
+"#;
+    assert!(markdown.starts_with("## `name`\n\nThe name shown in the window title.\n\n- Type: `String`\n"));
+    assert!(markdown.contains(expected), "{markdown}");
+    let port = "## `server.port`\n\n- Type: `u16`\n- Default: `8080`\n- Environment: `PORT`\n- Range: 1 to 65535\n";
+    assert!(markdown.ends_with(port));
+}
@@ -0,0 +1,200 @@
+use super::*;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig, schemars::JsonSchema)]
+pub struct Server {
+    #[fast_config(non_empty)]
+    pub host: String,
+    #[fast_config(range(min = 1, max = 65535))]
+    pub port: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig, schemars::JsonSchema)]
+pub struct Data {
+    #[fast_config(length(min = 3, max = 16))]
+    pub name: String,
+    #[fast_config(one_of("debug", "info", "warn"))]
+    pub level: String,
+    #[fast_config(range(min = 0.0, max = 1.0))]
+    pub ratio: Option<f64>,
+    #[fast_config(non_empty, each(length(max = 8)))]
+    pub tags: Vec<String>,
+    #[fast_config(nested)]
+    pub server: Server,
+}
+
+fn example() -> Data {
+    Data {
+        name: "app".into(),
+        level: "info".into(),
+        ratio: Some(0.5),
+        tags: vec!["web".into()],
+        server: Server {
+            host: "localhost".into(),
+            port: 8080,
+        },
+    }
+}
+
+#[test]
+fn field_rules_end_up_in_the_schema() {
+    let schema = Data::json_schema();
+    let properties = &schema["properties"];
+    assert_eq!(properties["name"]["minLength"], 3);
+    assert_eq!(properties["name"]["maxLength"], 16);
+    assert_eq!(properties["level"]["enum"], serde_json::json!(["debug", "info", "warn"]));
+    assert_eq!(properties["ratio"]["minimum"], 0);
+    assert_eq!(properties["ratio"]["maximum"], 1);
+    assert_eq!(properties["tags"]["minItems"], 1);
+    assert_eq!(properties["tags"]["items"]["maxLength"], 8);
+    assert_eq!(properties["server"]["properties"]["host"]["minLength"], 1);
+    assert_eq!(properties["server"]["properties"]["port"]["minimum"], 1);
+    assert_eq!(properties["server"]["properties"]["port"]["maximum"], 65535);
+    let required = schema["required"].as_array().unwrap();
+    assert!(required.contains(&"name".into()));
+    assert!(!required.contains(&"ratio".into()));
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn toml_files_point_at_their_schema() {
+    let c = MANAGER.setup();
+    let path = c.path.join("schema/config.toml");
+    example().save_with_schema(&path, TOML).unwrap();
+
+    let schema = std::fs::read_to_string(c.path.join("schema/config.schema.json")).unwrap();
+    let schema: serde_json::Value = serde_json::from_str(&schema).unwrap();
+    assert_eq!(schema, Data::json_schema());
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(saved.starts_with("#:schema ./config.schema.json\n"));
+    assert_eq!(Data::new(&path, TOML).unwrap(), example());
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn yaml_files_point_at_their_schema() {
+    let c = MANAGER.setup();
+    let path = c.path.join("schema/config.yaml");
+    example().save_with_schema(&path, YAML).unwrap();
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(saved.starts_with("# yaml-language-server: $schema=./config.schema.json\n"));
+    assert_eq!(Data::new(&path, YAML).unwrap(), example());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_files_point_at_their_schema() {
+    let c = MANAGER.setup();
+    let path = c.path.join("schema/settings.json");
+    example().save_with_schema(&path, JSON).unwrap();
+    assert!(c.path.join("schema/settings.schema.json").exists());
+
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(saved.starts_with("{\n  \"$schema\": \"./settings.schema.json\",\n"));
+    // The reference isn't taken for an unknown key
+    let loaded = Data::new_checked(&path, JSON, fast_config::UnknownKeys::Deny).unwrap();
+    assert_eq!(loaded.value, example());
+}
+
+fn service_schema() -> fast_config::Schema {
+    fast_config::Schema::new(serde_json::json!({
+        "type": "object",
+        "required": ["name", "server"],
+        "additionalProperties": false,
+        "properties": {
+            "name": { "type": "string", "minLength": 3 },
+            "level": { "enum": ["debug", "info", "warn"] },
+            "server": { "$ref": "#/$defs/Server" },
+            "backup": { "anyOf": [{ "$ref": "#/$defs/Server" }, { "type": "null" }] },
+            "tags": { "type": "array", "items": { "type": "string" }, "uniqueItems": true }
+        },
+        "$defs": {
+            "Server": {
+                "type": "object",
+                "required": ["host"],
+                "properties": {
+                    "host": { "type": "string" },
+                    "port": { "type": "integer", "minimum": 1, "maximum": 65535 }
+                }
+            }
+        }
+    }))
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn documents_are_checked_without_their_type() {
+    let content = r#"
+        name = "ab"
+        levle = "info"
+        tags = ["a", 2, "a"]
+
+        [server]
+        host = "localhost"
+        port = 70000
+
+        [backup]
+        port = 0
+    "#;
+    let problems = service_schema().problems(content, TOML).unwrap();
+    let found = problems.iter().map(ToString::to_string).collect::<Vec<_>>();
+    assert_eq!(
+        found,
+        [
+            "2:9: `name`: length must be at least 3",
+            "3:9: `levle`: unknown key, did you mean `level`?",
+            "4:9: `tags`: must not contain duplicates",
+            "4:22: `tags[1]`: expected a string, found an integer",
+            "8:9: `server.port`: must be between 1 and 65535",
+            // Missing keys point at their table
+            "10:10: `backup.host`: is missing",
+            "11:9: `backup.port`: must be between 1 and 65535",
+        ]
+    );
+    let valid = r#"
+        name = "api"
+        level = "info"
+        [server]
+        host = "localhost"
+    "#;
+    assert!(service_schema().problems(valid, TOML).unwrap().is_empty());
+    assert!(service_schema().validate_str(valid, TOML).is_ok());
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn files_failing_the_schema_point_at_the_first_problem() {
+    let c = MANAGER.setup();
+    let path = c.path.join("schema/service.yaml");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, "name: api\nserver:\n  host: localhost\n  port: 0\nextra: true\n").unwrap();
+
+    let error = service_schema().validate_file(&path, YAML).unwrap_err();
+    assert_eq!(error.key(), Some("server.port"));
+    assert_eq!(error.line(), Some(4));
+    let fast_config::Error::File(file) = &error else {
+        panic!("expected a file error, got {error:?}");
+    };
+    let fast_config::Error::Validation(violations) = &file.source else {
+        panic!("expected a validation error, got {:?}", file.source);
+    };
+    assert_eq!(violations.len(), 2);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn saved_schemas_check_files_of_other_formats() {
+    let c = MANAGER.setup();
+    let path = c.path.join("schema/generated.json");
+    example().save_with_schema(&path, JSON).unwrap();
+    let schema = fast_config::Schema::from_file(c.path.join("schema/generated.schema.json")).unwrap();
+    assert_eq!(schema, fast_config::Schema::of::<Data>());
+    schema.validate_file(&path, JSON).unwrap();
+
+    let content = r#"{ "name": "app", "level": "trace", "ratio": 2.5, "tags": [], "server": { "host": "", "port": 80 } }"#;
+    let problems = schema.validate_str(content, JSON).unwrap_err().to_string();
+    assert_eq!(
+        problems,
+        "invalid config: `level`: must be one of `debug`, `info`, `warn`; `ratio`: must be between 0 and 1; \
+         `tags`: length must be at least 1; `server.host`: length must be at least 1"
+    );
+}
@@ -0,0 +1,182 @@
+use super::*;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Debug,
+    Info,
+    Warn,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+pub struct Server {
+    /// The address to listen on.
+    pub host: String,
+    #[fast_config(range(min = 1, max = 65535))]
+    pub port: u16,
+}
+
+/// Documented defaults, written out on the first run.
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+pub struct Data {
+    /// The name shown in the window title.
+    pub name: String,
+    /// How much to log.
+    pub level: Level,
+    /// A file to log into as well.
+    /// Leave unset to only log to the terminal.
+    pub log_file: Option<String>,
+    #[fast_config(one_of("light", "dark"))]
+    pub theme: String,
+    #[fast_config(nested)]
+    pub server: Server,
+    /// A server to fall back to.
+    pub backup: Option<Server>,
+}
+
+impl Default for Data {
+    fn default() -> Self {
+        Self {
+            name: "app".into(),
+            level: Level::Info,
+            log_file: None,
+            theme: "dark".into(),
+            server: Server {
+                host: "localhost".into(),
+                port: 8080,
+            },
+            backup: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub enum Shape {
+    Point,
+    Circle(u32),
+    Rect { w: u32, h: u32 },
+}
+
+/// Enum variants holding data, which YAML writes as tags.
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+pub struct Drawing {
+    /// Drawn first.
+    pub first: Shape,
+    pub second: Shape,
+    pub third: Shape,
+    pub extra: Option<Shape>,
+}
+
+impl Default for Drawing {
+    fn default() -> Self {
+        Self {
+            first: Shape::Point,
+            second: Shape::Circle(3),
+            third: Shape::Rect { w: 1, h: 2 },
+            extra: None,
+        }
+    }
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn toml_templates_are_documented() {
+    let expected = r#"# The name shown in the window title.
+name = "app"
+
+# How much to log.
+# Allowed values: debug, info, warn
+level = "info"
+
+# A file to log into as well.
+# Leave unset to only log to the terminal.
+# log_file = ""
+
+# Allowed values: light, dark
+theme = "dark"
+
+[server]
+# The address to listen on.
+host = "localhost"
+
+# Range: 1 to 65535
+port = 8080
+
+# A server to fall back to.
+# [backup]
+# host = ""
+# port = 0
+"#;
+    assert_eq!(Data::template(TOML).unwrap(), expected);
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn yaml_templates_comment_out_unset_options() {
+    let template = Data::template(YAML).unwrap();
+    assert!(template.contains("# How much to log.\n# Allowed values: debug, info, warn\nlevel: info\n"));
+    assert!(template.contains("server:\n  # The address to listen on.\n  host: localhost\n"));
+    assert!(template.contains("# backup:\n  # host: ''\n  # port: 0\n"));
+
+    let template = Drawing::template(YAML).unwrap();
+    assert!(template.contains("second: !Circle 3\n"), "{template}");
+    assert!(template.contains("third: !Rect\n  w: 1\n  h: 2\n"), "{template}");
+}
+
+#[cfg(feature = "json5")]
+#[test]
+fn json5_templates_comment_out_unset_options() {
+    let template = Data::template(JSON5).unwrap();
+    assert!(template.starts_with("{\n  // The name shown in the window title.\n  \"name\": \"app\",\n"));
+    assert!(template.contains("  // Leave unset to only log to the terminal.\n  // \"log_file\": \"\",\n"));
+    assert!(template.contains("    // Range: 1 to 65535\n    \"port\": 8080,\n"));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_templates_leave_out_unset_options() {
+    let expected = r#"{
+  "name": "app",
+  "level": "info",
+  "theme": "dark",
+  "server": {
+    "host": "localhost",
+    "port": 8080
+  }
+}
+"#;
+    assert_eq!(Data::template(JSON).unwrap(), expected);
+}
+
+fn reads_back_as_the_defaults(format: fast_config::Format, name: &str) {
+    let c = MANAGER.setup();
+    let path = c.path.join("template").join(name);
+    Data::write_template(&path, format).unwrap();
+    assert_eq!(Data::new(&path, format).unwrap(), Data::default());
+    let template = Drawing::template(format).unwrap();
+    assert_eq!(Drawing::from_string(&template, format).unwrap(), Drawing::default(), "{template}");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn written_templates_read_back_json() {
+    reads_back_as_the_defaults(JSON, "config.json");
+}
+
+#[cfg(feature = "json5")]
+#[test]
+fn written_templates_read_back_json5() {
+    reads_back_as_the_defaults(JSON5, "config.json5");
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn written_templates_read_back_toml() {
+    reads_back_as_the_defaults(TOML, "config.toml");
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn written_templates_read_back_yaml() {
+    reads_back_as_the_defaults(YAML, "config.yaml");
+}
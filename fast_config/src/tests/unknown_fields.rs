@@ -0,0 +1,152 @@
+use super::*;
+
+use fast_config::UnknownFields;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct Server {
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct Logging {
+    pub level: String,
+}
+
+/// What an older build knows about.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+#[serde(default)]
+#[fast_config(env_prefix = "FAST_CONFIG_KEPT")]
+pub struct Old {
+    pub name: String,
+    pub logging: Logging,
+    pub servers: Vec<Server>,
+    #[serde(skip)]
+    #[fast_config(unknown)]
+    pub unknown: UnknownFields,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct NewServer {
+    pub host: String,
+    pub port: u16,
+    pub weight: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct NewLogging {
+    pub level: String,
+    pub targets: Vec<String>,
+}
+
+/// What a newer build writes.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+#[serde(default)]
+pub struct New {
+    pub name: String,
+    pub theme: String,
+    pub logging: NewLogging,
+    pub servers: Vec<NewServer>,
+}
+
+fn newer() -> New {
+    New {
+        name: "app".into(),
+        theme: "dark".into(),
+        logging: NewLogging { level: "info".into(), targets: vec!["stderr".into(), "file".into()] },
+        servers: vec![
+            NewServer { host: "a".into(), port: 80, weight: 1 },
+            NewServer { host: "b".into(), port: 81, weight: 5 },
+        ],
+    }
+}
+
+fn round_trip(format: fast_config::Format, file: &str, pretty: bool) {
+    let c = MANAGER.setup();
+    let path = c.path.join("unknown_fields").join(file);
+    newer().save(&path, format).unwrap();
+
+    let mut old = Old::new(&path, format).unwrap();
+    let mut kept = old.unknown.paths().collect::<Vec<_>>();
+    kept.sort();
+    assert_eq!(kept, vec!["logging.targets", "servers[0].weight", "servers[1].weight", "theme"]);
+    old.logging.level = "debug".into();
+    old.servers[1].port = 8080;
+    match pretty {
+        true => old.save_pretty(&path, format).unwrap(),
+        false => old.save(&path, format).unwrap(),
+    }
+
+    let mut expected = newer();
+    expected.logging.level = "debug".into();
+    expected.servers[1].port = 8080;
+    assert_eq!(New::new(&path, format).unwrap(), expected);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn unknown_fields_survive_a_save_json() {
+    round_trip(JSON, "config.json", false);
+}
+
+#[cfg(feature = "json5")]
+#[test]
+fn unknown_fields_survive_a_save_json5() {
+    round_trip(JSON5, "config.json5", true);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn unknown_fields_survive_a_save_toml() {
+    round_trip(TOML, "config.toml", true);
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn unknown_fields_survive_a_save_yaml() {
+    round_trip(YAML, "config.yaml", false);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn unknown_fields_are_kept_by_from_string() {
+    let content = "name = \"app\"\ntheme = \"dark\"\n\n[logging]\nlevel = \"info\"\ntargets = [\"stderr\"]\n";
+    let old = Old::from_string(content, TOML).unwrap();
+    assert_eq!(old.unknown.paths().collect::<Vec<_>>(), vec!["theme", "logging.targets"]);
+    let written = old.to_string(TOML).unwrap();
+    assert!(written.contains("theme = \"dark\""));
+    assert!(written.contains("targets = [\"stderr\"]"));
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn fields_win_over_kept_keys() {
+    let content = "name = \"app\"\n\n[[servers]]\nhost = \"a\"\nweight = 1\n\n[[servers]]\nhost = \"b\"\nweight = 2\n";
+    let mut old = Old::from_string(content, TOML).unwrap();
+    // The second server is gone, and its kept key with it
+    old.servers.pop();
+    let new = New::from_string(&old.to_string(TOML).unwrap(), TOML).unwrap();
+    assert_eq!(new.servers, vec![NewServer { host: "a".into(), port: 0, weight: 1 }]);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn unknown_fields_survive_env_and_overrides() {
+    let c = MANAGER.setup();
+    let path = c.path.join("unknown_fields").join("patched.toml");
+    newer().save(&path, TOML).unwrap();
+
+    let _env = EnvGuard::set(&[("FAST_CONFIG_KEPT_NAME", "from env")]);
+    let mut old = Old::new(&path, TOML).unwrap();
+    assert_eq!(old.name, "from env");
+    assert_eq!(old.unknown.len(), 4);
+    old.apply_overrides(["logging.level=debug"]).unwrap();
+    assert_eq!(old.unknown.len(), 4);
+    old.save(&path, TOML).unwrap();
+    let new = New::new(&path, TOML).unwrap();
+    assert_eq!((new.name.as_str(), new.theme.as_str(), new.logging.level.as_str()), ("from env", "dark", "debug"));
+}
@@ -0,0 +1,188 @@
+use super::*;
+
+use fast_config::Loaded;
+use fast_config::UnknownKeys;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct Server {
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct Logging {
+    pub level: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+#[serde(default)]
+pub struct Data {
+    pub name: String,
+    pub servers: Vec<Server>,
+    pub logging: Option<Logging>,
+    pub labels: std::collections::BTreeMap<String, String>,
+}
+
+/// `(path, line, column)` of every warning.
+fn found<T>(loaded: &Loaded<T>) -> Vec<(&str, usize, usize)> {
+    loaded
+        .warnings
+        .iter()
+        .map(|w| {
+            let location = w.location.expect("every unknown key is located");
+            (w.path.as_str(), location.line, location.column)
+        })
+        .collect()
+}
+
+fn expected() -> Data {
+    Data {
+        name: "app".into(),
+        servers: vec![Server { host: "a".into(), port: 80 }, Server { host: "b".into(), port: 0 }],
+        logging: Some(Logging { level: "debug".into() }),
+        labels: [("team".to_owned(), "core".to_owned())].into(),
+    }
+}
+
+#[cfg(feature = "toml")]
+const TOML_CONTENT: &str = r#"name = "app"
+colour = "red"
+
+[labels]
+team = "core"
+
+[logging]
+level = "debug"
+verbose = true
+
+[[servers]]
+host = "a"
+port = 80
+
+[[servers]]
+host = "b"
+prot = 8080
+"#;
+
+#[cfg(feature = "toml")]
+#[test]
+fn unknown_keys_are_warned_about_toml() {
+    let loaded = Data::from_string_checked(TOML_CONTENT, TOML, UnknownKeys::Warn).unwrap();
+    assert_eq!(
+        found(&loaded),
+        vec![("colour", 2, 1), ("logging.verbose", 9, 1), ("servers[1].prot", 17, 1)]
+    );
+    assert_eq!(loaded.warnings[2].message, "unknown key, did you mean `port`?");
+    assert_eq!(loaded.warnings[0].message, "unknown key");
+    assert_eq!(loaded.value, expected());
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn unknown_keys_are_ignored_by_default_toml() {
+    let loaded = Data::from_string_checked(TOML_CONTENT, TOML, UnknownKeys::default()).unwrap();
+    assert!(loaded.warnings.is_empty());
+    assert_eq!(loaded.value, expected());
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn unknown_keys_can_be_denied_toml() {
+    let c = MANAGER.setup();
+    let path = c.path.join("unknown_keys/denied.toml");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, TOML_CONTENT).unwrap();
+
+    let error = Data::new_checked(&path, TOML, UnknownKeys::Deny).unwrap_err();
+    assert_eq!(error.key(), Some("colour"));
+    assert_eq!(error.line(), Some(2));
+    let fast_config::Error::UnknownKeys(keys) = error.into_inner() else {
+        panic!("expected unknown keys");
+    };
+    let paths = keys.iter().map(|key| key.path.as_str()).collect::<Vec<_>>();
+    assert_eq!(paths, vec!["colour", "logging.verbose", "servers[1].prot"]);
+
+    std::fs::write(&path, "name = \"app\"\n").unwrap();
+    let loaded = Data::new_checked(&path, TOML, UnknownKeys::Deny).unwrap();
+    assert_eq!(loaded.value.name, "app");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn unknown_keys_are_warned_about_json() {
+    let content = r#"{
+  "name": "app",
+  "colour": "red",
+  "labels": { "team": "core" },
+  "logging": { "level": "debug", "verbose": true },
+  "servers": [
+    { "host": "a", "port": 80 },
+    { "host": "b", "prot": 8080 }
+  ]
+}"#;
+    let loaded = Data::from_string_checked(content, JSON, UnknownKeys::Warn).unwrap();
+    assert_eq!(
+        found(&loaded),
+        vec![("colour", 3, 3), ("logging.verbose", 5, 34), ("servers[1].prot", 8, 20)]
+    );
+    assert_eq!(loaded.value, expected());
+}
+
+#[cfg(feature = "json5")]
+#[test]
+fn unknown_keys_are_warned_about_json5() {
+    let content = "{\n  name: 'app',\n  colour: 'red',\n  labels: { team: 'core' },\n  logging: { level: 'debug', verbose: true },\n  servers: [\n    { host: 'a', port: 80 },\n    { host: 'b', prot: 8080 },\n  ],\n}\n";
+    let loaded = Data::from_string_checked(content, JSON5, UnknownKeys::Warn).unwrap();
+    assert_eq!(
+        found(&loaded),
+        vec![("colour", 3, 3), ("logging.verbose", 5, 30), ("servers[1].prot", 8, 18)]
+    );
+    assert_eq!(loaded.value, expected());
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn unknown_keys_are_warned_about_yaml() {
+    let content = "name: app\ncolour: red\nlabels:\n  team: core\nlogging:\n  level: debug\n  verbose: true\nservers:\n  - host: a\n    port: 80\n  - host: b\n    prot: 8080\n";
+    let loaded = Data::from_string_checked(content, YAML, UnknownKeys::Warn).unwrap();
+    assert_eq!(
+        found(&loaded),
+        vec![("colour", 2, 1), ("logging.verbose", 7, 3), ("servers[1].prot", 12, 5)]
+    );
+    assert_eq!(loaded.value, expected());
+}
+
+thread_local! {
+    static WARNINGS: std::cell::RefCell<Vec<String>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+fn record(warning: &fast_config::Problem) {
+    WARNINGS.with_borrow_mut(|warnings| warnings.push(warning.to_string()));
+}
+
+/// Hands the unknown keys `new_checked` would warn about to `record`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+#[serde(default)]
+#[fast_config(on_warning = "record")]
+pub struct Warned {
+    pub server: Server,
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn plain_loads_hand_unknown_keys_to_the_hook() {
+    let c = MANAGER.setup();
+    let path = c.path.join("unknown_keys").join("warned.toml");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, "[server]\nhost = \"a\"\nprot = 80\n").unwrap();
+    let expected = Warned { server: Server { host: "a".into(), port: 0 } };
+    assert_eq!(Warned::new(&path, TOML).unwrap(), expected);
+    let mut warned = Warned::default();
+    warned.load(&path, TOML).unwrap();
+    assert_eq!(warned, expected);
+    assert_eq!(Warned::from_string("[server]\nhost = \"a\"\n", TOML).unwrap(), expected);
+    let warning = "3:1: `server.prot`: unknown key, did you mean `port`?";
+    assert_eq!(WARNINGS.take(), [warning, warning]);
+}
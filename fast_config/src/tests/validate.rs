@@ -0,0 +1,276 @@
+use super::*;
+
+use fast_config::Validate;
+use fast_config::Violations;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+#[fast_config(validate(on_save))]
+pub struct Data {
+    pub url: String,
+    pub port: u16,
+    pub min: u32,
+    pub max: u32,
+}
+
+impl Validate for Data {
+    fn validate(&self) -> Result<(), Violations> {
+        let mut violations = Violations::new();
+        if self.url.is_empty() {
+            violations.push("url", "must not be empty");
+        }
+        if self.port == 0 {
+            violations.push("port", "must not be 0");
+        }
+        if self.min > self.max {
+            violations.push("min", "must not be greater than `max`");
+        }
+        violations.into_result()
+    }
+}
+
+fn valid() -> Data {
+    Data {
+        url: "https://example.com".into(),
+        port: 443,
+        min: 1,
+        max: 2,
+    }
+}
+
+fn invalid() -> Data {
+    Data {
+        url: String::new(),
+        port: 0,
+        min: 3,
+        max: 2,
+    }
+}
+
+fn paths(error: fast_config::Error) -> Vec<String> {
+    match error.into_inner() {
+        fast_config::Error::Validation(violations) => violations.into_iter().map(|v| v.path).collect(),
+        other => panic!("expected a validation error, got {other:?}"),
+    }
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn validation_runs_on_every_load() {
+    let c = MANAGER.setup();
+    let path = c.path.join("config_validate.json");
+    valid().save(&path, JSON).unwrap();
+    assert_eq!(Data::new(&path, JSON).unwrap(), valid());
+
+    let content = invalid().to_string(JSON).unwrap();
+    std::fs::write(&path, &content).unwrap();
+    let expected = vec!["url", "port", "min"];
+    assert_eq!(paths(Data::from_string(&content, JSON).unwrap_err()), expected);
+    assert_eq!(paths(Data::new(&path, JSON).unwrap_err()), expected);
+    let mut config = valid();
+    assert_eq!(paths(config.load(&path, JSON).unwrap_err()), expected);
+    assert_eq!(config, valid());
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn invalid_configs_are_not_saved() {
+    let c = MANAGER.setup();
+    let path = c.path.join("config_validate_refused.toml");
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(paths(invalid().save_pretty(&path, TOML).unwrap_err()).len(), 3);
+    assert!(!path.exists());
+}
+
+#[test]
+fn nested_violations_are_prefixed() {
+    let mut nested = Violations::new();
+    nested.push("port", "must not be 0");
+    nested.push("", "is missing a host");
+    let mut violations = Violations::new();
+    violations.extend_nested("servers[2]", nested);
+    assert_eq!(
+        violations.to_string(),
+        "`servers[2].port`: must not be 0; `servers[2]`: is missing a host"
+    );
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+pub struct Server {
+    #[fast_config(non_empty)]
+    pub host: String,
+    #[fast_config(range(min = 1, max = 65535))]
+    pub port: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+pub struct Rules {
+    #[fast_config(length(min = 3, max = 16))]
+    #[cfg_attr(feature = "regex", fast_config(regex = "^[a-z-]+$"))]
+    pub name: String,
+    #[fast_config(one_of("debug", "info", "warn"))]
+    pub level: String,
+    #[fast_config(range(min = 0.0, max = 1.0))]
+    pub ratio: Option<f64>,
+    #[fast_config(path_exists)]
+    pub data_dir: Option<std::path::PathBuf>,
+    #[fast_config(custom = "even")]
+    pub workers: u32,
+    #[fast_config(nested)]
+    pub servers: Vec<Server>,
+}
+
+fn even(workers: &u32) -> Result<(), String> {
+    match workers % 2 {
+        0 => Ok(()),
+        _ => Err(format!("{workers} is not even")),
+    }
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn field_rules_report_every_violation_toml() {
+    let content = r#"
+        name = "Ab"
+        level = "trace"
+        ratio = 1.5
+        data_dir = "./does/not/exist"
+        workers = 3
+
+        [[servers]]
+        host = "a"
+        port = 80
+
+        [[servers]]
+        host = ""
+        port = 0
+    "#;
+    let violations = match Rules::from_string(content, TOML).unwrap_err() {
+        fast_config::Error::Validation(violations) => violations,
+        other => panic!("expected a validation error, got {other:?}"),
+    };
+    let mut expected = vec![
+        ("name", "length must be between 3 and 16"),
+        ("name", "must match `^[a-z-]+$`"),
+        ("level", "must be one of `debug`, `info`, `warn`"),
+        ("ratio", "must be between 0.0 and 1.0"),
+        ("data_dir", "path `./does/not/exist` does not exist"),
+        ("workers", "3 is not even"),
+        ("servers[1].host", "must not be empty"),
+        ("servers[1].port", "must be between 1 and 65535"),
+    ];
+    if cfg!(not(feature = "regex")) {
+        expected.remove(1);
+    }
+    let found = violations
+        .iter()
+        .map(|v| (v.path.as_str(), v.message.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(found, expected);
+
+    let valid = content
+        .replace("\"Ab\"", "\"api\"")
+        .replace("trace", "info")
+        .replace("1.5", "0.5")
+        .replace("./does/not/exist", ".")
+        .replace("workers = 3", "workers = 4")
+        .replace("host = \"\"", "host = \"b\"")
+        .replace("port = 0", "port = 8080");
+    assert_eq!(Rules::from_string(&valid, TOML).unwrap().servers[1].port, 8080);
+}
+
+#[test]
+fn field_rules_are_described_as_constraints() {
+    use fast_config::Bound;
+    use fast_config::Rule;
+
+    let found = Rules::constraints()
+        .into_iter()
+        .map(|constraint| (constraint.path, constraint.rule))
+        .collect::<Vec<_>>();
+    let mut expected = vec![
+        ("name".to_owned(), Rule::Length { min: Some(3), max: Some(16) }),
+        ("name".to_owned(), Rule::Regex("^[a-z-]+$".into())),
+        ("level".to_owned(), Rule::OneOf(vec!["\"debug\"".into(), "\"info\"".into(), "\"warn\"".into()])),
+        ("ratio".to_owned(), Rule::Range { min: Some(Bound::Float(0.0)), max: Some(Bound::Float(1.0)) }),
+        ("data_dir".to_owned(), Rule::PathExists),
+        ("workers".to_owned(), Rule::Custom("even".into())),
+    ];
+    if cfg!(not(feature = "regex")) {
+        expected.remove(1);
+    }
+    assert_eq!(found, expected);
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+pub struct Items {
+    #[fast_config(each(range(min = 1, max = 65535)))]
+    pub ports: Vec<u32>,
+    #[fast_config(each(non_empty, custom = "lowercase"))]
+    pub labels: Option<std::collections::BTreeMap<String, String>>,
+    #[fast_config(range(max = 9007199254740993))]
+    pub limit: u64,
+}
+
+fn lowercase(label: &str) -> Result<(), String> {
+    match label.chars().any(char::is_uppercase) {
+        true => Err(format!("`{label}` is not lowercase")),
+        false => Ok(()),
+    }
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn item_rules_check_every_item_toml() {
+    let content = r#"
+        ports = [80, 0, 70000]
+        limit = 9007199254740994
+
+        [labels]
+        a = "x"
+        b = ""
+        c = "Y"
+    "#;
+    let violations = match Items::from_string(content, TOML).unwrap_err() {
+        fast_config::Error::Validation(violations) => violations,
+        other => panic!("expected a validation error, got {other:?}"),
+    };
+    let found = violations
+        .iter()
+        .map(|v| (v.path.as_str(), v.message.as_str()))
+        .collect::<Vec<_>>();
+    let expected = vec![
+        ("ports[1]", "must be between 1 and 65535"),
+        ("ports[2]", "must be between 1 and 65535"),
+        ("labels.b", "must not be empty"),
+        ("labels.c", "`Y` is not lowercase"),
+        ("limit", "must be at most 9007199254740993"),
+    ];
+    assert_eq!(found, expected);
+
+    let valid = content
+        .replace("0, 70000", "443")
+        .replace("994", "993")
+        .replace("\"\"", "\"z\"")
+        .replace("Y", "y");
+    assert_eq!(Items::from_string(&valid, TOML).unwrap().ports, vec![80, 443]);
+}
+
+#[test]
+fn item_rules_and_integer_bounds_are_described() {
+    use fast_config::Bound;
+    use fast_config::ConfigFields;
+    use fast_config::Rule;
+
+    let found = Items::constraints()
+        .into_iter()
+        .map(|constraint| (constraint.path, constraint.rule))
+        .collect::<Vec<_>>();
+    let expected = vec![
+        ("ports[]".to_owned(), Rule::Range { min: Some(Bound::Integer(1)), max: Some(Bound::Integer(65535)) }),
+        ("labels.*".to_owned(), Rule::NonEmpty),
+        ("labels.*".to_owned(), Rule::Custom("lowercase".into())),
+        ("limit".to_owned(), Rule::Range { min: None, max: Some(Bound::Integer(9007199254740993)) }),
+    ];
+    assert_eq!(found, expected);
+    assert_eq!(Items::field("ports").unwrap().attributes.rules, ["each(range(min = 1, max = 65535))"]);
+}
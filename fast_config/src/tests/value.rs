@@ -0,0 +1,243 @@
+use super::*;
+
+use fast_config::Error;
+use fast_config::Format;
+use fast_config::Map;
+use fast_config::UnknownFields;
+use fast_config::Value;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+#[fast_config(version = 2)]
+pub struct Data {
+    pub name: String,
+    pub ports: Vec<u16>,
+    pub ratio: f64,
+    #[serde(skip)]
+    #[fast_config(unknown)]
+    pub unknown: UnknownFields,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub enum Shape {
+    Point,
+    Circle(u32),
+    Rect { w: u32, h: u32 },
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+pub struct Shapes {
+    pub first: Shape,
+    pub second: Shape,
+    pub third: Shape,
+}
+
+/// Read through the document, which versioning asks for.
+#[derive(Debug, Serialize, Deserialize, PartialEq, FastConfig)]
+#[fast_config(version = 1)]
+pub struct VersionedShapes {
+    pub first: Shape,
+    pub second: Shape,
+    pub third: Shape,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+pub struct Release {
+    pub dates: Value,
+}
+
+fn keys(value: &Value) -> Vec<&str> {
+    value.as_table().unwrap().iter().map(|(key, _)| key.as_str()).collect()
+}
+
+fn keeps_key_order(format: Format, content: &str) {
+    let document = Value::from_string(content, format).unwrap();
+    assert_eq!(keys(&document), ["zebra", "apple", "mango"]);
+    let written = Value::from_string(&document.to_string_pretty(format).unwrap(), format).unwrap();
+    assert_eq!(written, document);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn keeps_key_order_json() {
+    keeps_key_order(JSON, r#"{ "zebra": 1, "apple": [true, 2.5], "mango": { "b": "x", "a": null } }"#);
+}
+
+#[cfg(feature = "json5")]
+#[test]
+fn keeps_key_order_json5() {
+    keeps_key_order(JSON5, "{ zebra: 1, apple: [true, 2.5], mango: { b: 'x', a: null } }");
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn keeps_key_order_toml() {
+    keeps_key_order(TOML, "zebra = 1\napple = [true, 2.5]\n\n[mango]\nb = \"x\"\na = 1\n");
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn keeps_key_order_yaml() {
+    keeps_key_order(YAML, "zebra: 1\napple: [true, 2.5]\nmango:\n  b: x\n  a: null\n");
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn toml_datetimes_are_kept() {
+    let content = "released = 1979-05-27T07:32:00Z\nday = 1979-05-27\n";
+    let document = Value::from_string(content, TOML).unwrap();
+    let released = document.get_path("released").and_then(Value::as_datetime).unwrap();
+    assert_eq!(released.as_str(), "1979-05-27T07:32:00Z");
+    assert_eq!(document.to_string(TOML).unwrap(), content);
+
+    // Other formats have no datetimes and get them as strings
+    #[cfg(feature = "json")]
+    assert_eq!(
+        document.to_string(JSON).unwrap(),
+        r#"{"released":"1979-05-27T07:32:00Z","day":"1979-05-27"}"#
+    );
+    #[cfg(feature = "yaml")]
+    assert_eq!(document.to_string(YAML).unwrap(), "released: '1979-05-27T07:32:00Z'\nday: '1979-05-27'\n");
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn yaml_tags_are_kept() {
+    let content = "first: Point\nsecond: !Circle 3\nthird: !Rect\n  w: 1\n  h: 2\n";
+    let document = Value::from_string(content, YAML).unwrap();
+    assert_eq!(document.get_path("second.Circle").and_then(Value::as_integer), Some(3));
+    assert_eq!(document.to_string(YAML).unwrap(), content);
+
+    let shapes = Shapes { first: Shape::Point, second: Shape::Circle(3), third: Shape::Rect { w: 1, h: 2 } };
+    assert_eq!(Shapes::from_value(document.clone()).unwrap(), shapes);
+    assert_eq!(shapes.to_value().unwrap().to_string(YAML).unwrap(), content);
+    assert_eq!(VersionedShapes::from_string(content, YAML).unwrap().third, shapes.third);
+
+    // Other formats have no tags and get a table with a single key
+    #[cfg(feature = "json")]
+    assert_eq!(
+        document.to_string(JSON).unwrap(),
+        r#"{"first":"Point","second":{"Circle":3},"third":{"Rect":{"w":1,"h":2}}}"#
+    );
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn yaml_enums_save_and_load_back() {
+    let c = MANAGER.setup();
+    let shapes = Shapes { first: Shape::Point, second: Shape::Circle(3), third: Shape::Rect { w: 1, h: 2 } };
+    let path = c.path.join("value").join("shapes.yaml");
+    shapes.save(&path, YAML).unwrap();
+    assert_eq!(Shapes::new(&path, YAML).unwrap(), shapes);
+
+    assert!(std::fs::read_to_string(&path).unwrap().contains("second: !Circle 3\n"));
+
+    let versioned = VersionedShapes { first: shapes.first, second: shapes.second, third: shapes.third };
+    let path = c.path.join("value").join("versioned.yaml");
+    versioned.save(&path, YAML).unwrap();
+    assert!(std::fs::read_to_string(&path).unwrap().contains("second: !Circle 3\n"));
+    assert_eq!(VersionedShapes::new(&path, YAML).unwrap(), versioned);
+}
+
+#[cfg(all(feature = "toml", feature = "json"))]
+#[test]
+fn datetimes_inside_configs_follow_the_format() {
+    let dates = Value::from_string("first = 1979-05-27\nall = [1979-05-27, 2024-01-01]\n", TOML).unwrap();
+    let release = Release { dates };
+    assert_eq!(
+        release.to_string(TOML).unwrap(),
+        "[dates]\nfirst = 1979-05-27\nall = [1979-05-27, 2024-01-01]\n"
+    );
+    assert_eq!(
+        release.to_string(JSON).unwrap(),
+        r#"{"dates":{"first":"1979-05-27","all":["1979-05-27","2024-01-01"]}}"#
+    );
+}
+
+#[test]
+fn values_are_changed_by_path() {
+    let mut document = Value::Table(Map::new());
+    document.set_path("server.host", Value::from("localhost")).unwrap();
+    document.set_path("server.ports", Value::from(vec![Value::from(80), Value::from(443)])).unwrap();
+    document.set_path("server.ports[1]", Value::from(8443)).unwrap();
+    assert_eq!(document.get_path("server.host").and_then(Value::as_str), Some("localhost"));
+    assert_eq!(document.get_path("server.ports[1]").and_then(Value::as_integer), Some(8443));
+    assert_eq!(document.get_path("server.ports.0").and_then(Value::as_integer), Some(80));
+    assert_eq!(document.get_path("server.missing"), None);
+
+    assert_eq!(document.remove_path("server.ports[0]"), Some(Value::from(80)));
+    assert_eq!(document.get_path("server.ports").and_then(Value::as_array).map(Vec::len), Some(1));
+    assert_eq!(document.remove_path("server.host"), Some(Value::from("localhost")));
+    assert_eq!(document.remove_path("server.host"), None);
+    assert_eq!(keys(document.get_path("server").unwrap()), ["ports"]);
+
+    for path in ["server.ports[5]", "server.ports[0].x", "server..host"] {
+        let error = document.set_path(path, Value::Null).unwrap_err();
+        assert!(matches!(&error, Error::UnknownPath { key } if key == path), "{error}");
+    }
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn configs_convert_to_values_and_back() {
+    let content = "name = \"app\"\nports = [80, 443]\nratio = 0.5\nextra = \"kept\"\nversion = 2\n";
+    let config = Data::from_string(content, TOML).unwrap();
+    let document = config.to_value().unwrap();
+    assert_eq!(keys(&document), ["name", "ports", "ratio", "extra", "version"]);
+    assert_eq!(document.get_path("extra").and_then(Value::as_str), Some("kept"));
+    assert_eq!(document.to_string(TOML).unwrap(), content);
+    assert_eq!(Data::from_value(document).unwrap(), config);
+}
+
+#[test]
+fn invalid_values_are_reported() {
+    let mut document = Data::default().to_value().unwrap();
+    document.set_path("ports", Value::from(vec![Value::from(80), Value::from(-1)])).unwrap();
+    let error = Data::from_value(document).unwrap_err();
+    assert!(matches!(&error, Error::Deserialize { key, .. } if key == "ports[1]"), "{error}");
+}
+
+fn saves_and_loads(format: Format, name: &str) {
+    let c = MANAGER.setup();
+    let path = c.path.join("value").join(name);
+    let mut document = Value::Table(Map::new());
+    document.set_path("name", Value::from("app")).unwrap();
+    document.set_path("ratio", Value::from(0.25)).unwrap();
+    document.save_pretty(&path, format).unwrap();
+    assert_eq!(Value::load(&path, format).unwrap(), document);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn saves_and_loads_json() {
+    saves_and_loads(JSON, "value.json");
+}
+
+#[cfg(feature = "json5")]
+#[test]
+fn saves_and_loads_json5() {
+    saves_and_loads(JSON5, "value.json5");
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn saves_and_loads_toml() {
+    saves_and_loads(TOML, "value.toml");
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn saves_and_loads_yaml() {
+    saves_and_loads(YAML, "value.yaml");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn load_errors_point_into_the_file() {
+    let c = MANAGER.setup();
+    let path = c.path.join("value").join("broken.json");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, "{\n  \"name\": \"app\",\n  \"ratio\": ,\n}\n").unwrap();
+    let error = Value::load(&path, JSON).unwrap_err();
+    assert_eq!(error.file(), Some(path.as_path()));
+    assert_eq!(error.line(), Some(3));
+}
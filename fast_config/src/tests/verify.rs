@@ -0,0 +1,106 @@
+use super::*;
+
+use fast_config::Error;
+use fast_config::Format;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+#[fast_config(verify_save)]
+pub struct Data {
+    pub name: Option<String>,
+    pub ids: Vec<Option<u32>>,
+    pub big: u64,
+    pub ratio: f64,
+}
+
+fn lossy_paths(error: &Error) -> Vec<String> {
+    match error.inner() {
+        Error::Unrepresentable { problems, .. } => problems.iter().map(|problem| problem.path.clone()).collect(),
+        other => panic!("{other}"),
+    }
+}
+
+/// Saves the config with `verify_save`, returning the paths that didn't come back the same.
+fn save(config: &Data, format: Format, name: &str) -> Vec<String> {
+    let c = MANAGER.setup();
+    let path = c.path.join("verify").join(name);
+    match config.save_pretty(&path, format) {
+        Ok(()) => {
+            assert_eq!(&Data::new(&path, format).unwrap(), config);
+            Vec::new()
+        }
+        Err(error) => {
+            assert!(!path.exists(), "{error}");
+            lossy_paths(&error)
+        }
+    }
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn check_format_toml() {
+    use fast_config::Value;
+
+    let mut document = Value::default();
+    document.set_path("a", Value::Null).unwrap();
+    document.set_path("list", Value::from(vec![Value::from(1), Value::Null])).unwrap();
+    document.set_path("big", Value::from(u64::MAX)).unwrap();
+    let error = fast_config::check_format(&document, TOML).unwrap_err();
+    assert_eq!(lossy_paths(&error), ["a", "list[1]", "big"]);
+    let error = fast_config::check_format(&Value::from("light"), TOML).unwrap_err();
+    assert!(error.to_string().contains("a TOML document must be a table, not a string"), "{error}");
+    #[cfg(feature = "yaml")]
+    assert!(fast_config::check_format(&document, YAML).is_ok());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn check_format_json() {
+    use fast_config::Value;
+
+    let document = Value::from(vec![Value::from(f64::INFINITY), Value::Integer(-1 << 70), Value::Null]);
+    let error = fast_config::check_format(&document, JSON).unwrap_err();
+    assert_eq!(lossy_paths(&error), ["[0]", "[1]"]);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn verified_saves_toml() {
+    // Unset fields are left out, which reads them back as `None`
+    assert!(save(&Data { ids: vec![Some(1)], ..Data::default() }, TOML, "fine.toml").is_empty());
+    assert_eq!(save(&Data { ids: vec![Some(1), None], ..Data::default() }, TOML, "none.toml"), ["ids[1]"]);
+    assert_eq!(save(&Data { big: u64::MAX, ..Data::default() }, TOML, "big.toml"), ["big"]);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn verified_saves_json() {
+    let data = Data { ratio: f64::NAN, ids: vec![None], ..Data::default() };
+    assert_eq!(save(&data, JSON, "nan.json"), ["ratio"]);
+    assert!(save(&Data { big: u64::MAX, ..Data::default() }, JSON, "big.json").is_empty());
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn verified_saves_yaml() {
+    let data = Data { ratio: f64::INFINITY, ids: vec![None], big: u64::MAX, ..Data::default() };
+    assert!(save(&data, YAML, "any.yaml").is_empty());
+}
+
+/// Saves without `verify_save`, which still refuse what `check_format` reports.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, FastConfig)]
+pub struct Plain {
+    pub name: Option<String>,
+    pub ids: Vec<Option<u32>>,
+    pub big: u64,
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn saves_agree_with_check_format_toml() {
+    let plain = Plain { name: None, ids: vec![Some(1), None], big: u64::MAX };
+    let error = plain.to_string(TOML).unwrap_err();
+    assert_eq!(lossy_paths(&error), ["ids[1]", "big"]);
+    let error = fast_config::check_format(&plain.to_value().unwrap(), TOML).unwrap_err();
+    assert_eq!(lossy_paths(&error), ["name", "ids[1]", "big"]);
+    assert!(Plain { ids: vec![Some(1)], ..Plain::default() }.to_string(TOML).is_ok());
+}
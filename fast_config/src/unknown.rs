@@ -0,0 +1,100 @@
+use crate::FastConfig;
+use crate::location::Locations;
+use crate::recover;
+use crate::recover::Problem;
+use crate::render;
+use crate::value;
+use crate::value::Segment;
+use crate::value::UnknownKey;
+use crate::value::Value;
+
+/// What to do with keys no field asked for, see [`FastConfig::new_checked`](crate::FastConfig::new_checked).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownKeys {
+    /// Leave them be, like serde does without `deny_unknown_fields`.
+    #[default]
+    Ignore,
+    /// Return them in [`Loaded::warnings`] along with the config.
+    Warn,
+    /// Fail with [`Error::UnknownKeys`](crate::Error::UnknownKeys) when there are any.
+    Deny,
+}
+
+/// A config read along with what was found wrong with it that didn't stop it from loading.
+#[derive(Debug)]
+pub struct Loaded<T> {
+    pub value: T,
+    /// Every deprecated key and, unless ignored, every unknown key, in the order they appear in the file.
+    pub warnings: Vec<Problem>,
+}
+
+/// Turns the unknown keys into problems pointing at where they are, suggesting the field a typo was meant to be.
+pub(crate) fn problems(unknown: Vec<UnknownKey>, locations: Option<&Locations>, prefixes: &[String]) -> Vec<Problem> {
+    let mut problems = unknown
+        .into_iter()
+        .map(|key| {
+            let name = key.path.rsplit_once('.').map_or(key.path.as_str(), |(_, name)| name);
+            let message = match render::closest(name, key.fields.iter().copied()) {
+                Some(field) => format!("unknown key, did you mean `{field}`?"),
+                None => "unknown key".to_owned(),
+            };
+            Problem {
+                location: locations.and_then(|locations| recover::locate(locations, prefixes, &key.path)),
+                path: key.path,
+                message,
+            }
+        })
+        .collect::<Vec<_>>();
+    recover::sort(&mut problems);
+    problems
+}
+
+/// The keys no field asked for, kept by a `#[fast_config(unknown)]` field so that `save` writes them back unchanged. <br/>
+/// The field must also be `#[serde(skip)]`, and only needs to be on the outermost struct to keep the keys of nested ones.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UnknownFields(Vec<(Vec<Segment>, Value)>);
+
+impl UnknownFields {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The dotted key paths of the kept keys, such as `servers[1].weight`.
+    pub fn paths(&self) -> impl Iterator<Item = String> + '_ {
+        self.0.iter().map(|(segments, _)| value::render_path(segments))
+    }
+}
+
+/// Stores the unknown keys in the config's `#[fast_config(unknown)]` field, if it has one.
+pub(crate) fn keep<T: FastConfig>(config: &mut T, unknown: &[UnknownKey]) {
+    if let Some(fields) = config.unknown_fields_mut() {
+        *fields = UnknownFields(unknown.iter().map(|key| (key.segments.clone(), key.value.clone())).collect());
+    }
+}
+
+/// Puts the keys kept by the config's `#[fast_config(unknown)]` field back where they were in its document. <br/>
+/// Keys whose parent is gone, or that a field now writes, are left out.
+pub(crate) fn restore<T: FastConfig>(config: &T, document: &mut Value) {
+    for (segments, kept) in config.unknown_fields().map(|fields| fields.0.as_slice()).unwrap_or_default() {
+        let Some((Segment::Key(key), parent)) = segments.split_last() else {
+            continue;
+        };
+        let mut table = Some(&mut *document);
+        for segment in parent {
+            table = match (table, segment) {
+                (Some(Value::Table(map)), Segment::Key(key)) => map.get_mut(key),
+                (Some(Value::Array(items)), Segment::Index(index)) => items.get_mut(*index),
+                _ => None,
+            };
+        }
+        if let Some(Value::Table(map)) = table
+            && !map.contains_key(key)
+        {
+            map.insert(key.clone(), kept.clone());
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::fmt;
+
+/// Checks that a config makes sense beyond what deserialization guarantees, such as ports not being `0`. <br/>
+/// Wire it into `load`, `new` and `from_string` with `#[fast_config(validate)]`.
+///
+/// ```rust
+/// impl Validate for MyConfig {
+///     fn validate(&self) -> Result<(), Violations> {
+///         let mut violations = Violations::new();
+///         if self.min > self.max {
+///             violations.push("min", "must not be greater than `max`");
+///         }
+///         violations.into_result()
+///     }
+/// }
+/// ```
+pub trait Validate {
+    /// Returns every problem found, not just the first one.
+    fn validate(&self) -> Result<(), Violations>;
+}
+
+/// A single validation problem and the key path it was found at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The dotted key path of the offending value, such as `servers[2].port`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path.is_empty() {
+            true => write!(f, "{}", self.message),
+            false => write!(f, "`{}`: {}", self.path, self.message),
+        }
+    }
+}
+
+/// Every [`Violation`] found while validating a config.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Violations(Vec<Violation>);
+
+impl Violations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a problem at the given key path.
+    pub fn push(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.0.push(Violation {
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Records the problems of a nested value, prefixing their paths with the key path of that value.
+    pub fn extend_nested(&mut self, prefix: &str, nested: Violations) {
+        for mut violation in nested.0 {
+            violation.path = match (prefix.is_empty(), violation.path.is_empty()) {
+                (true, _) => violation.path,
+                (false, true) => prefix.to_owned(),
+                (false, false) if violation.path.starts_with('[') => format!("{prefix}{}", violation.path),
+                (false, false) => format!("{prefix}.{}", violation.path),
+            };
+            self.0.push(violation);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Violation> {
+        self.0.iter()
+    }
+
+    /// `Ok(())` when nothing was recorded, otherwise `Err(self)`.
+    pub fn into_result(self) -> Result<(), Violations> {
+        match self.is_empty() {
+            true => Ok(()),
+            false => Err(self),
+        }
+    }
+}
+
+impl fmt::Display for Violations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, violation) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{violation}")?;
+        }
+        Ok(())
+    }
+}
+
+impl IntoIterator for Violations {
+    type Item = Violation;
+    type IntoIter = std::vec::IntoIter<Violation>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Violations {
+    type Item = &'a Violation;
+    type IntoIter = std::slice::Iter<'a, Violation>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// A rule put on a field with `#[fast_config(...)]`, the way schemas describe it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rule {
+    /// `range(min = ..., max = ...)`, described when its bounds are number literals.
+    Range { min: Option<Bound>, max: Option<Bound> },
+    /// `length(min = ..., max = ...)`, counting characters, items or entries.
+    Length { min: Option<usize>, max: Option<usize> },
+    Regex(String),
+    /// `one_of(...)`, with each allowed value written as JSON, such as `"debug"` or `3`.
+    /// Described when every value is a literal.
+    OneOf(Vec<String>),
+    NonEmpty,
+    PathExists,
+    /// `custom = "..."`, holding the path of the function.
+    Custom(String),
+}
+
+/// A `range` bound, kept whole for integer fields so that bounds past `f64` precision stay exact.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bound {
+    Integer(i128),
+    Float(f64),
+}
+
+impl fmt::Display for Bound {
+    /// Writes whole floats without a fraction, as `1` rather than `1.0`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bound::Integer(n) => write!(f, "{n}"),
+            Bound::Float(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => write!(f, "{}", *n as i64),
+            Bound::Float(n) => write!(f, "{n}"),
+        }
+    }
+}
+
+/// A [`Rule`] and the dotted key path of the field it's put on, see [`FastConfig::constraints`](crate::FastConfig::constraints).
+/// Rules put on the items of a field with `each(...)` are at `key[]`, or `key.*` for the values of a map.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constraint {
+    pub path: String,
+    pub rule: Rule,
+}
+
+/// Turns a `length` bound into a count, whatever integer type it was written as.
+pub fn bound(value: impl TryInto<usize>) -> Option<usize> {
+    value.try_into().ok()
+}
+
+/// How many characters, items or entries a value has, for `#[fast_config(length(...))]` and `non_empty`.
+pub trait Length {
+    fn length(&self) -> usize;
+}
+
+impl Length for str {
+    fn length(&self) -> usize {
+        self.chars().count()
+    }
+}
+
+impl Length for String {
+    fn length(&self) -> usize {
+        self.as_str().length()
+    }
+}
+
+impl<T> Length for [T] {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T, const N: usize> Length for [T; N] {
+    fn length(&self) -> usize {
+        N
+    }
+}
+
+impl<T> Length for Vec<T> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T> Length for VecDeque<T> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T, S> Length for HashSet<T, S> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T> Length for BTreeSet<T> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<K, V, S> Length for HashMap<K, V, S> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<K, V> Length for BTreeMap<K, V> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
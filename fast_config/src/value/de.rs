@@ -0,0 +1,552 @@
+use super::DATETIME;
+use super::Datetime;
+use super::Error;
+use super::Map;
+use super::Segment;
+use super::Value;
+use super::render_path;
+
+use serde::Deserialize;
+use serde::de;
+use serde::de::DeserializeOwned;
+use serde::de::IntoDeserializer;
+
+use std::cell::RefCell;
+use std::fmt;
+
+/// Converts a [`Value`] into any deserializable type, reading every value as it is.
+pub(crate) fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, Error> {
+    T::deserialize(value)
+}
+
+/// Like [`from_value`], also reading the strings [`Coercion`] allows as what the target asks for.
+pub(crate) fn from_value_coercing<T: DeserializeOwned>(value: Value, coercion: Coercion) -> Result<T, Error> {
+    from_value_tracking(value, coercion).0
+}
+
+/// Which strings of a document may be read as something else, since they were written as text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Coercion {
+    /// Key paths set from environment variables, `--set` overrides or `${...}` references. <br/>
+    /// Strings below them are parsed into the number, boolean or comma separated list the target asks for,
+    /// and numbers and booleans below them are read as strings.
+    pub(crate) paths: Vec<Vec<Segment>>,
+    /// Whether the numbers `NumberPolicy::String` writes as strings are read back,
+    /// which are the integers past `i64` and the floats that aren't finite.
+    pub(crate) numbers: bool,
+}
+
+/// A key that no struct field asked for, found by [`from_value_tracking`].
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct UnknownKey {
+    pub(crate) path: String,
+    /// The same path, split into its steps.
+    pub(crate) segments: Vec<Segment>,
+    pub(crate) value: Value,
+    /// The fields the struct does have, to suggest one in case of a typo.
+    pub(crate) fields: &'static [&'static str],
+}
+
+/// What [`from_value_tracking`] has seen so far on this thread.
+struct Tracker {
+    /// The key path currently being deserialized.
+    path: Vec<Segment>,
+    unknown: Vec<UnknownKey>,
+    coercion: Coercion,
+}
+
+thread_local! {
+    static TRACKER: RefCell<Option<Tracker>> = const { RefCell::new(None) };
+}
+
+/// Like [`from_value_coercing`], also returning the keys of every table read into a struct that none of its fields
+/// asked for. Tables read as maps, such as `HashMap`s and `#[serde(flatten)]` structs, are never reported.
+pub(crate) fn from_value_tracking<T: DeserializeOwned>(
+    value: Value,
+    coercion: Coercion,
+) -> (Result<T, Error>, Vec<UnknownKey>) {
+    let previous = TRACKER.replace(Some(Tracker { path: Vec::new(), unknown: Vec::new(), coercion }));
+    let result = T::deserialize(value);
+    let tracker = TRACKER.replace(previous).expect("the tracker was just installed");
+    (result, tracker.unknown)
+}
+
+/// Whether the value being deserialized is below one of the paths [`Coercion`] allows reading as text.
+fn coercing() -> bool {
+    TRACKER.with_borrow(|tracker| {
+        tracker.as_ref().is_some_and(|tracker| tracker.coercion.paths.iter().any(|path| tracker.path.starts_with(path)))
+    })
+}
+
+/// Whether the numbers `NumberPolicy::String` writes as strings are read back.
+fn coercing_numbers() -> bool {
+    TRACKER.with_borrow(|tracker| tracker.as_ref().is_some_and(|tracker| tracker.coercion.numbers))
+}
+
+/// Runs `f` with `segment` added to the tracked key path, when tracking.
+fn tracked<R>(segment: impl FnOnce() -> Segment, f: impl FnOnce() -> R) -> R {
+    let tracking = TRACKER.with_borrow_mut(|tracker| match tracker {
+        Some(tracker) => {
+            tracker.path.push(segment());
+            true
+        }
+        None => false,
+    });
+    let result = f();
+    if tracking {
+        TRACKER.with_borrow_mut(|tracker| tracker.as_mut().map(|tracker| tracker.path.pop()));
+    }
+    result
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> de::Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any config value")
+    }
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Integer(v.into()))
+    }
+    fn visit_i128<E>(self, v: i128) -> Result<Value, E> {
+        Ok(Value::Integer(v))
+    }
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::Integer(v.into()))
+    }
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<Value, E> {
+        i128::try_from(v)
+            .map(Value::Integer)
+            .map_err(|_| E::custom(format!("integer {v} is too large")))
+    }
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::Float(v))
+    }
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_owned()))
+    }
+    fn visit_string<E>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+    fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut list = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element()? {
+            list.push(item);
+        }
+        Ok(Value::Array(list))
+    }
+    fn visit_map<A: de::MapAccess<'de>>(self, mut access: A) -> Result<Value, A::Error> {
+        let mut map = Map::new();
+        while let Some(key) = access.next_key::<String>()? {
+            // TOML hands datetimes over as a table with a single key of this name
+            if key == DATETIME && map.is_empty() {
+                return Ok(Value::Datetime(Datetime(access.next_value()?)));
+            }
+            map.insert(key, access.next_value()?);
+        }
+        Ok(Value::Table(map))
+    }
+    /// YAML's `!Variant` tags, kept as a table with the variant's content under its name, or its name alone.
+    fn visit_enum<A: de::EnumAccess<'de>>(self, access: A) -> Result<Value, A::Error> {
+        let (variant, content) = access.variant::<String>()?;
+        match de::VariantAccess::newtype_variant::<Value>(content)? {
+            Value::Null => Ok(Value::String(variant)),
+            content => Ok(Value::Table(Map::variant(variant, content))),
+        }
+    }
+}
+
+impl IntoDeserializer<'_, Error> for Value {
+    type Deserializer = Value;
+    fn into_deserializer(self) -> Value {
+        self
+    }
+}
+
+impl Value {
+    fn invalid_type<E: de::Error>(&self, exp: &dyn de::Expected) -> E {
+        let unexpected = match self {
+            Value::Null => de::Unexpected::Unit,
+            Value::Bool(b) => de::Unexpected::Bool(*b),
+            Value::Integer(i) => match i64::try_from(*i) {
+                Ok(i) => de::Unexpected::Signed(i),
+                Err(_) => de::Unexpected::Other("a large integer"),
+            },
+            Value::Float(f) => de::Unexpected::Float(*f),
+            Value::String(s) => de::Unexpected::Str(s),
+            Value::Datetime(_) => de::Unexpected::Other("a datetime"),
+            Value::Array(_) => de::Unexpected::Seq,
+            Value::Table(_) => de::Unexpected::Map,
+        };
+        E::invalid_type(unexpected, exp)
+    }
+
+    /// Reinterprets a string as the scalar the target type asked for, when [`Coercion`] allows it.
+    fn parse_scalar(self, expected: &'static str) -> Result<Value, Error> {
+        let Value::String(s) = self else {
+            return Ok(self);
+        };
+        let trimmed = s.trim();
+        if !coercing() {
+            // The numbers `NumberPolicy::String` writes, which no format has a form for
+            let written = match expected {
+                "an integer" => trimmed.parse::<i128>().ok().filter(|i| i64::try_from(*i).is_err()).map(Value::Integer),
+                "a float" => trimmed.parse::<f64>().ok().filter(|f| !f.is_finite()).map(Value::Float),
+                _ => None,
+            };
+            return Ok(match written.filter(|_| coercing_numbers()) {
+                Some(number) => number,
+                None => Value::String(s),
+            });
+        }
+        let parsed = match expected {
+            "a boolean" => match trimmed.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => Some(Value::Bool(true)),
+                "false" | "no" | "off" | "0" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            "an integer" => trimmed.parse::<i128>().ok().map(Value::Integer),
+            _ => trimmed.parse::<f64>().ok().map(Value::Float),
+        };
+        parsed.ok_or_else(|| Error::new(format!("invalid value `{s}`, expected {expected}")))
+    }
+
+    /// Splits a comma separated string into a list for targets that ask for a sequence, when [`Coercion`] allows it.
+    fn split_list(self) -> Value {
+        match self {
+            Value::String(s) if coercing() && s.trim().is_empty() => Value::Array(Vec::new()),
+            Value::String(s) if coercing() => Value::Array(
+                s.split(',')
+                    .map(|item| Value::String(item.trim().to_owned()))
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+}
+
+macro_rules! deserialize_scalar {
+    ($($method:ident => $expected:literal),* $(,)?) => {$(
+        fn $method<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            self.parse_scalar($expected)?.deserialize_any(visitor)
+        }
+    )*};
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(b),
+            Value::Integer(i) => match (i64::try_from(i), u64::try_from(i)) {
+                (Ok(i), _) => visitor.visit_i64(i),
+                (_, Ok(u)) => visitor.visit_u64(u),
+                _ => visitor.visit_i128(i),
+            },
+            Value::Float(f) => visitor.visit_f64(f),
+            Value::String(s) => visitor.visit_string(s),
+            // The way TOML hands datetimes over, which types such as `toml::value::Datetime` read them from
+            Value::Datetime(datetime) => {
+                let map = Map::from_iter([(DATETIME.to_owned(), Value::String(datetime.0))]);
+                visitor.visit_map(MapDeserializer::new(map))
+            }
+            Value::Array(list) => visitor.visit_seq(SeqDeserializer::new(list)),
+            Value::Table(map) => visitor.visit_map(MapDeserializer::new(map)),
+        }
+    }
+
+    deserialize_scalar! {
+        deserialize_bool => "a boolean",
+        deserialize_i8 => "an integer",
+        deserialize_i16 => "an integer",
+        deserialize_i32 => "an integer",
+        deserialize_i64 => "an integer",
+        deserialize_i128 => "an integer",
+        deserialize_u8 => "an integer",
+        deserialize_u16 => "an integer",
+        deserialize_u32 => "an integer",
+        deserialize_u64 => "an integer",
+        deserialize_u128 => "an integer",
+        deserialize_f32 => "a float",
+        deserialize_f64 => "a float",
+    }
+
+    fn deserialize_char<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_string(visitor)
+    }
+    fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_string(visitor)
+    }
+    fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Bool(b) if coercing() => visitor.visit_string(b.to_string()),
+            Value::Integer(i) if coercing() => visitor.visit_string(i.to_string()),
+            Value::Float(f) if coercing() => visitor.visit_string(f.to_string()),
+            Value::Datetime(datetime) => visitor.visit_string(datetime.0),
+            other => other.deserialize_any(visitor),
+        }
+    }
+    fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_any(visitor)
+    }
+    fn deserialize_byte_buf<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_any(visitor)
+    }
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Null => visitor.visit_none(),
+            other => visitor.visit_some(other),
+        }
+    }
+    fn deserialize_unit<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Null => visitor.visit_unit(),
+            other => Err(other.invalid_type(&visitor)),
+        }
+    }
+    fn deserialize_unit_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+    fn deserialize_seq<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.split_list() {
+            Value::Array(list) => visitor.visit_seq(SeqDeserializer::new(list)),
+            other => Err(other.invalid_type(&visitor)),
+        }
+    }
+    fn deserialize_tuple<V: de::Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+    fn deserialize_tuple_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+    fn deserialize_map<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Table(map) => visitor.visit_map(MapDeserializer::new(map)),
+            other => Err(other.invalid_type(&visitor)),
+        }
+    }
+    fn deserialize_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        if let Value::Table(map) = &self {
+            TRACKER.with_borrow_mut(|tracker| {
+                let Some(tracker) = tracker else {
+                    return;
+                };
+                for (key, value) in map.iter().filter(|(key, _)| !fields.contains(&key.as_str())) {
+                    let mut segments = tracker.path.clone();
+                    segments.push(Segment::Key(key.clone()));
+                    tracker.unknown.push(UnknownKey {
+                        path: render_path(&segments),
+                        segments,
+                        value: value.clone(),
+                        fields,
+                    });
+                }
+            });
+        }
+        self.deserialize_map(visitor)
+    }
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self {
+            Value::String(variant) => visitor.visit_enum(EnumDeserializer {
+                variant,
+                content: None,
+            }),
+            Value::Table(map) if map.len() == 1 => {
+                let (variant, content) = map.into_iter().next().expect("length was checked");
+                let enum_access = EnumDeserializer {
+                    variant: variant.clone(),
+                    content: Some(content),
+                };
+                tracked(|| Segment::Key(variant.clone()), || visitor.visit_enum(enum_access))
+                    .map_err(|e| e.in_key(&variant))
+            }
+            other => Err(other.invalid_type(&"a variant name or a table with a single key")),
+        }
+    }
+    fn deserialize_identifier<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_string(visitor)
+    }
+    fn deserialize_ignored_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+}
+
+struct SeqDeserializer {
+    iter: std::iter::Enumerate<std::vec::IntoIter<Value>>,
+}
+
+impl SeqDeserializer {
+    fn new(list: Vec<Value>) -> Self {
+        Self {
+            iter: list.into_iter().enumerate(),
+        }
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for SeqDeserializer {
+    type Error = Error;
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        match self.iter.next() {
+            Some((index, value)) => tracked(|| Segment::Index(index), || seed.deserialize(value))
+                .map(Some)
+                .map_err(|e| e.in_index(index)),
+            None => Ok(None),
+        }
+    }
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct MapDeserializer {
+    iter: std::vec::IntoIter<(String, Value)>,
+    pending: Option<(String, Value)>,
+}
+
+impl MapDeserializer {
+    fn new(map: Map) -> Self {
+        Self {
+            iter: map.into_iter(),
+            pending: None,
+        }
+    }
+}
+
+impl<'de> de::MapAccess<'de> for MapDeserializer {
+    type Error = Error;
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                let result = seed.deserialize(Value::String(key.clone())).map_err(|e| e.in_key(&key));
+                self.pending = Some((key, value));
+                result.map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let (key, value) = self
+            .pending
+            .take()
+            .expect("next_value_seed called before next_key_seed");
+        tracked(|| Segment::Key(key.clone()), || seed.deserialize(value)).map_err(|e| e.in_key(&key))
+    }
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct EnumDeserializer {
+    variant: String,
+    content: Option<Value>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumDeserializer {
+    type Error = Error;
+    type Variant = VariantDeserializer;
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, VariantDeserializer), Error> {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((variant, VariantDeserializer(self.content)))
+    }
+}
+
+struct VariantDeserializer(Option<Value>);
+
+impl<'de> de::VariantAccess<'de> for VariantDeserializer {
+    type Error = Error;
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.0 {
+            None | Some(Value::Null) => Ok(()),
+            Some(other) => Err(other.invalid_type(&"a unit variant")),
+        }
+    }
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        match self.0 {
+            Some(value) => seed.deserialize(value),
+            None => Err(de::Error::invalid_type(de::Unexpected::UnitVariant, &"a newtype variant")),
+        }
+    }
+    fn tuple_variant<V: de::Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            Some(value) => de::Deserializer::deserialize_seq(value, visitor),
+            None => Err(de::Error::invalid_type(de::Unexpected::UnitVariant, &visitor)),
+        }
+    }
+    fn struct_variant<V: de::Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.0 {
+            Some(value) => de::Deserializer::deserialize_struct(value, "", fields, visitor),
+            None => Err(de::Error::invalid_type(de::Unexpected::UnitVariant, &visitor)),
+        }
+    }
+}
@@ -0,0 +1,655 @@
+//! A format-neutral tree used to manipulate config data without knowing its Rust type.
+
+mod de;
+mod parse;
+mod sample;
+mod ser;
+
+use crate::FastConfig;
+use crate::Format;
+use crate::file;
+use crate::format_dependant;
+use crate::unknown;
+
+use std::fmt;
+use std::path::Path;
+
+pub(crate) use de::UnknownKey;
+pub(crate) use de::Coercion;
+pub(crate) use de::from_value;
+pub(crate) use de::from_value_coercing;
+pub(crate) use de::from_value_tracking;
+pub(crate) use parse::parse_inline;
+pub(crate) use parse::to_inline;
+pub(crate) use sample::Variants;
+pub(crate) use sample::sample;
+pub(crate) use ser::to_value;
+
+/// A config document whose shape isn't known in advance, read from and written to any [`Format`]. <br/>
+/// Tables keep the order of their keys, and converting to and from a config type with
+/// [`FastConfig::to_value`](crate::FastConfig::to_value) and [`FastConfig::from_value`](crate::FastConfig::from_value)
+/// loses nothing.
+///
+/// ```rust
+/// let mut document = Value::load("config.toml", Format::TOML)?;
+/// if let Some(port) = document.get_path("server.port").and_then(Value::as_integer) {
+///     document.set_path("server.port", Value::from(port + 1))?;
+/// }
+/// document.remove_path("legacy");
+/// document.save_pretty("config.yaml", Format::YAML)?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Value {
+    #[default]
+    Null,
+    Bool(bool),
+    Integer(i128),
+    Float(f64),
+    String(String),
+    /// A TOML date, time or both. Formats without datetimes write it as a string.
+    Datetime(Datetime),
+    Array(Vec<Value>),
+    Table(Map),
+}
+
+/// The struct name TOML's serde support gives datetimes, which its serializer recognizes them by.
+pub(crate) const DATETIME_STRUCT: &str = "$__toml_private_Datetime";
+/// The single field of that struct, holding the datetime as text. Its deserializer hands them over as a map with this key.
+pub(crate) const DATETIME: &str = "$__toml_private_datetime";
+
+/// A TOML date and time, kept as written, such as `1979-05-27T07:32:00Z`, `1979-05-27` or `07:32:00`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Datetime(pub(crate) String);
+
+impl Datetime {
+    /// Takes the text of a TOML datetime. It isn't checked until it's written as TOML.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self(text.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Datetime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Value {
+    /// Reads a document in the given format.
+    pub fn from_string(content: &str, format: Format) -> Result<Value, crate::Error> {
+        format_dependant::from_str(content, format)
+    }
+
+    /// Writes the document in the given format, compactly where the format allows it.
+    pub fn to_string(&self, format: Format) -> Result<String, crate::Error> {
+        format_dependant::to_string(&self.in_format(format), format)
+    }
+
+    /// Writes the document in the given format, indented and human-readable.
+    pub fn to_string_pretty(&self, format: Format) -> Result<String, crate::Error> {
+        format_dependant::to_string_pretty(&self.in_format(format), format)
+    }
+
+    /// Reads a file, failing with [`crate::Error::File`] pointing at where it couldn't be parsed.
+    pub fn load(path: impl AsRef<Path>, format: Format) -> Result<Value, crate::Error> {
+        file::read_value(path.as_ref(), format)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>, format: Format) -> Result<(), crate::Error> {
+        file::write_value(self, path.as_ref(), format, false)
+    }
+
+    pub fn save_pretty(&self, path: impl AsRef<Path>, format: Format) -> Result<(), crate::Error> {
+        file::write_value(self, path.as_ref(), format, true)
+    }
+
+    /// The value at a dotted key path such as `server.port` or `servers[0].host`, if any.
+    pub fn get_path(&self, path: &str) -> Option<&Value> {
+        let mut current = self;
+        for segment in parse_path(path).ok()? {
+            current = match (current, segment) {
+                (Value::Table(map), Segment::Key(key)) => map.get(&key)?,
+                (Value::Array(list), Segment::Index(index)) => list.get(index)?,
+                (Value::Array(list), Segment::Key(key)) => list.get(key.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+        Some(current)
+    }
+
+    /// Replaces the value at a dotted key path, creating any missing tables along the way. <br/>
+    /// Fails with [`crate::Error::UnknownPath`] when the path goes through a value that isn't a table or a list,
+    /// or past the end of a list.
+    pub fn set_path(&mut self, path: &str, value: Value) -> Result<(), crate::Error> {
+        self.set_path_matching(path, value, false)
+            .map(|_| ())
+            .map_err(|_| crate::Error::UnknownPath { key: path.to_owned() })
+    }
+
+    /// Like [`Value::set_path`], matching keys case-insensitively when `ignore_case` is set, keeping the existing spelling.
+    /// <br/> Returns the path that was written, with the keys as spelled in the document and list indexes as indexes.
+    pub(crate) fn set_path_matching(
+        &mut self,
+        path: &str,
+        value: Value,
+        ignore_case: bool,
+    ) -> Result<Vec<Segment>, Error> {
+        let segments = parse_path(path)?;
+        let mut written = Vec::new();
+        let mut current = self;
+        for (i, segment) in segments.iter().enumerate() {
+            if current.is_null() {
+                *current = Value::Table(Map::new());
+            }
+            let at = || Error::at(render_path(&segments[..i]), "");
+            current = match (current, segment) {
+                (Value::Table(map), Segment::Key(key)) => {
+                    let key = match ignore_case {
+                        true => map.find_key_ignore_case(key).unwrap_or(key).to_owned(),
+                        false => key.clone(),
+                    };
+                    if !map.contains_key(&key) {
+                        map.insert(key.clone(), Value::Null);
+                    }
+                    written.push(Segment::Key(key.clone()));
+                    map.get_mut(&key).expect("key was just inserted")
+                }
+                (Value::Array(list), Segment::Index(index)) => {
+                    let len = list.len();
+                    written.push(Segment::Index(*index));
+                    list.get_mut(*index).ok_or_else(|| {
+                        at().with_message(format!("index {index} is out of bounds for a list of length {len}"))
+                    })?
+                }
+                (Value::Array(list), Segment::Key(key)) => {
+                    let len = list.len();
+                    let index = key
+                        .parse::<usize>()
+                        .map_err(|_| at().with_message(format!("expected a list index, found `{key}`")))?;
+                    written.push(Segment::Index(index));
+                    list.get_mut(index).ok_or_else(|| {
+                        at().with_message(format!("index {index} is out of bounds for a list of length {len}"))
+                    })?
+                }
+                (other, _) => {
+                    return Err(at().with_message(format!("cannot index into {}", other.kind())));
+                }
+            };
+        }
+        *current = value;
+        Ok(written)
+    }
+
+    /// Deep-merges `other` on top of this value: tables are merged key by key, anything else is replaced. <br/>
+    /// Enum variants are replaced too, since merging two of them would hold both.
+    pub(crate) fn merge(&mut self, other: Value) {
+        match (self, other) {
+            (Value::Table(base), Value::Table(other)) if !base.is_variant() && !other.is_variant() => {
+                for (key, value) in other {
+                    match base.get_mut(&key) {
+                        Some(existing) => existing.merge(value),
+                        None => {
+                            base.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (this, other) => *this = other,
+        }
+    }
+
+    /// Removes the key found at `path`, or nulls the list item found there so the other items keep their index. <br/>
+    /// Returns whether anything changed.
+    pub(crate) fn clear_path(&mut self, path: &str) -> bool {
+        let Ok(mut segments) = parse_path(path) else {
+            return false;
+        };
+        let Some(last) = segments.pop() else {
+            return false;
+        };
+        let parent = match segments.is_empty() {
+            true => Some(self),
+            false => self.get_path_mut(&render_path(&segments)),
+        };
+        match (parent, last) {
+            (Some(Value::Table(map)), Segment::Key(key)) => map.remove(&key).is_some(),
+            (Some(Value::Array(list)), Segment::Index(index)) => match list.get_mut(index) {
+                Some(item) if !item.is_null() => {
+                    *item = Value::Null;
+                    true
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Removes the value at a dotted key path and returns it, shifting the items after it when it's in a list.
+    pub fn remove_path(&mut self, path: &str) -> Option<Value> {
+        let mut segments = parse_path(path).ok()?;
+        let last = segments.pop()?;
+        let parent = match segments.is_empty() {
+            true => self,
+            false => self.get_path_mut(&render_path(&segments))?,
+        };
+        match (parent, last) {
+            (Value::Table(map), Segment::Key(key)) => map.remove(&key),
+            (Value::Array(list), Segment::Index(index)) if index < list.len() => Some(list.remove(index)),
+            _ => None,
+        }
+    }
+
+    /// Like [`Value::get_path`], for changing the value in place.
+    pub fn get_path_mut(&mut self, path: &str) -> Option<&mut Value> {
+        let mut current = self;
+        for segment in parse_path(path).ok()? {
+            current = match (current, segment) {
+                (Value::Table(map), Segment::Key(key)) => map.get_mut(&key)?,
+                (Value::Array(list), Segment::Index(index)) => list.get_mut(index)?,
+                (Value::Array(list), Segment::Key(key)) => list.get_mut(key.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+        Some(current)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i128> {
+        match self {
+            Value::Integer(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// The number as a float, including integers.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Float(n) => Some(*n),
+            Value::Integer(n) => Some(*n as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_datetime(&self) -> Option<&Datetime> {
+        match self {
+            Value::Datetime(datetime) => Some(datetime),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Value>> {
+        match self {
+            Value::Array(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<Value>> {
+        match self {
+            Value::Array(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    pub fn as_table(&self) -> Option<&Map> {
+        match self {
+            Value::Table(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    pub fn as_table_mut(&mut self) -> Option<&mut Map> {
+        match self {
+            Value::Table(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// A short human-readable name of the value's type, used in error messages.
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "a boolean",
+            Value::Integer(_) => "an integer",
+            Value::Float(_) => "a float",
+            Value::String(_) => "a string",
+            Value::Datetime(_) => "a datetime",
+            Value::Array(_) => "a list",
+            Value::Table(_) => "a table",
+        }
+    }
+}
+
+macro_rules! from_scalar {
+    ($($ty:ty => $variant:ident),* $(,)?) => {$(
+        impl From<$ty> for Value {
+            fn from(value: $ty) -> Self {
+                Value::$variant(value.into())
+            }
+        }
+    )*};
+}
+
+from_scalar! {
+    bool => Bool,
+    i8 => Integer,
+    i16 => Integer,
+    i32 => Integer,
+    i64 => Integer,
+    i128 => Integer,
+    u8 => Integer,
+    u16 => Integer,
+    u32 => Integer,
+    u64 => Integer,
+    f32 => Float,
+    f64 => Float,
+    &str => String,
+    String => String,
+    Datetime => Datetime,
+    Vec<Value> => Array,
+    Map => Table,
+}
+
+/// Writes each `(path, value)` pair into the document of `config`, then deserializes it back,
+/// reading the patched values as text, see [`Coercion`], and keeping the keys its `#[fast_config(unknown)]` field
+/// holds. <br/>
+/// On failure, returns the index of the patch that most likely caused the error alongside it.
+pub(crate) fn patch<T: FastConfig>(
+    config: &mut T,
+    patches: &[(String, Value)],
+    ignore_case: bool,
+) -> Result<(), (usize, Error)> {
+    if patches.is_empty() {
+        return Ok(());
+    }
+    *config = patched(config, patches, ignore_case)?;
+    Ok(())
+}
+
+/// Like [`patch`], returning the patched config instead of replacing it.
+pub(crate) fn patched<T: FastConfig>(
+    config: &T,
+    patches: &[(String, Value)],
+    ignore_case: bool,
+) -> Result<T, (usize, Error)> {
+    let mut document = file::to_value(config).map_err(|e| (0, e))?;
+    let mut coercion = Coercion::default();
+    for (i, (path, value)) in patches.iter().enumerate() {
+        let written = document
+            .set_path_matching(path, value.clone(), ignore_case)
+            .map_err(|e| (i, e))?;
+        coercion.paths.push(written);
+    }
+    let (updated, unread) = from_value_tracking::<T>(document, coercion);
+    let mut updated = updated.map_err(|e| {
+        // Blame the last patch that touched the failing key, or the first one if none did
+        let culprit = patches
+            .iter()
+            .rposition(|(path, _)| paths_overlap(path, &e.path))
+            .unwrap_or(0);
+        (culprit, e)
+    })?;
+    unknown::keep(&mut updated, &unread);
+    Ok(updated)
+}
+
+/// Whether one path is the other or one of its parents, ignoring case and index notation.
+fn paths_overlap(a: &str, b: &str) -> bool {
+    let normalize = |path: &str| path.replace('[', ".").replace(']', "").to_lowercase();
+    let (a, b) = (normalize(a), normalize(b));
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    long == short || long.starts_with(&format!("{short}."))
+}
+
+/// An insertion-ordered map of string keys to values.
+#[derive(Debug, Clone, Default)]
+pub struct Map {
+    entries: Vec<(String, Value)>,
+    /// Whether the table is an enum variant holding its content, which YAML writes as a `!Variant` tag.
+    variant: bool,
+}
+
+/// Tables are equal when their keys and values are, however the variants among them were read.
+impl PartialEq for Map {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+impl Map {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            variant: false,
+        }
+    }
+
+    /// A table holding the content of an enum variant under its name, as serde writes them outside of YAML.
+    pub(crate) fn variant(name: String, content: Value) -> Self {
+        Self {
+            entries: vec![(name, content)],
+            variant: true,
+        }
+    }
+
+    /// Whether the table still holds a single enum variant, see [`Map::variant`].
+    pub(crate) fn is_variant(&self) -> bool {
+        self.variant && self.entries.len() == 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.entries.iter_mut().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Inserts a value, keeping the key's original position if it already exists.
+    pub fn insert(&mut self, key: String, value: Value) -> Option<Value> {
+        match self.get_mut(&key) {
+            Some(existing) => Some(std::mem::replace(existing, value)),
+            None => {
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Removes a key, keeping the order of the remaining ones.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let index = self.entries.iter().position(|(k, _)| k == key)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.entries.iter().map(|(k, v)| (k, v))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&String, &mut Value)> {
+        self.entries.iter_mut().map(|(k, v)| (&*k, v))
+    }
+
+    fn find_key_ignore_case(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(k, _)| k.as_str())
+    }
+}
+
+impl IntoIterator for Map {
+    type Item = (String, Value);
+    type IntoIter = std::vec::IntoIter<(String, Value)>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl FromIterator<(String, Value)> for Map {
+    fn from_iter<I: IntoIterator<Item = (String, Value)>>(iter: I) -> Self {
+        let mut map = Map::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+/// A single step of a key path such as `servers[2].port`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Splits a key path like `servers[2].port` (or `servers.2.port`) into its segments.
+pub(crate) fn parse_path(path: &str) -> Result<Vec<Segment>, Error> {
+    let mut segments = Vec::new();
+    for (i, part) in path.split('.').enumerate() {
+        let (key, mut rest) = match part.find('[') {
+            Some(i) => (&part[..i], &part[i..]),
+            None => (part, ""),
+        };
+        if key.is_empty() && (rest.is_empty() || i > 0) {
+            return Err(Error::new(format!("invalid key path `{path}`")));
+        }
+        if !key.is_empty() {
+            segments.push(Segment::Key(key.to_owned()));
+        }
+        while !rest.is_empty() {
+            let end = rest
+                .find(']')
+                .ok_or_else(|| Error::new(format!("unclosed `[` in key path `{path}`")))?;
+            let index = rest[1..end]
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| Error::new(format!("invalid list index in key path `{path}`")))?;
+            segments.push(Segment::Index(index));
+            rest = &rest[end + 1..];
+            if !rest.is_empty() && !rest.starts_with('[') {
+                return Err(Error::new(format!("invalid key path `{path}`")));
+            }
+        }
+    }
+    Ok(segments)
+}
+
+/// Joins path segments back into the `servers[2].port` notation.
+pub(crate) fn render_path(segments: &[Segment]) -> String {
+    let mut out = String::new();
+    for segment in segments {
+        match segment {
+            Segment::Key(key) => {
+                if !out.is_empty() {
+                    out.push('.');
+                }
+                out.push_str(key);
+            }
+            Segment::Index(index) => out.push_str(&format!("[{index}]")),
+        }
+    }
+    out
+}
+
+/// An error produced while converting to or from a [`Value`], such as when reading a typed value out of one. <br/>
+/// Carries the key path of the value that caused it, when known.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    pub(crate) path: String,
+    pub(crate) message: String,
+}
+
+impl Error {
+    pub(crate) fn new(message: impl Into<String>) -> Self {
+        Self { path: String::new(), message: message.into() }
+    }
+
+    pub(crate) fn at(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { path: path.into(), message: message.into() }
+    }
+
+    fn with_message(mut self, message: String) -> Self {
+        self.message = message;
+        self
+    }
+
+    /// Prepends a table key to the path, as the error bubbles up out of that key.
+    pub(crate) fn in_key(mut self, key: &str) -> Self {
+        self.path = match self.path.is_empty() {
+            true => key.to_owned(),
+            false if self.path.starts_with('[') => format!("{key}{}", self.path),
+            false => format!("{key}.{}", self.path),
+        };
+        self
+    }
+
+    /// Prepends a list index to the path, as the error bubbles up out of that element.
+    pub(crate) fn in_index(mut self, index: usize) -> Self {
+        self.path = match self.path.is_empty() || self.path.starts_with('[') {
+            true => format!("[{index}]{}", self.path),
+            false => format!("[{index}].{}", self.path),
+        };
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path.is_empty() {
+            true => write!(f, "{}", self.message),
+            false => write!(f, "`{}`: {}", self.path, self.message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl serde::ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::new(msg.to_string())
+    }
+}
+
+impl serde::de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::new(msg.to_string())
+    }
+    /// Points at the missing key itself rather than at the table it's missing from.
+    fn missing_field(field: &'static str) -> Self {
+        Error::at(field, format!("missing field `{field}`"))
+    }
+}
@@ -0,0 +1,200 @@
+use super::Error;
+use super::Map;
+use super::Value;
+
+/// Parses a TOML/JSON-like inline value, such as the right-hand side of `--set key=value`. <br/>
+/// Bare scalars are kept as strings so the target type decides how to read them,
+/// quoted strings are unquoted, and `[...]`/`{...}` become lists and tables.
+pub(crate) fn parse_inline(text: &str) -> Result<Value, Error> {
+    let mut parser = Parser { text, pos: 0 };
+    let value = parser.value(true)?;
+    parser.skip_whitespace();
+    match parser.pos == text.len() {
+        true => Ok(value),
+        false => Err(parser.error("unexpected trailing characters")),
+    }
+}
+
+/// Writes a value on one line in the form [`parse_inline`] reads back, with strings always quoted.
+pub(crate) fn to_inline(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_owned(),
+        Value::Bool(b) => b.to_string(),
+        Value::Integer(n) => n.to_string(),
+        Value::Float(n) if n.is_finite() && n.fract() == 0.0 => format!("{n:.1}"),
+        Value::Float(n) => n.to_string(),
+        Value::String(s) => quote(s),
+        Value::Datetime(datetime) => datetime.to_string(),
+        Value::Array(items) => format!("[{}]", items.iter().map(to_inline).collect::<Vec<_>>().join(", ")),
+        Value::Table(map) => {
+            let entries = map.iter().map(|(key, value)| format!("{}: {}", quote(key), to_inline(value)));
+            format!("{{{}}}", entries.collect::<Vec<_>>().join(", "))
+        }
+    }
+}
+
+fn quote(s: &str) -> String {
+    let mut out = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\0' => out.push_str("\\0"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn rest(&self) -> &str {
+        &self.text[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let trimmed = self.rest().trim_start();
+        self.pos = self.text.len() - trimmed.len();
+    }
+
+    fn error(&self, message: &str) -> Error {
+        Error::new(format!("{message} at column {} of `{}`", self.pos + 1, self.text))
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Error> {
+        self.skip_whitespace();
+        match self.peek() == Some(c) {
+            true => {
+                self.pos += c.len_utf8();
+                Ok(())
+            }
+            false => Err(self.error(&format!("expected `{c}`"))),
+        }
+    }
+
+    fn value(&mut self, top_level: bool) -> Result<Value, Error> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('[') => self.array(),
+            Some('{') => self.table(),
+            Some('"') | Some('\'') => self.quoted().map(Value::String),
+            _ => {
+                let bare = self.bare(top_level);
+                match bare {
+                    "null" => Ok(Value::Null),
+                    "" if !top_level => Err(self.error("expected a value")),
+                    _ => Ok(Value::String(bare.to_owned())),
+                }
+            }
+        }
+    }
+
+    /// Reads an unquoted scalar; nested ones end at the next delimiter.
+    fn bare(&mut self, top_level: bool) -> &str {
+        let rest = self.rest();
+        let len = match top_level {
+            true => rest.len(),
+            false => rest.find([',', ']', '}']).unwrap_or(rest.len()),
+        };
+        let start = self.pos;
+        self.pos += len;
+        self.text[start..self.pos].trim()
+    }
+
+    fn quoted(&mut self) -> Result<String, Error> {
+        let quote = self.peek().expect("called on a quote");
+        self.pos += 1;
+        let mut out = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                c if c == quote => {
+                    self.pos += i + 1;
+                    return Ok(out);
+                }
+                '\\' if quote == '"' => {
+                    let escaped = match chars.next().map(|(_, c)| c) {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('0') => '\0',
+                        Some(c @ ('"' | '\\' | '/' | '\'')) => c,
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    out.push(escaped);
+                }
+                c => out.push(c),
+            }
+        }
+        Err(self.error("unclosed string"))
+    }
+
+    fn array(&mut self) -> Result<Value, Error> {
+        self.expect('[')?;
+        let mut list = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some(']') {
+                break;
+            }
+            list.push(self.value(false)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                _ => break,
+            }
+        }
+        self.expect(']')?;
+        Ok(Value::Array(list))
+    }
+
+    fn table(&mut self) -> Result<Value, Error> {
+        self.expect('{')?;
+        let mut map = Map::new();
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some('}') {
+                break;
+            }
+            let key = match self.peek() {
+                Some('"') | Some('\'') => self.quoted()?,
+                _ => {
+                    let rest = self.rest();
+                    let len = rest.find(['=', ':', ',', '}']).unwrap_or(rest.len());
+                    let key = rest[..len].trim().to_owned();
+                    self.pos += len;
+                    if key.is_empty() {
+                        return Err(self.error("expected a key"));
+                    }
+                    key
+                }
+            };
+            self.skip_whitespace();
+            match self.peek() {
+                Some('=') | Some(':') => self.pos += 1,
+                _ => return Err(self.error(&format!("expected `=` after key `{key}`"))),
+            }
+            let value = self.value(false)?;
+            map.insert(key, value);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                _ => break,
+            }
+        }
+        self.expect('}')?;
+        Ok(Value::Table(map))
+    }
+}
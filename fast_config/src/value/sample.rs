@@ -0,0 +1,205 @@
+use super::Error;
+
+use serde::de;
+use serde::de::DeserializeOwned;
+use serde::de::IntoDeserializer;
+
+use std::cell::RefCell;
+
+/// How deep samples of recursive types such as `Option<Box<Self>>` go before `Option`s are left empty.
+const MAX_DEPTH: usize = 32;
+
+/// The variants of each enum met while sampling, by dotted key path.
+pub(crate) type Variants = Vec<(String, &'static [&'static str])>;
+
+/// Builds an example of `T` with every `Option` filled in, every enum on its first variant,
+/// zeroes, empty strings and empty lists, along with the variants of the enums it went through. <br/>
+/// Types that read themselves in a way of their own, such as from a specific string, can't be sampled.
+pub(crate) fn sample<T: DeserializeOwned>() -> (Option<T>, Variants) {
+    let variants = RefCell::new(Vec::new());
+    let sample = T::deserialize(Sampler { path: String::new(), depth: 0, variants: &variants }).ok();
+    (sample, variants.into_inner())
+}
+
+struct Sampler<'a> {
+    path: String,
+    depth: usize,
+    variants: &'a RefCell<Variants>,
+}
+
+impl<'a> Sampler<'a> {
+    fn child(&self, key: &str) -> Sampler<'a> {
+        Sampler {
+            path: match self.path.is_empty() {
+                true => key.to_owned(),
+                false => format!("{}.{key}", self.path),
+            },
+            depth: self.depth + 1,
+            variants: self.variants,
+        }
+    }
+
+    fn deeper(&self) -> Sampler<'a> {
+        Sampler { path: self.path.clone(), depth: self.depth + 1, variants: self.variants }
+    }
+}
+
+macro_rules! sample_scalar {
+    ($($method:ident => $visit:ident($($value:expr)?)),* $(,)?) => {$(
+        fn $method<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.$visit($($value)?)
+        }
+    )*};
+}
+
+impl<'de> de::Deserializer<'de> for Sampler<'_> {
+    type Error = Error;
+
+    sample_scalar! {
+        deserialize_bool => visit_bool(false),
+        deserialize_i8 => visit_i8(0),
+        deserialize_i16 => visit_i16(0),
+        deserialize_i32 => visit_i32(0),
+        deserialize_i64 => visit_i64(0),
+        deserialize_i128 => visit_i128(0),
+        deserialize_u8 => visit_u8(0),
+        deserialize_u16 => visit_u16(0),
+        deserialize_u32 => visit_u32(0),
+        deserialize_u64 => visit_u64(0),
+        deserialize_u128 => visit_u128(0),
+        deserialize_f32 => visit_f32(0.0),
+        deserialize_f64 => visit_f64(0.0),
+        deserialize_char => visit_char('a'),
+        deserialize_str => visit_str(""),
+        deserialize_string => visit_str(""),
+        deserialize_identifier => visit_str(""),
+        deserialize_bytes => visit_bytes(&[]),
+        deserialize_byte_buf => visit_bytes(&[]),
+        deserialize_unit => visit_unit(),
+        deserialize_any => visit_unit(),
+        deserialize_ignored_any => visit_unit(),
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.depth < MAX_DEPTH {
+            true => visitor.visit_some(self.deeper()),
+            false => visitor.visit_none(),
+        }
+    }
+    fn deserialize_unit_struct<V: de::Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+    fn deserialize_seq<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_seq(de::value::SeqDeserializer::new(std::iter::empty::<()>()))
+    }
+    fn deserialize_tuple<V: de::Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_seq(Tuple { sampler: self, left: len })
+    }
+    fn deserialize_tuple_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_seq(Tuple { sampler: self, left: len })
+    }
+    fn deserialize_map<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_map(de::value::MapDeserializer::new(std::iter::empty::<((), ())>()))
+    }
+    fn deserialize_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_map(Fields { sampler: self, fields: fields.iter() })
+    }
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.variants.borrow_mut().push((self.path.clone(), variants));
+        let Some(first) = variants.first() else {
+            return Err(de::Error::custom("an enum without variants can't be sampled"));
+        };
+        visitor.visit_enum(Variant { sampler: self, name: first })
+    }
+}
+
+struct Tuple<'a> {
+    sampler: Sampler<'a>,
+    left: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for Tuple<'_> {
+    type Error = Error;
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Error> {
+        if self.left == 0 {
+            return Ok(None);
+        }
+        self.left -= 1;
+        seed.deserialize(self.sampler.deeper()).map(Some)
+    }
+}
+
+struct Fields<'a> {
+    sampler: Sampler<'a>,
+    fields: std::slice::Iter<'static, &'static str>,
+}
+
+impl<'de> de::MapAccess<'de> for Fields<'_> {
+    type Error = Error;
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+        match self.fields.as_slice().first() {
+            Some(field) => seed.deserialize((*field).into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let field = self.fields.next().expect("next_value_seed called before next_key_seed");
+        seed.deserialize(self.sampler.child(field))
+    }
+}
+
+struct Variant<'a> {
+    sampler: Sampler<'a>,
+    name: &'static str,
+}
+
+impl<'a, 'de> de::EnumAccess<'de> for Variant<'a> {
+    type Error = Error;
+    type Variant = Sampler<'a>;
+    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Sampler<'a>), Error> {
+        let variant = seed.deserialize(self.name.into_deserializer())?;
+        Ok((variant, self.sampler.deeper()))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Sampler<'_> {
+    type Error = Error;
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+    fn tuple_variant<V: de::Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_seq(Tuple { sampler: self, left: len })
+    }
+    fn struct_variant<V: de::Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_map(Fields { sampler: self, fields: fields.iter() })
+    }
+}
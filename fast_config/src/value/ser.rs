@@ -0,0 +1,376 @@
+use super::DATETIME;
+use super::DATETIME_STRUCT;
+use super::Datetime;
+use super::Error;
+use super::Map;
+use super::Value;
+use crate::Format;
+
+use serde::Serialize;
+use serde::ser;
+
+/// Converts any serializable type into a [`Value`].
+pub(crate) fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value, Error> {
+    value.serialize(Serializer)
+}
+
+/// Datetimes are written the way the `toml` crate writes its own, which TOML files and [`Value`] keep as datetimes.
+/// [`Value::to_string`] writes them as strings in the formats that have no datetimes.
+impl Serialize for Value {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Written { value: self, datetimes: true, tags: false }.serialize(serializer)
+    }
+}
+
+impl Value {
+    /// The value as written in `format`, with datetimes as TOML's own or else as strings,
+    /// and enum variants as YAML's `!Variant` tags or else as tables with a single key.
+    #[allow(unused_variables)]
+    pub(crate) fn in_format(&self, format: Format) -> Written<'_> {
+        #[cfg(feature = "toml")]
+        let datetimes = format == Format::TOML;
+        #[cfg(not(feature = "toml"))]
+        let datetimes = false;
+        #[cfg(feature = "yaml")]
+        let tags = format == Format::YAML;
+        #[cfg(not(feature = "yaml"))]
+        let tags = false;
+        Written { value: self, datetimes, tags }
+    }
+}
+
+/// A [`Value`] along with whether the serializer writing it has datetimes and tags, see [`Value::in_format`].
+pub(crate) struct Written<'a> {
+    value: &'a Value,
+    datetimes: bool,
+    tags: bool,
+}
+
+/// The name of an enum variant written as a map key that `serde_yml` turns into a `!Variant` tag.
+struct Tag<'a>(&'a str);
+
+impl Serialize for Tag<'_> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!("!{}", self.0))
+    }
+}
+
+impl Serialize for Written<'_> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use ser::SerializeMap;
+        use ser::SerializeSeq;
+        use ser::SerializeStruct;
+        let (datetimes, tags) = (self.datetimes, self.tags);
+        match self.value {
+            Value::Null => serializer.serialize_none(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Integer(i) => match (i64::try_from(*i), u64::try_from(*i)) {
+                (Ok(i), _) => serializer.serialize_i64(i),
+                (_, Ok(u)) => serializer.serialize_u64(u),
+                _ => serializer.serialize_i128(*i),
+            },
+            Value::Float(f) => serializer.serialize_f64(*f),
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Datetime(datetime) if datetimes => {
+                let mut fields = serializer.serialize_struct(DATETIME_STRUCT, 1)?;
+                fields.serialize_field(DATETIME, datetime.as_str())?;
+                fields.end()
+            }
+            Value::Datetime(datetime) => serializer.serialize_str(datetime.as_str()),
+            Value::Array(list) => {
+                let mut seq = serializer.serialize_seq(Some(list.len()))?;
+                for value in list {
+                    seq.serialize_element(&Written { value, datetimes, tags })?;
+                }
+                seq.end()
+            }
+            Value::Table(table) if tags && table.is_variant() => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                for (variant, value) in table.iter() {
+                    map.serialize_entry(&Tag(variant), &Written { value, datetimes, tags })?;
+                }
+                map.end()
+            }
+            Value::Table(table) => {
+                let mut map = serializer.serialize_map(Some(table.len()))?;
+                for (key, value) in table.iter() {
+                    map.serialize_entry(key, &Written { value, datetimes, tags })?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = Value;
+    type Error = Error;
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeVariant<SerializeList>;
+    type SerializeMap = SerializeTable;
+    type SerializeStruct = SerializeTable;
+    type SerializeStructVariant = SerializeVariant<SerializeTable>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, Error> {
+        Ok(Value::Bool(v))
+    }
+    fn serialize_i8(self, v: i8) -> Result<Value, Error> {
+        Ok(Value::Integer(v.into()))
+    }
+    fn serialize_i16(self, v: i16) -> Result<Value, Error> {
+        Ok(Value::Integer(v.into()))
+    }
+    fn serialize_i32(self, v: i32) -> Result<Value, Error> {
+        Ok(Value::Integer(v.into()))
+    }
+    fn serialize_i64(self, v: i64) -> Result<Value, Error> {
+        Ok(Value::Integer(v.into()))
+    }
+    fn serialize_i128(self, v: i128) -> Result<Value, Error> {
+        Ok(Value::Integer(v))
+    }
+    fn serialize_u8(self, v: u8) -> Result<Value, Error> {
+        Ok(Value::Integer(v.into()))
+    }
+    fn serialize_u16(self, v: u16) -> Result<Value, Error> {
+        Ok(Value::Integer(v.into()))
+    }
+    fn serialize_u32(self, v: u32) -> Result<Value, Error> {
+        Ok(Value::Integer(v.into()))
+    }
+    fn serialize_u64(self, v: u64) -> Result<Value, Error> {
+        Ok(Value::Integer(v.into()))
+    }
+    fn serialize_u128(self, v: u128) -> Result<Value, Error> {
+        i128::try_from(v)
+            .map(Value::Integer)
+            .map_err(|_| Error::new(format!("integer {v} is too large")))
+    }
+    fn serialize_f32(self, v: f32) -> Result<Value, Error> {
+        // Widened through its shortest decimal form, so an `f32` of 1.1 is written as 1.1 and not 1.100000023841858
+        Ok(Value::Float(v.to_string().parse().unwrap_or(v.into())))
+    }
+    fn serialize_f64(self, v: f64) -> Result<Value, Error> {
+        Ok(Value::Float(v))
+    }
+    fn serialize_char(self, v: char) -> Result<Value, Error> {
+        Ok(Value::String(v.to_string()))
+    }
+    fn serialize_str(self, v: &str) -> Result<Value, Error> {
+        Ok(Value::String(v.to_owned()))
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, Error> {
+        Ok(Value::Array(v.iter().map(|b| Value::Integer((*b).into())).collect()))
+    }
+    fn serialize_none(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Value, Error> {
+        Ok(Value::String(variant.to_owned()))
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        Ok(Value::Table(Map::variant(variant.to_owned(), value.serialize(self)?)))
+    }
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList, Error> {
+        Ok(SerializeList(Vec::with_capacity(len.unwrap_or(0))))
+    }
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList, Error> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeList, Error> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeTable, Error> {
+        Ok(SerializeTable {
+            map: Map::new(),
+            key: None,
+            datetime: false,
+        })
+    }
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<SerializeTable, Error> {
+        let table = self.serialize_map(Some(len))?;
+        Ok(SerializeTable { datetime: name == DATETIME_STRUCT, ..table })
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+pub(crate) struct SerializeList(Vec<Value>);
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = Value;
+    type Error = Error;
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let index = self.0.len();
+        self.0.push(value.serialize(Serializer).map_err(|e| e.in_index(index))?);
+        Ok(())
+    }
+    fn end(self) -> Result<Value, Error> {
+        Ok(Value::Array(self.0))
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = Value;
+    type Error = Error;
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+    fn end(self) -> Result<Value, Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = Value;
+    type Error = Error;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+    fn end(self) -> Result<Value, Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+pub(crate) struct SerializeTable {
+    map: Map,
+    key: Option<String>,
+    /// Whether this is a TOML datetime, such as one of `toml::value::Datetime`, rather than a table.
+    datetime: bool,
+}
+
+impl ser::SerializeMap for SerializeTable {
+    type Ok = Value;
+    type Error = Error;
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(match key.serialize(Serializer)? {
+            Value::String(s) => s,
+            Value::Integer(i) => i.to_string(),
+            Value::Bool(b) => b.to_string(),
+            other => return Err(Error::new(format!("map keys must be strings, found {}", other.kind()))),
+        });
+        Ok(())
+    }
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self.key.take().expect("serialize_value called before serialize_key");
+        let value = value.serialize(Serializer).map_err(|e| e.in_key(&key))?;
+        self.map.insert(key, value);
+        Ok(())
+    }
+    fn end(self) -> Result<Value, Error> {
+        Ok(Value::Table(self.map))
+    }
+}
+
+impl ser::SerializeStruct for SerializeTable {
+    type Ok = Value;
+    type Error = Error;
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        ser::SerializeMap::serialize_entry(self, key, value)
+    }
+    fn end(self) -> Result<Value, Error> {
+        match (self.datetime, self.map.get(DATETIME)) {
+            (true, Some(Value::String(text))) => Ok(Value::Datetime(Datetime(text.clone()))),
+            _ => ser::SerializeMap::end(self),
+        }
+    }
+}
+
+/// Wraps a tuple or struct variant's content in a single-key table named after the variant.
+pub(crate) struct SerializeVariant<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl<S> SerializeVariant<S> {
+    fn wrap(variant: &'static str, content: Value) -> Value {
+        Value::Table(Map::variant(variant.to_owned(), content))
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeVariant<SerializeList> {
+    type Ok = Value;
+    type Error = Error;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+    fn end(self) -> Result<Value, Error> {
+        Ok(Self::wrap(self.variant, ser::SerializeSeq::end(self.inner)?))
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeVariant<SerializeTable> {
+    type Ok = Value;
+    type Error = Error;
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        ser::SerializeMap::serialize_entry(&mut self.inner, key, value)
+    }
+    fn end(self) -> Result<Value, Error> {
+        Ok(Self::wrap(self.variant, ser::SerializeMap::end(self.inner)?))
+    }
+}
@@ -0,0 +1,523 @@
+use crate::validate;
+
+use proc_macro2::TokenStream;
+use quote::format_ident;
+use quote::quote;
+use syn::Attribute;
+use syn::Data;
+use syn::DeriveInput;
+use syn::Expr;
+use syn::Fields;
+use syn::Ident;
+use syn::LitStr;
+use syn::Type;
+use syn::meta::ParseNestedMeta;
+use syn::parse::Parse;
+
+/// Options set on the struct itself with `#[fast_config(...)]`.
+pub(crate) struct Container {
+    pub(crate) crate_path: TokenStream,
+    pub(crate) env_prefix: Option<String>,
+    /// Name of the generated clap arguments struct, if requested with `#[fast_config(clap)]`.
+    pub(crate) clap: Option<Ident>,
+    pub(crate) interpolate: bool,
+    /// Set by `#[fast_config(verify_save)]`, reading back what `save` writes before writing it.
+    pub(crate) verify_save: bool,
+    /// `#[fast_config(numbers = "string")]`, the `NumberPolicy` variant to use.
+    pub(crate) numbers: Option<Ident>,
+    pub(crate) includes: Option<IncludesAttr>,
+    pub(crate) profiles: Option<ProfilesAttr>,
+    /// Set by `#[fast_config(validate)]`, holding whether `on_save` was given too.
+    pub(crate) validate: Option<bool>,
+    pub(crate) version: Option<VersionAttr>,
+    /// `#[fast_config(migrate(v1 = "...", v2 = "..."))]`, the function upgrading each older version to the next one.
+    pub(crate) migrations: Vec<(u32, syn::Path)>,
+    pub(crate) aliases: Vec<AliasAttr>,
+    /// `#[fast_config(on_warning = "...")]`, the function plain loads hand each warning to.
+    pub(crate) on_warning: Option<syn::Path>,
+}
+
+/// `#[fast_config(version = 3)]` or `#[fast_config(version(current = 3, key = "...", rewrite))]`.
+pub(crate) struct VersionAttr {
+    pub(crate) current: syn::LitInt,
+    pub(crate) key: Option<String>,
+    pub(crate) rewrite: bool,
+}
+
+/// `#[fast_config(includes(key = "...", root = "...", drop_ins = false))]`, every option being optional.
+#[derive(Default)]
+pub(crate) struct IncludesAttr {
+    pub(crate) key: Option<String>,
+    pub(crate) root: Option<String>,
+    pub(crate) drop_ins: Option<bool>,
+}
+
+/// `#[fast_config(profiles(env = "...", default = "..."))]`, every option being optional.
+#[derive(Default)]
+pub(crate) struct ProfilesAttr {
+    pub(crate) env: Option<String>,
+    pub(crate) default: Option<String>,
+}
+
+/// A named field along with its `#[fast_config(...)]` options.
+pub(crate) struct Field {
+    pub(crate) ident: Ident,
+    pub(crate) ty: Type,
+    /// The key this field is written under, after serde renames.
+    pub(crate) name: String,
+    pub(crate) env: Option<String>,
+    pub(crate) nested: bool,
+    /// The field's doc comment, with the leading space of each line removed.
+    pub(crate) docs: String,
+    /// Whether serde never reads this field, through `skip` or `skip_deserializing`.
+    pub(crate) skipped: bool,
+    /// Whether serde reads this field with a function of its own, through `with` or `deserialize_with`.
+    pub(crate) custom: bool,
+    /// Validation rules such as `range(min = 1)`, in the order they were written.
+    pub(crate) rules: Vec<Rule>,
+    /// Rules put on each item of a list or each value of a map with `each(...)`.
+    pub(crate) items: Vec<Rule>,
+    /// Set by `#[fast_config(unknown)]`, keeping the keys no field asked for.
+    pub(crate) unknown: bool,
+    /// `#[fast_config(deprecated_alias = "...", since = "...")]`, the old name of this field.
+    pub(crate) alias: Option<String>,
+    pub(crate) since: Option<String>,
+}
+
+/// `#[fast_config(deprecated_alias(from = "...", to = "...", since = "..."))]` on the struct, for keys moved across nesting levels.
+pub(crate) struct AliasAttr {
+    pub(crate) from: String,
+    pub(crate) to: String,
+    pub(crate) since: Option<String>,
+}
+
+/// A declarative validation rule put on a field.
+pub(crate) enum Rule {
+    /// `range(min = ..., max = ...)`, either bound being optional.
+    Range { min: Option<Expr>, max: Option<Expr> },
+    /// `length(min = ..., max = ...)`, counting characters, items or entries.
+    Length { min: Option<Expr>, max: Option<Expr> },
+    /// `regex = "..."`, which needs the `regex` feature.
+    Regex(String),
+    /// `one_of("a", "b")` or `one_of = ["a", "b"]`.
+    OneOf(Vec<Expr>),
+    NonEmpty,
+    PathExists,
+    /// `custom = "path::to::function"`, called with a reference to the field or item.
+    Custom(syn::Path),
+}
+
+pub(crate) fn parse_container(input: &DeriveInput) -> syn::Result<Container> {
+    let mut container = Container {
+        crate_path: quote! { fast_config },
+        env_prefix: None,
+        clap: None,
+        interpolate: false,
+        verify_save: false,
+        numbers: None,
+        includes: None,
+        profiles: None,
+        validate: None,
+        version: None,
+        migrations: Vec::new(),
+        aliases: Vec::new(),
+        on_warning: None,
+    };
+    for attr in fast_config_attrs(&input.attrs) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("crate") {
+                let path = meta.value()?.parse::<LitStr>()?;
+                container.crate_path = path.parse()?;
+            } else if meta.path.is_ident("env_prefix") {
+                let prefix = meta.value()?.parse::<LitStr>()?.value();
+                container.env_prefix = Some(prefix.trim_end_matches('_').to_owned());
+            } else if meta.path.is_ident("clap") {
+                let name = match meta.input.peek(syn::Token![=]) {
+                    true => meta.value()?.parse::<LitStr>()?.parse::<Ident>()?,
+                    false => format_ident!("{}Args", input.ident),
+                };
+                container.clap = Some(name);
+            } else if meta.path.is_ident("interpolate") {
+                container.interpolate = true;
+            } else if meta.path.is_ident("verify_save") {
+                container.verify_save = true;
+            } else if meta.path.is_ident("numbers") {
+                let policy = meta.value()?.parse::<LitStr>()?;
+                let variant = match policy.value().as_str() {
+                    "native" => "Native",
+                    "error" => "Error",
+                    "string" => "String",
+                    _ => return Err(syn::Error::new(policy.span(), "expected `native`, `error` or `string`")),
+                };
+                container.numbers = Some(Ident::new(variant, policy.span()));
+            } else if meta.path.is_ident("includes") {
+                let mut includes = IncludesAttr::default();
+                if meta.input.peek(syn::token::Paren) {
+                    meta.parse_nested_meta(|inner| {
+                        if inner.path.is_ident("key") {
+                            includes.key = Some(inner.value()?.parse::<LitStr>()?.value());
+                        } else if inner.path.is_ident("root") {
+                            includes.root = Some(inner.value()?.parse::<LitStr>()?.value());
+                        } else if inner.path.is_ident("drop_ins") {
+                            includes.drop_ins = Some(inner.value()?.parse::<syn::LitBool>()?.value);
+                        } else {
+                            return Err(inner.error("unknown includes option"));
+                        }
+                        Ok(())
+                    })?;
+                }
+                container.includes = Some(includes);
+            } else if meta.path.is_ident("profiles") {
+                let mut profiles = ProfilesAttr::default();
+                if meta.input.peek(syn::token::Paren) {
+                    meta.parse_nested_meta(|inner| {
+                        if inner.path.is_ident("env") {
+                            profiles.env = Some(inner.value()?.parse::<LitStr>()?.value());
+                        } else if inner.path.is_ident("default") {
+                            profiles.default = Some(inner.value()?.parse::<LitStr>()?.value());
+                        } else {
+                            return Err(inner.error("unknown profiles option"));
+                        }
+                        Ok(())
+                    })?;
+                }
+                container.profiles = Some(profiles);
+            } else if meta.path.is_ident("validate") {
+                let mut on_save = false;
+                if meta.input.peek(syn::token::Paren) {
+                    meta.parse_nested_meta(|inner| {
+                        if !inner.path.is_ident("on_save") {
+                            return Err(inner.error("unknown validate option"));
+                        }
+                        on_save = true;
+                        Ok(())
+                    })?;
+                }
+                container.validate = Some(on_save);
+            } else if meta.path.is_ident("version") {
+                if meta.input.peek(syn::Token![=]) {
+                    let current = meta.value()?.parse::<syn::LitInt>()?;
+                    container.version = Some(VersionAttr { current, key: None, rewrite: false });
+                    return Ok(());
+                }
+                let (mut current, mut key, mut rewrite) = (None, None, false);
+                meta.parse_nested_meta(|inner| {
+                    if inner.path.is_ident("current") {
+                        current = Some(inner.value()?.parse::<syn::LitInt>()?);
+                    } else if inner.path.is_ident("key") {
+                        key = Some(inner.value()?.parse::<LitStr>()?.value());
+                    } else if inner.path.is_ident("rewrite") {
+                        rewrite = true;
+                    } else {
+                        return Err(inner.error("unknown version option"));
+                    }
+                    Ok(())
+                })?;
+                let current = current.ok_or_else(|| meta.error("expected `current = ...`"))?;
+                container.version = Some(VersionAttr { current, key, rewrite });
+            } else if meta.path.is_ident("migrate") {
+                meta.parse_nested_meta(|inner| {
+                    let from = inner
+                        .path
+                        .get_ident()
+                        .and_then(|ident| ident.to_string().strip_prefix('v')?.parse::<u32>().ok())
+                        .ok_or_else(|| inner.error("expected the version migrated from, such as `v1`"))?;
+                    let with = inner.value()?.parse::<LitStr>()?.parse::<syn::Path>()?;
+                    container.migrations.push((from, with));
+                    Ok(())
+                })?;
+            } else if meta.path.is_ident("on_warning") {
+                container.on_warning = Some(meta.value()?.parse::<LitStr>()?.parse::<syn::Path>()?);
+            } else if meta.path.is_ident("deprecated_alias") {
+                let (mut from, mut to, mut since) = (None, None, None);
+                meta.parse_nested_meta(|inner| {
+                    let value = inner.value()?.parse::<LitStr>()?.value();
+                    if inner.path.is_ident("from") {
+                        from = Some(value);
+                    } else if inner.path.is_ident("to") {
+                        to = Some(value);
+                    } else if inner.path.is_ident("since") {
+                        since = Some(value);
+                    } else {
+                        return Err(inner.error("expected `from`, `to` or `since`"));
+                    }
+                    Ok(())
+                })?;
+                match (from, to) {
+                    (Some(from), Some(to)) => container.aliases.push(AliasAttr { from, to, since }),
+                    _ => return Err(meta.error("expected `deprecated_alias(from = \"...\", to = \"...\")`")),
+                }
+            } else {
+                return Err(meta.error("unknown fast_config container attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    if container.version.is_none() && !container.migrations.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "`#[fast_config(migrate(...))]` needs `#[fast_config(version = ...)]`",
+        ));
+    }
+    Ok(container)
+}
+
+pub(crate) fn parse_fields(input: &DeriveInput) -> syn::Result<Vec<Field>> {
+    let Data::Struct(data) = &input.data else {
+        return Ok(Vec::new());
+    };
+    let Fields::Named(named) = &data.fields else {
+        return Ok(Vec::new());
+    };
+    let rename_all = serde_rename_all(&input.attrs);
+    let mut fields = Vec::new();
+    for field in &named.named {
+        let ident = field.ident.as_ref().expect("named fields have identifiers");
+        let raw = ident.to_string();
+        let raw = raw.strip_prefix("r#").unwrap_or(&raw);
+        let mut parsed = Field {
+            ident: ident.clone(),
+            ty: field.ty.clone(),
+            name: serde_rename(&field.attrs)
+                .unwrap_or_else(|| apply_rename_rule(raw, rename_all.as_deref())),
+            env: None,
+            nested: false,
+            docs: doc_comment(&field.attrs),
+            skipped: serde_flag(&field.attrs, &["skip", "skip_deserializing"]),
+            custom: serde_flag(&field.attrs, &["with", "deserialize_with"]),
+            rules: Vec::new(),
+            items: Vec::new(),
+            unknown: false,
+            alias: None,
+            since: None,
+        };
+        for attr in fast_config_attrs(&field.attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("env") {
+                    parsed.env = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("nested") {
+                    parsed.nested = true;
+                } else if meta.path.is_ident("each") {
+                    meta.parse_nested_meta(|inner| {
+                        let rule = parse_rule(&inner)?;
+                        let rule = rule.ok_or_else(|| inner.error("expected a rule such as `range(min = 1)`"))?;
+                        parsed.items.push(rule);
+                        Ok(())
+                    })?;
+                } else if let Some(rule) = parse_rule(&meta)? {
+                    parsed.rules.push(rule);
+                } else if meta.path.is_ident("deprecated_alias") {
+                    parsed.alias = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("since") {
+                    parsed.since = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("unknown") {
+                    parsed.unknown = true;
+                } else {
+                    return Err(meta.error("unknown fast_config field attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        if !parsed.items.is_empty() && validate::items(&parsed.ty).is_none() {
+            let message = "`each` needs a list, set, array or map field, or an `Option` of one";
+            return Err(syn::Error::new_spanned(&field.ty, message));
+        }
+        if parsed.since.is_some() && parsed.alias.is_none() {
+            return Err(syn::Error::new_spanned(ident, "`since` needs `deprecated_alias = \"...\"`"));
+        }
+        // Serde would otherwise read and write the kept keys as a field of their own
+        if parsed.unknown && !serde_flag(&field.attrs, &["skip"]) {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "`#[fast_config(unknown)]` fields must also be `#[serde(skip)]`",
+            ));
+        }
+        if parsed.unknown && fields.iter().any(|field: &Field| field.unknown) {
+            return Err(syn::Error::new_spanned(ident, "only one field can be `#[fast_config(unknown)]`"));
+        }
+        fields.push(parsed);
+    }
+    Ok(fields)
+}
+
+/// Reads a validation rule such as `range(min = 1)`, or `None` when the option isn't one.
+fn parse_rule(meta: &ParseNestedMeta) -> syn::Result<Option<Rule>> {
+    let rule = if meta.path.is_ident("range") {
+        let (min, max) = parse_bounds(meta)?;
+        Rule::Range { min, max }
+    } else if meta.path.is_ident("length") {
+        let (min, max) = parse_bounds(meta)?;
+        Rule::Length { min, max }
+    } else if meta.path.is_ident("regex") {
+        Rule::Regex(parse_pattern(meta)?)
+    } else if meta.path.is_ident("one_of") {
+        let content;
+        if meta.input.peek(syn::Token![=]) {
+            let input = meta.value()?;
+            syn::bracketed!(content in input);
+        } else {
+            syn::parenthesized!(content in meta.input);
+        }
+        let values = content.parse_terminated(Expr::parse, syn::Token![,])?;
+        Rule::OneOf(values.into_iter().collect())
+    } else if meta.path.is_ident("non_empty") {
+        Rule::NonEmpty
+    } else if meta.path.is_ident("path_exists") {
+        Rule::PathExists
+    } else if meta.path.is_ident("custom") {
+        Rule::Custom(meta.value()?.parse::<LitStr>()?.parse()?)
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(rule))
+}
+
+/// Reads the `(min = ..., max = ...)` of `range` and `length`, requiring at least one of them.
+fn parse_bounds(meta: &ParseNestedMeta) -> syn::Result<(Option<Expr>, Option<Expr>)> {
+    let (mut min, mut max) = (None, None);
+    meta.parse_nested_meta(|inner| {
+        if inner.path.is_ident("min") {
+            min = Some(inner.value()?.parse::<Expr>()?);
+        } else if inner.path.is_ident("max") {
+            max = Some(inner.value()?.parse::<Expr>()?);
+        } else {
+            return Err(inner.error("expected `min` or `max`"));
+        }
+        Ok(())
+    })?;
+    if min.is_none() && max.is_none() {
+        return Err(meta.error("expected `min`, `max` or both"));
+    }
+    Ok((min, max))
+}
+
+/// Reads the pattern of `regex`, compiling it so an invalid one is reported where it's written.
+fn parse_pattern(meta: &ParseNestedMeta) -> syn::Result<String> {
+    let pattern = meta.value()?.parse::<LitStr>()?;
+    check_pattern(&pattern)?;
+    Ok(pattern.value())
+}
+
+#[cfg(feature = "regex")]
+fn check_pattern(pattern: &LitStr) -> syn::Result<()> {
+    match regex::Regex::new(&pattern.value()) {
+        Ok(_) => Ok(()),
+        Err(error) => Err(syn::Error::new_spanned(pattern, format!("invalid regex: {error}"))),
+    }
+}
+
+#[cfg(not(feature = "regex"))]
+fn check_pattern(pattern: &LitStr) -> syn::Result<()> {
+    let message = "`regex` needs fast_config's `regex` feature, as `features = [\"regex\"]`";
+    Err(syn::Error::new_spanned(pattern, message))
+}
+
+fn fast_config_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("fast_config"))
+}
+
+/// Joins the lines of `///` comments, which arrive as `#[doc = "..."]` attributes.
+fn doc_comment(attrs: &[Attribute]) -> String {
+    let lines = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value: syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(line), .. }),
+                ..
+            }) => Some(line.value()),
+            _ => None,
+        })
+        .map(|line| line.strip_prefix(' ').map(str::to_owned).unwrap_or(line))
+        .collect::<Vec<_>>();
+    lines.join("\n").trim().to_owned()
+}
+
+/// Whether any of the given options, such as `#[serde(skip)]` or `#[serde(with = "...")]`, is present.
+fn serde_flag(attrs: &[Attribute], flags: &[&str]) -> bool {
+    let mut found = false;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if flags.iter().any(|flag| meta.path.is_ident(flag)) {
+                found = true;
+            }
+            skip_meta_value(&meta)
+        });
+    }
+    found
+}
+
+/// Reads `#[serde(rename = "...")]`, preferring the serialize name when both are given.
+fn serde_rename(attrs: &[Attribute]) -> Option<String> {
+    serde_name_value(attrs, "rename")
+}
+
+/// Reads `#[serde(rename_all = "...")]` off the container.
+fn serde_rename_all(attrs: &[Attribute]) -> Option<String> {
+    serde_name_value(attrs, "rename_all")
+}
+
+fn serde_name_value(attrs: &[Attribute], key: &str) -> Option<String> {
+    let mut found = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        // Serde reports malformed attributes itself, so parse errors are ignored here
+        let _ = attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident(key) {
+                return skip_meta_value(&meta);
+            }
+            if meta.input.peek(syn::Token![=]) {
+                found = Some(meta.value()?.parse::<LitStr>()?.value());
+                return Ok(());
+            }
+            meta.parse_nested_meta(|inner| {
+                let value = inner.value()?.parse::<LitStr>()?.value();
+                if inner.path.is_ident("serialize") {
+                    found = Some(value);
+                }
+                Ok(())
+            })
+        });
+    }
+    found
+}
+
+/// Consumes the value of a meta item we don't care about, such as `default = "..."` or `bound(...)`.
+fn skip_meta_value(meta: &ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(syn::Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        let content;
+        syn::parenthesized!(content in meta.input);
+        content.parse::<TokenStream>()?;
+    }
+    Ok(())
+}
+
+/// Mirrors serde's `rename_all` rules for a snake_case field name.
+fn apply_rename_rule(name: &str, rule: Option<&str>) -> String {
+    let words = name.split('_').filter(|w| !w.is_empty());
+    let capitalize = |w: &str| {
+        let mut chars = w.chars();
+        chars
+            .next()
+            .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+            .unwrap_or_default()
+    };
+    match rule {
+        Some("lowercase") => name.to_lowercase(),
+        Some("UPPERCASE") => name.to_uppercase(),
+        Some("PascalCase") => words.map(capitalize).collect(),
+        Some("camelCase") => {
+            let pascal: String = words.map(capitalize).collect();
+            let mut chars = pascal.chars();
+            chars
+                .next()
+                .map(|c| c.to_lowercase().chain(chars).collect())
+                .unwrap_or_default()
+        }
+        Some("SCREAMING_SNAKE_CASE") => name.to_uppercase(),
+        Some("kebab-case") => name.replace('_', "-"),
+        Some("SCREAMING-KEBAB-CASE") => name.to_uppercase().replace('_', "-"),
+        _ => name.to_owned(),
+    }
+}
@@ -0,0 +1,168 @@
+use crate::attrs::Container;
+use crate::attrs::Field;
+use crate::validate;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::DeriveInput;
+use syn::Type;
+
+/// Generates `cli_flags`, listing a flag for each field with a parser checking the field's type. <br/>
+/// Plain `nested` structs add their own flags under the field's name, such as `server.port`,
+/// while anything else is set as a whole, as inline TOML/JSON.
+pub(crate) fn expand_flags(input: &DeriveInput, container: &Container, fields: &[Field]) -> TokenStream {
+    let crate_path = &container.crate_path;
+    let params = input.generics.type_params().map(|param| param.ident.to_string()).collect::<Vec<_>>();
+    let pushes = fields.iter().filter(|field| !field.skipped && !field.unknown).map(|field| {
+        let name = field.ident.to_string().trim_start_matches("r#").to_owned();
+        let path = &field.name;
+        let ty = &field.ty;
+        if field.nested && validate::wrapper(ty).is_none() {
+            return quote! {
+                for flag in <#ty as #crate_path::FastConfig>::cli_flags() {
+                    flags.push(#crate_path::__private::Flag {
+                        name: format!("{}.{}", #name, flag.name),
+                        path: format!("{}.{}", #path, flag.path),
+                        ..flag
+                    });
+                }
+            };
+        }
+        let doc = match field.docs.is_empty() {
+            true => quote! { None },
+            false => {
+                let docs = &field.docs;
+                quote! { Some(#docs) }
+            }
+        };
+        let switch = is_bool(ty);
+        // Generic types may not be deserializable on their own, and custom ones are read by other means
+        let parse = match field.custom || mentions(quote! { #ty }, &params) {
+            true => quote! { #crate_path::__private::flag_text },
+            false => quote! { #crate_path::__private::flag_value::<#ty> },
+        };
+        quote! {
+            flags.push(#crate_path::__private::Flag {
+                name: #name.into(),
+                path: #path.into(),
+                doc: #doc,
+                switch: #switch,
+                parse: #parse,
+            });
+        }
+    });
+    quote! {
+        fn cli_flags() -> Vec<#crate_path::__private::Flag> {
+            let mut flags = Vec::new();
+            #(#pushes)*
+            flags
+        }
+    }
+}
+
+/// Generates the `#[fast_config(clap)]` companion struct, with one optional long flag per field.
+pub(crate) fn expand(input: &DeriveInput, container: &Container) -> TokenStream {
+    let Some(args) = &container.clap else {
+        return TokenStream::new();
+    };
+    // The flags are built from the config's fields, which needs a single config type
+    if !input.generics.params.is_empty() {
+        let message = "`#[fast_config(clap)]` can't be used on a struct with generic parameters";
+        return syn::Error::new_spanned(&input.generics, message).to_compile_error();
+    }
+    let crate_path = &container.crate_path;
+    let clap = quote! { #crate_path::__private::clap };
+    let (vis, ident) = (&input.vis, &input.ident);
+    let doc = format!(
+        "Command-line flags for every field of [`{ident}`], generated by `#[fast_config(clap)]`. <br/>\n\
+         Fields of `nested` structs get flags of their own, such as `--server.port`."
+    );
+    quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, Default, PartialEq)]
+        #vis struct #args {
+            /// The key path and value of each flag that was passed, in the order the fields are declared.
+            pub values: Vec<(String, #crate_path::Value)>,
+        }
+
+        impl #args {
+            /// Overlays the flags that were passed onto `config`. <br/>
+            /// Call this after `load` or `new` so flags take precedence over the file and the environment.
+            pub fn apply(&self, config: &mut #ident) -> Result<(), #crate_path::Error> {
+                #crate_path::__private::apply_flags(config, &self.values)
+            }
+        }
+
+        impl #clap::FromArgMatches for #args {
+            fn from_arg_matches(matches: &#clap::ArgMatches) -> Result<Self, #clap::Error> {
+                let mut args = Self::default();
+                #clap::FromArgMatches::update_from_arg_matches(&mut args, matches)?;
+                Ok(args)
+            }
+            fn update_from_arg_matches(&mut self, matches: &#clap::ArgMatches) -> Result<(), #clap::Error> {
+                for flag in <#ident as #crate_path::FastConfig>::cli_flags() {
+                    // Named like clap names flags, as `server.max-connections`
+                    if let Some(value) = matches.get_one::<#crate_path::Value>(&flag.name.replace('_', "-")) {
+                        self.values.retain(|(path, _)| *path != flag.path);
+                        self.values.push((flag.path, value.clone()));
+                    }
+                }
+                Ok(())
+            }
+        }
+
+        impl #clap::Args for #args {
+            fn augment_args(cmd: #clap::Command) -> #clap::Command {
+                let mut cmd = cmd;
+                for flag in <#ident as #crate_path::FastConfig>::cli_flags() {
+                    let long = flag.name.replace('_', "-");
+                    let value_name = flag.name.rsplit('.').next().unwrap_or_default().to_uppercase();
+                    let mut arg = #clap::Arg::new(long.clone())
+                        .long(long)
+                        .value_name(value_name)
+                        .action(#clap::ArgAction::Set)
+                        .value_parser(flag.parse);
+                    if flag.switch {
+                        arg = arg.num_args(0..=1).default_missing_value("true");
+                    }
+                    if let Some(doc) = flag.doc {
+                        arg = arg.help(doc);
+                    }
+                    cmd = cmd.arg(arg);
+                }
+                cmd
+            }
+            fn augment_args_for_update(cmd: #clap::Command) -> #clap::Command {
+                <Self as #clap::Args>::augment_args(cmd)
+            }
+        }
+    }
+}
+
+/// Whether any of the identifiers, such as generic parameters, appears in the tokens.
+fn mentions(tokens: TokenStream, idents: &[String]) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => idents.contains(&ident.to_string()),
+        proc_macro2::TokenTree::Group(group) => mentions(group.stream(), idents),
+        _ => false,
+    })
+}
+
+/// Whether the field is a `bool` or `Option<bool>`, which become flags that don't need a value.
+fn is_bool(ty: &Type) -> bool {
+    let Type::Path(path) = ty else {
+        return false;
+    };
+    let Some(last) = path.path.segments.last() else {
+        return false;
+    };
+    if last.ident == "bool" {
+        return true;
+    }
+    match &last.arguments {
+        syn::PathArguments::AngleBracketed(args) if last.ident == "Option" => {
+            matches!(args.args.first(), Some(syn::GenericArgument::Type(inner)) if is_bool(inner))
+        }
+        _ => false,
+    }
+}
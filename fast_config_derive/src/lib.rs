@@ -1,79 +1,354 @@
 use proc_macro::TokenStream;
-use proc_macro2;
 use quote::quote;
 use syn::DeriveInput;
 use syn::parse_macro_input;
-use syn::Attribute;
-use syn::Meta;
+
+mod attrs;
+mod cli;
+mod validate;
 
 #[proc_macro_derive(FastConfig, attributes(fast_config))]
 pub fn derive_config(input: TokenStream) -> TokenStream {
-    let path_type = quote! {impl AsRef<std::path::Path>};
-
     let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let path_type = quote! {impl AsRef<std::path::Path>};
     let ident = &input.ident;
-    
-    // Extract crate path from attributes, default to "fast_config"
-    let crate_path = extract_crate_path(&input.attrs);
+
+    let container = attrs::parse_container(input)?;
+    let fields = attrs::parse_fields(input)?;
+    let crate_path = &container.crate_path;
 
     let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let nested_bounds = fields.iter().filter(|field| field.nested).map(|field| {
+        let ty = validate::innermost(&field.ty);
+        quote! { #ty: #crate_path::FastConfig, }
+    });
+    // Prefixed environment variables are matched against the keys the fields describe
+    let fields_bound = container.env_prefix.as_ref().map(|_| quote! { Self: #crate_path::ConfigFields, });
     let where_clause = quote! { where
-        Self: for<'a> Deserialize<'a> + Serialize + Sized
+        Self: for<'a> Deserialize<'a> + Serialize + Sized,
+        #fields_bound
+        #(#nested_bounds)*
     };
-    quote! {
+    let env = expand_env(&container, &fields);
+    let cli = cli::expand(input, &container);
+    let flags = cli::expand_flags(input, &container, &fields);
+    let loading = expand_loading(&container);
+    let check = validate::expand(&container, &fields);
+    let constraints = validate::expand_constraints(crate_path, &fields);
+    let unknown = expand_unknown(&container, &fields);
+    let aliases = expand_aliases(&container, &fields);
+    let descriptors = expand_descriptors(input, &container, &fields);
+    Ok(quote! {
         impl #impl_generics #crate_path::FastConfig for #ident #ty_generics #where_clause {
             fn load(&mut self, path: #path_type, format: #crate_path::Format) -> Result<(), #crate_path::Error> {
-                let mut content = String::new();
-                let mut file = std::fs::File::open(path)?;
-                std::io::Read::read_to_string(&mut file, &mut content)?;
-                *self = #crate_path::FastConfig::from_string(&content, format)?;
+                *self = #crate_path::FastConfig::new(path, format)?;
                 Ok(())
             }
             fn save(&self, path: #path_type, format: #crate_path::Format) -> Result<(), #crate_path::Error> {
-                if let Some(parent_dir) = path.as_ref().parent() {
-                    std::fs::create_dir_all(parent_dir)?;
-                }
-                let mut file = std::fs::File::create(path)?;
-                let content = #crate_path::FastConfig::to_string(self, format)?;
-                use std::io::Write;
-                write!(file, "{}", content)?;
-                Ok(())
+                #crate_path::__private::save(self, path, format, false)
             }
             fn save_pretty(&self, path: #path_type, format: #crate_path::Format) -> Result<(), #crate_path::Error> {
-                if let Some(parent_dir) = path.as_ref().parent() {
-                    std::fs::create_dir_all(parent_dir)?;
-                }
-                let mut file = std::fs::File::create(path)?;
-                let content = #crate_path::FastConfig::to_string_pretty(self, format)?;
-                use std::io::Write;
-                write!(file, "{}", content)?;
-                Ok(())
+                #crate_path::__private::save(self, path, format, true)
+            }
+            #env
+            #loading
+            #check
+            #constraints
+            #unknown
+            #aliases
+            #flags
+        }
+
+        #descriptors
+        #cli
+    })
+}
+
+/// Generates `env_prefix`, `env_vars` and `apply_env` when the struct opts into environment overrides.
+fn expand_env(container: &attrs::Container, fields: &[attrs::Field]) -> proc_macro2::TokenStream {
+    let crate_path = &container.crate_path;
+    let prefix = container.env_prefix.as_ref().map(|prefix| {
+        quote! {
+            fn env_prefix() -> Option<&'static str> {
+                Some(#prefix)
+            }
+            fn apply_env(&mut self) -> Result<(), #crate_path::Error> {
+                #crate_path::__private::apply_env(self, <Self as #crate_path::ConfigFields>::FIELDS)
+            }
+        }
+    });
+    let fields = fields.iter().filter(|field| !field.skipped).collect::<Vec<_>>();
+    let pinned = fields.iter().filter_map(|field| {
+        let (name, path) = (field.env.as_ref()?, &field.name);
+        Some(quote! {
+            vars.push(#crate_path::EnvVar { name: #name.into(), path: #path.into() });
+        })
+    });
+    // Only plain nested structs have a fixed key path, so fields wrapped in a container are left out
+    let nested = fields.iter().filter(|field| field.nested && validate::wrapper(&field.ty).is_none());
+    let nested = nested.map(|field| {
+        let (ty, path) = (&field.ty, &field.name);
+        quote! {
+            for var in <#ty as #crate_path::FastConfig>::env_vars() {
+                vars.push(#crate_path::EnvVar { name: var.name, path: format!("{}.{}", #path, var.path) });
             }
         }
-    }.into()
+    });
+    let vars = match fields.iter().any(|field| field.env.is_some() || field.nested) {
+        true => Some(quote! {
+            fn env_vars() -> Vec<#crate_path::EnvVar> {
+                let mut vars = Vec::new();
+                #(#pinned)*
+                #(#nested)*
+                vars
+            }
+        }),
+        false => None,
+    };
+    quote! { #prefix #vars }
 }
 
-fn extract_crate_path(attrs: &[Attribute]) -> proc_macro2::TokenStream {
-    for attr in attrs {
-        if attr.path().is_ident("fast_config") {
-            // Parse the attribute content - for #[fast_config(crate = "...")]
-            // parse_args parses what's inside the parentheses
-            if let Ok(meta) = attr.parse_args::<Meta>() {
-                if let Meta::NameValue(name_value) = meta {
-                    if name_value.path.is_ident("crate") {
-                        if let syn::Expr::Lit(syn::ExprLit {
-                            lit: syn::Lit::Str(lit_str),
-                            ..
-                        }) = name_value.value
-                        {
-                            let path_str = lit_str.value();
-                            return syn::parse_str::<proc_macro2::TokenStream>(&path_str)
-                                .unwrap_or_else(|_| quote! { fast_config });
-                        }
-                    }
+/// Generates `includes`, `profiles`, `interpolate`, `versioning`, `verify_on_save`, `numbers` and `on_warning`,
+/// which change how files are read and written.
+fn expand_loading(container: &attrs::Container) -> proc_macro2::TokenStream {
+    let crate_path = &container.crate_path;
+    let includes = container.includes.as_ref().map(|includes| {
+        let key = includes.key.as_ref().map(|key| quote! { key: #key.into(), });
+        let root = includes.root.as_ref().map(|root| quote! { root: Some(#root.into()), });
+        let drop_ins = includes.drop_ins.map(|drop_ins| quote! { drop_ins: #drop_ins, });
+        quote! {
+            fn includes() -> Option<#crate_path::Includes> {
+                Some(#crate_path::Includes {
+                    #key
+                    #root
+                    #drop_ins
+                    ..Default::default()
+                })
+            }
+        }
+    });
+    let profiles = container.profiles.as_ref().map(|profiles| {
+        let env = profiles.env.as_ref().map(|env| quote! { env: Some(#env.into()), });
+        let default = profiles.default.as_ref().map(|default| quote! { default: #default.into(), });
+        quote! {
+            fn profiles() -> Option<#crate_path::Profiles> {
+                Some(#crate_path::Profiles {
+                    #env
+                    #default
+                    ..Default::default()
+                })
+            }
+        }
+    });
+    let interpolate = container.interpolate.then(|| {
+        quote! {
+            fn interpolate() -> bool {
+                true
+            }
+        }
+    });
+    let versioning = container.version.as_ref().map(|version| {
+        let current = &version.current;
+        let key = version.key.as_deref().unwrap_or("version");
+        let rewrite = version.rewrite;
+        let migrations = container.migrations.iter().map(|(from, with)| {
+            quote! {
+                #crate_path::Migration {
+                    from: #from,
+                    migrate: |document| #with(document).map_err(|e| e.to_string()),
                 }
             }
+        });
+        quote! {
+            fn versioning() -> Option<#crate_path::Versioning> {
+                Some(#crate_path::Versioning {
+                    current: #current,
+                    key: #key.into(),
+                    migrations: vec![#(#migrations),*],
+                    rewrite: #rewrite,
+                })
+            }
+        }
+    });
+    let verify_save = container.verify_save.then(|| {
+        quote! {
+            fn verify_on_save() -> bool {
+                true
+            }
+        }
+    });
+    let numbers = container.numbers.as_ref().map(|variant| {
+        quote! {
+            fn numbers() -> #crate_path::NumberPolicy {
+                #crate_path::NumberPolicy::#variant
+            }
+        }
+    });
+    let on_warning = container.on_warning.as_ref().map(|with| {
+        quote! {
+            fn on_warning() -> Option<fn(&#crate_path::Problem)> {
+                Some(#with)
+            }
+        }
+    });
+    quote! { #includes #profiles #interpolate #versioning #verify_save #numbers #on_warning }
+}
+
+/// Generates `unknown_fields` and `unknown_fields_mut` for the `#[fast_config(unknown)]` field, if any.
+fn expand_unknown(container: &attrs::Container, fields: &[attrs::Field]) -> proc_macro2::TokenStream {
+    let crate_path = &container.crate_path;
+    let Some(field) = fields.iter().find(|field| field.unknown) else {
+        return proc_macro2::TokenStream::new();
+    };
+    let ident = &field.ident;
+    quote! {
+        fn unknown_fields(&self) -> Option<&#crate_path::UnknownFields> {
+            Some(&self.#ident)
+        }
+        fn unknown_fields_mut(&mut self) -> Option<&mut #crate_path::UnknownFields> {
+            Some(&mut self.#ident)
+        }
+    }
+}
+
+/// Generates `aliases` from the field and struct `deprecated_alias` attributes, along with those of `nested` fields.
+fn expand_aliases(container: &attrs::Container, fields: &[attrs::Field]) -> proc_macro2::TokenStream {
+    let crate_path = &container.crate_path;
+    let alias = |from: &str, to: &str, since: &Option<String>| {
+        let since = match since {
+            Some(since) => quote! { Some(#since.into()) },
+            None => quote! { None },
+        };
+        quote! {
+            aliases.push(#crate_path::Alias { from: #from.into(), to: #to.into(), since: #since });
+        }
+    };
+    let renamed = fields
+        .iter()
+        .filter_map(|field| Some(alias(field.alias.as_ref()?, &field.name, &field.since)))
+        .collect::<Vec<_>>();
+    let moved = container
+        .aliases
+        .iter()
+        .map(|moved| alias(&moved.from, &moved.to, &moved.since));
+    // Like environment variables, only plain nested structs have a fixed key path
+    let nested = fields
+        .iter()
+        .filter(|field| field.nested && !field.skipped && validate::wrapper(&field.ty).is_none())
+        .collect::<Vec<_>>();
+    if renamed.is_empty() && container.aliases.is_empty() && nested.is_empty() {
+        return proc_macro2::TokenStream::new();
+    }
+    let nested = nested.iter().map(|field| {
+        let (ty, path) = (&field.ty, &field.name);
+        quote! {
+            for alias in <#ty as #crate_path::FastConfig>::aliases() {
+                aliases.push(#crate_path::Alias {
+                    from: format!("{}.{}", #path, alias.from),
+                    to: format!("{}.{}", #path, alias.to),
+                    since: alias.since,
+                });
+            }
+        }
+    });
+    quote! {
+        fn aliases() -> Vec<#crate_path::Alias> {
+            let mut aliases = Vec::new();
+            #(#renamed)*
+            #(#moved)*
+            #(#nested)*
+            aliases
+        }
+    }
+}
+
+/// Generates the `ConfigFields` impl, describing each field serde reads along with its `#[fast_config(...)]` options.
+fn expand_descriptors(
+    input: &syn::DeriveInput,
+    container: &attrs::Container,
+    fields: &[attrs::Field],
+) -> proc_macro2::TokenStream {
+    let crate_path = &container.crate_path;
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let fields = fields.iter().filter(|field| !field.skipped && !field.unknown).collect::<Vec<_>>();
+    let optional = |field: &attrs::Field| matches!(validate::wrapper(&field.ty), Some(validate::Wrapper::Option(_)));
+    let or_none = |value: Option<&String>| match value {
+        Some(value) => quote! { Some(#value) },
+        None => quote! { None },
+    };
+    // Nested structs are described by their own impl, wherever they sit inside options, lists and maps
+    let nested_bounds = fields.iter().filter(|field| field.nested).map(|field| {
+        let ty = validate::innermost(&field.ty);
+        quote! { #ty: #crate_path::ConfigFields, }
+    });
+    let descriptors = fields.iter().map(|field| {
+        let name = field.ident.to_string();
+        let key = &field.name;
+        let type_name = type_name(&field.ty);
+        let doc = or_none((!field.docs.is_empty()).then_some(&field.docs));
+        let optional = optional(field);
+        let repeated = repeated(&field.ty);
+        let children = match field.nested {
+            true => {
+                let ty = validate::innermost(&field.ty);
+                quote! { <#ty as #crate_path::ConfigFields>::FIELDS }
+            }
+            false => quote! { &[] },
+        };
+        let env = or_none(field.env.as_ref());
+        let nested = field.nested;
+        let alias = or_none(field.alias.as_ref());
+        let since = or_none(field.since.as_ref());
+        let items = field.items.iter().map(|rule| format!("each({})", validate::source(rule)));
+        let rules = field.rules.iter().map(validate::source).chain(items);
+        quote! {
+            #crate_path::FieldDescriptor {
+                name: #name,
+                key: #key,
+                type_name: #type_name,
+                doc: #doc,
+                optional: #optional,
+                repeated: #repeated,
+                children: #children,
+                attributes: #crate_path::FieldAttributes {
+                    env: #env,
+                    nested: #nested,
+                    deprecated_alias: #alias,
+                    since: #since,
+                    rules: &[#(#rules),*],
+                },
+            }
         }
+    });
+    quote! {
+        impl #impl_generics #crate_path::ConfigFields for #ident #ty_generics where #(#nested_bounds)* {
+            const FIELDS: &'static [#crate_path::FieldDescriptor] = &[#(#descriptors),*];
+        }
+    }
+}
+
+/// Whether the type is a list or a map, including one inside an `Option`.
+fn repeated(ty: &syn::Type) -> bool {
+    match validate::wrapper(ty) {
+        Some(validate::Wrapper::Option(inner)) => repeated(inner),
+        Some(validate::Wrapper::Sequence(_) | validate::Wrapper::Map(_)) => true,
+        None => false,
+    }
+}
+
+/// Writes a type the way it reads in code, as `Option<Vec<String>>` rather than `Option < Vec < String > >`.
+fn type_name(ty: &syn::Type) -> String {
+    let mut name = quote! { #ty }.to_string();
+    let spacing = [(" <", "<"), ("< ", "<"), (" >", ">"), (" ,", ","), (" ;", ";"), (" :: ", "::"), ("& ", "&")];
+    for (spaced, tight) in spacing {
+        name = name.replace(spaced, tight);
     }
-    quote! { fast_config }
+    name
 }
@@ -0,0 +1,484 @@
+use crate::attrs::Container;
+use crate::attrs::Field;
+use crate::attrs::Rule;
+
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use quote::quote;
+use syn::Expr;
+use syn::Type;
+
+/// What a field type wraps, as far as walking into it for nested validation goes.
+pub(crate) enum Wrapper<'a> {
+    Option(&'a Type),
+    /// `Vec`, `VecDeque`, sets and arrays, whose items are reported as `key[i]`.
+    Sequence(&'a Type),
+    /// `HashMap` and `BTreeMap`, whose values are reported as `key.name`.
+    Map(&'a Type),
+}
+
+/// Recognizes the standard containers by the last segment of their path.
+pub(crate) fn wrapper(ty: &Type) -> Option<Wrapper<'_>> {
+    if let Type::Array(array) = ty {
+        return Some(Wrapper::Sequence(&array.elem));
+    }
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    let mut types = args.args.iter().filter_map(|arg| match arg {
+        syn::GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    });
+    match segment.ident.to_string().as_str() {
+        "Option" => types.next().map(Wrapper::Option),
+        "Vec" | "VecDeque" | "HashSet" | "BTreeSet" => types.next().map(Wrapper::Sequence),
+        "HashMap" | "BTreeMap" => types.nth(1).map(Wrapper::Map),
+        _ => None,
+    }
+}
+
+/// The type at the bottom of any standard containers, which is what `nested` fields must implement `FastConfig` for.
+pub(crate) fn innermost(ty: &Type) -> &Type {
+    match wrapper(ty) {
+        Some(Wrapper::Option(inner) | Wrapper::Sequence(inner) | Wrapper::Map(inner)) => innermost(inner),
+        None => ty,
+    }
+}
+
+/// The list or map under any `Option`s, whose items `each(...)` rules are put on.
+pub(crate) fn items(ty: &Type) -> Option<Wrapper<'_>> {
+    match wrapper(ty)? {
+        Wrapper::Option(inner) => items(inner),
+        other => Some(other),
+    }
+}
+
+/// Whether the numbers at the bottom of the type are floats, which `range` bounds are then described as.
+fn float(ty: &Type) -> bool {
+    match innermost(ty) {
+        Type::Path(path) => path.path.is_ident("f32") || path.path.is_ident("f64"),
+        _ => false,
+    }
+}
+
+/// Generates `check`, running the field rules, the checks of `nested` fields
+/// and the hand-written `Validate` impl when `#[fast_config(validate)]` is given, collecting every violation.
+pub(crate) fn expand(container: &Container, fields: &[Field]) -> TokenStream {
+    let crate_path = &container.crate_path;
+    let has_rules = fields.iter().any(|field| field.nested || !field.rules.is_empty() || !field.items.is_empty());
+    if container.validate.is_none() && !has_rules {
+        return TokenStream::new();
+    }
+    let checks = fields.iter().map(|field| {
+        let (ident, name) = (&field.ident, &field.name);
+        let rules = expand_rules(crate_path, field);
+        let items = (!field.items.is_empty())
+            .then(|| expand_items(crate_path, &field.ty, quote! { &self.#ident }, name, &field.items));
+        let nested = field
+            .nested
+            .then(|| expand_nested(crate_path, &field.ty, quote! { &self.#ident }, quote! { #name }));
+        quote! { #rules #items #nested }
+    });
+    let manual = container.validate.is_some().then(|| {
+        quote! {
+            if let Err(nested) = #crate_path::Validate::validate(self) {
+                violations.extend_nested("", nested);
+            }
+        }
+    });
+    let on_save = (container.validate == Some(true)).then(|| {
+        quote! {
+            fn check_on_save() -> bool {
+                true
+            }
+        }
+    });
+    quote! {
+        fn check(&self) -> Result<(), #crate_path::Error> {
+            let mut violations = #crate_path::Violations::new();
+            #(#checks)*
+            #manual
+            violations.into_result().map_err(#crate_path::Error::Validation)
+        }
+        #on_save
+    }
+}
+
+/// Generates `constraints`, describing the field rules along with those of plain `nested` fields.
+pub(crate) fn expand_constraints(crate_path: &TokenStream, fields: &[Field]) -> TokenStream {
+    let own = fields.iter().flat_map(|field| {
+        let items = match items(&field.ty) {
+            Some(Wrapper::Map(_)) => format!("{}.*", field.name),
+            _ => format!("{}[]", field.name),
+        };
+        let rules = field.rules.iter().map(|rule| (field.name.clone(), rule));
+        let rules = rules.chain(field.items.iter().map(move |rule| (items.clone(), rule)));
+        rules.filter_map(|(path, rule)| {
+            let rule = describe(crate_path, rule, float(&field.ty))?;
+            Some(quote! {
+                constraints.push(#crate_path::Constraint { path: #path.into(), rule: #rule });
+            })
+        })
+    });
+    let own = own.collect::<Vec<_>>();
+    let nested = fields
+        .iter()
+        .filter(|field| field.nested && !field.skipped && wrapper(&field.ty).is_none())
+        .collect::<Vec<_>>();
+    if own.is_empty() && nested.is_empty() {
+        return TokenStream::new();
+    }
+    let nested = nested.iter().map(|field| {
+        let (ty, path) = (&field.ty, &field.name);
+        quote! {
+            for constraint in <#ty as #crate_path::FastConfig>::constraints() {
+                constraints.push(#crate_path::Constraint {
+                    path: format!("{}.{}", #path, constraint.path),
+                    rule: constraint.rule,
+                });
+            }
+        }
+    });
+    quote! {
+        fn constraints() -> Vec<#crate_path::Constraint> {
+            let mut constraints = Vec::new();
+            #(#own)*
+            #(#nested)*
+            constraints
+        }
+    }
+}
+
+/// The `Rule` value describing a field rule, if it can be described.
+fn describe(crate_path: &TokenStream, rule: &Rule, float: bool) -> Option<TokenStream> {
+    let rule = match rule {
+        Rule::Range { min, max } => {
+            let bound = |bound: &Option<Expr>| {
+                let Some(expr) = bound else {
+                    return Some(quote! { None });
+                };
+                let bound = match number(expr)? {
+                    Number::Integer(n) if !float => quote! { #crate_path::Bound::Integer(#n) },
+                    Number::Integer(n) => {
+                        let n = n as f64;
+                        quote! { #crate_path::Bound::Float(#n) }
+                    }
+                    Number::Float(n) => quote! { #crate_path::Bound::Float(#n) },
+                };
+                Some(quote! { Some(#bound) })
+            };
+            let (min, max) = (bound(min)?, bound(max)?);
+            quote! { #crate_path::Rule::Range { min: #min, max: #max } }
+        }
+        Rule::Length { min, max } => {
+            let bound = |bound: &Option<Expr>| match bound {
+                Some(expr) => quote! { #crate_path::__private::bound(#expr) },
+                None => quote! { None },
+            };
+            let (min, max) = (bound(min), bound(max));
+            quote! { #crate_path::Rule::Length { min: #min, max: #max } }
+        }
+        Rule::Regex(pattern) => quote! { #crate_path::Rule::Regex(#pattern.into()) },
+        Rule::OneOf(allowed) => {
+            let allowed = allowed.iter().map(json).collect::<Option<Vec<_>>>()?;
+            quote! { #crate_path::Rule::OneOf(vec![#(#allowed.into()),*]) }
+        }
+        Rule::NonEmpty => quote! { #crate_path::Rule::NonEmpty },
+        Rule::PathExists => quote! { #crate_path::Rule::PathExists },
+        Rule::Custom(function) => {
+            let function = function.to_token_stream().to_string().replace(' ', "");
+            quote! { #crate_path::Rule::Custom(#function.into()) }
+        }
+    };
+    Some(rule)
+}
+
+/// A number literal, keeping integers whole.
+enum Number {
+    Integer(i128),
+    Float(f64),
+}
+
+/// The value of a number literal such as `1`, `-0.5` or `1_000u32`.
+fn number(expr: &Expr) -> Option<Number> {
+    match expr {
+        Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(int), .. }) => int.base10_parse().ok().map(Number::Integer),
+        Expr::Lit(syn::ExprLit { lit: syn::Lit::Float(float), .. }) => float.base10_parse().ok().map(Number::Float),
+        Expr::Unary(syn::ExprUnary { op: syn::UnOp::Neg(_), expr, .. }) => match number(expr)? {
+            Number::Integer(n) => Some(Number::Integer(-n)),
+            Number::Float(n) => Some(Number::Float(-n)),
+        },
+        Expr::Group(group) => number(&group.expr),
+        _ => None,
+    }
+}
+
+/// A literal written as JSON, such as `"debug"` or `3`.
+fn json(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(string), .. }) => Some(json_string(&string.value())),
+        Expr::Lit(syn::ExprLit { lit: syn::Lit::Char(c), .. }) => Some(json_string(&c.value().to_string())),
+        Expr::Lit(syn::ExprLit { lit: syn::Lit::Bool(boolean), .. }) => Some(boolean.value.to_string()),
+        other => match number(other)? {
+            Number::Integer(n) => Some(n.to_string()),
+            Number::Float(n) => Some(n.to_string()),
+        },
+    }
+}
+
+fn json_string(string: &str) -> String {
+    let mut out = String::from('"');
+    for c in string.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Runs the check of a `nested` field, walking through options, sequences and maps to reach it.
+fn expand_nested(crate_path: &TokenStream, ty: &Type, access: TokenStream, path: TokenStream) -> TokenStream {
+    match wrapper(ty) {
+        Some(Wrapper::Option(inner)) => {
+            let check = expand_nested(crate_path, inner, quote! { inner }, path);
+            quote! {
+                if let Some(inner) = #access {
+                    #check
+                }
+            }
+        }
+        Some(Wrapper::Sequence(inner)) => {
+            let check = expand_nested(crate_path, inner, quote! { inner }, quote! { &path });
+            quote! {
+                for (i, inner) in (#access).iter().enumerate() {
+                    let path = format!("{}[{}]", #path, i);
+                    #check
+                }
+            }
+        }
+        Some(Wrapper::Map(inner)) => {
+            let check = expand_nested(crate_path, inner, quote! { inner }, quote! { &path });
+            quote! {
+                for (key, inner) in (#access).iter() {
+                    let path = format!("{}.{}", #path, key);
+                    #check
+                }
+            }
+        }
+        None => quote! {
+            match #crate_path::FastConfig::check(#access) {
+                Ok(()) => {}
+                Err(#crate_path::Error::Validation(nested)) => violations.extend_nested(#path, nested),
+                Err(other) => return Err(other),
+            }
+        },
+    }
+}
+
+/// Generates the field's rules, which only look at the value inside when the field is an `Option`.
+fn expand_rules(crate_path: &TokenStream, field: &Field) -> Option<TokenStream> {
+    if field.rules.is_empty() {
+        return None;
+    }
+    let (ident, name) = (&field.ident, &field.name);
+    let path = quote! { #name };
+    let custom = field.rules.iter().filter_map(|rule| match rule {
+        Rule::Custom(function) => Some(expand_custom(function, quote! { &self.#ident }, &path)),
+        _ => None,
+    });
+    let rules = field
+        .rules
+        .iter()
+        .filter_map(|rule| expand_rule(crate_path, &path, rule))
+        .collect::<Vec<_>>();
+    let optional = matches!(wrapper(&field.ty), Some(Wrapper::Option(_)));
+    let value_rules = if rules.is_empty() {
+        TokenStream::new()
+    } else if optional {
+        let missing = field.rules.iter().any(|rule| matches!(rule, Rule::NonEmpty)).then(|| {
+            quote! {
+                else {
+                    violations.push(#name, "must not be empty");
+                }
+            }
+        });
+        quote! {
+            if let Some(value) = &self.#ident {
+                #(#rules)*
+            } #missing
+        }
+    } else {
+        quote! {
+            let value = &self.#ident;
+            #(#rules)*
+        }
+    };
+    Some(quote! {
+        {
+            #value_rules
+            #(#custom)*
+        }
+    })
+}
+
+/// Generates the `each(...)` rules, run on every item of a list or value of a map, reported as `key[i]` or `key.name`.
+fn expand_items(crate_path: &TokenStream, ty: &Type, access: TokenStream, name: &str, rules: &[Rule]) -> TokenStream {
+    let path = quote! { &path };
+    let checks = rules.iter().map(|rule| match rule {
+        Rule::Custom(function) => Some(expand_custom(function, quote! { value }, &path)),
+        rule => expand_rule(crate_path, &path, rule),
+    });
+    match wrapper(ty) {
+        Some(Wrapper::Option(inner)) => {
+            let check = expand_items(crate_path, inner, quote! { items }, name, rules);
+            quote! {
+                if let Some(items) = #access {
+                    #check
+                }
+            }
+        }
+        Some(Wrapper::Sequence(_)) => quote! {
+            for (i, value) in (#access).iter().enumerate() {
+                let path = format!("{}[{}]", #name, i);
+                #(#checks)*
+            }
+        },
+        Some(Wrapper::Map(_)) => quote! {
+            for (key, value) in (#access).iter() {
+                let path = format!("{}.{}", #name, key);
+                #(#checks)*
+            }
+        },
+        None => unreachable!("`each` fields are checked while parsing"),
+    }
+}
+
+/// Calls the function of a `custom` rule with `value`, recording the message it fails with.
+fn expand_custom(function: &syn::Path, value: TokenStream, path: &TokenStream) -> TokenStream {
+    quote! {
+        if let Err(message) = #function(#value) {
+            violations.push(#path, message.to_string());
+        }
+    }
+}
+
+/// Generates a single rule checking `value`, a reference to the (unwrapped) field, recording problems at `path`.
+fn expand_rule(crate_path: &TokenStream, path: &TokenStream, rule: &Rule) -> Option<TokenStream> {
+    let rule = match rule {
+        Rule::Range { min, max } => {
+            let message = bounds_message("must be", min, max);
+            let outside = outside(quote! { value }, min, max);
+            quote! {
+                if #outside {
+                    violations.push(#path, #message);
+                }
+            }
+        }
+        Rule::Length { min, max } => {
+            let message = bounds_message("length must be", min, max);
+            let outside = outside(quote! { &length }, min, max);
+            quote! {
+                let length = #crate_path::__private::Length::length(value);
+                if #outside {
+                    violations.push(#path, #message);
+                }
+            }
+        }
+        Rule::Regex(pattern) => quote! {
+            {
+                static PATTERN: std::sync::OnceLock<#crate_path::__private::regex::Regex> = std::sync::OnceLock::new();
+                let text: &str = value.as_ref();
+                let pattern = PATTERN.get_or_init(|| {
+                    #crate_path::__private::regex::Regex::new(#pattern).expect("the derive compiled the pattern")
+                });
+                if !pattern.is_match(text) {
+                    violations.push(#path, concat!("must match `", #pattern, "`"));
+                }
+            }
+        },
+        Rule::OneOf(allowed) => {
+            let message = format!(
+                "must be one of {}",
+                allowed.iter().map(display).map(|v| format!("`{v}`")).collect::<Vec<_>>().join(", ")
+            );
+            quote! {
+                if !(#(*value == #allowed)||*) {
+                    violations.push(#path, #message);
+                }
+            }
+        }
+        Rule::NonEmpty => quote! {
+            if #crate_path::__private::Length::length(value) == 0 {
+                violations.push(#path, "must not be empty");
+            }
+        },
+        Rule::PathExists => quote! {
+            if !std::path::Path::new(value).exists() {
+                violations.push(#path, format!("path `{}` does not exist", std::path::Path::new(value).display()));
+            }
+        },
+        Rule::Custom(_) => return None,
+    };
+    Some(rule)
+}
+
+/// Whether the value behind the `value` reference falls outside the bounds.
+fn outside(value: TokenStream, min: &Option<Expr>, max: &Option<Expr>) -> TokenStream {
+    match (min, max) {
+        (Some(min), Some(max)) => quote! { !(#min..=#max).contains(#value) },
+        (Some(min), None) => quote! { *#value < #min },
+        (None, Some(max)) => quote! { *#value > #max },
+        (None, None) => unreachable!("bounds are checked while parsing"),
+    }
+}
+
+fn bounds_message(prefix: &str, min: &Option<Expr>, max: &Option<Expr>) -> String {
+    match (min, max) {
+        (Some(min), Some(max)) => format!("{prefix} between {} and {}", display(min), display(max)),
+        (Some(min), None) => format!("{prefix} at least {}", display(min)),
+        (None, Some(max)) => format!("{prefix} at most {}", display(max)),
+        (None, None) => unreachable!("bounds are checked while parsing"),
+    }
+}
+
+/// Shows a rule argument the way it was written, without the quotes around strings.
+fn display(expr: &Expr) -> String {
+    match expr {
+        Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit), .. }) => lit.value(),
+        other => other.to_token_stream().to_string().replace(' ', ""),
+    }
+}
+
+/// Writes a rule back the way it reads in the attribute, such as `range(min = 1, max = 65535)`.
+pub(crate) fn source(rule: &Rule) -> String {
+    let bounds = |name: &str, min: &Option<Expr>, max: &Option<Expr>| {
+        let min = min.iter().map(|min| format!("min = {}", written(min)));
+        let max = max.iter().map(|max| format!("max = {}", written(max)));
+        format!("{name}({})", min.chain(max).collect::<Vec<_>>().join(", "))
+    };
+    match rule {
+        Rule::Range { min, max } => bounds("range", min, max),
+        Rule::Length { min, max } => bounds("length", min, max),
+        Rule::Regex(pattern) => format!("regex = {}", json_string(pattern)),
+        Rule::OneOf(values) => format!("one_of({})", values.iter().map(written).collect::<Vec<_>>().join(", ")),
+        Rule::NonEmpty => "non_empty".to_owned(),
+        Rule::PathExists => "path_exists".to_owned(),
+        Rule::Custom(path) => format!("custom = \"{}\"", path.to_token_stream().to_string().replace(' ', "")),
+    }
+}
+
+/// Shows an expression as it was written, keeping the quotes and spaces of literals.
+fn written(expr: &Expr) -> String {
+    match expr {
+        Expr::Lit(lit) => lit.to_token_stream().to_string(),
+        other => other.to_token_stream().to_string().replace(' ', ""),
+    }
+}